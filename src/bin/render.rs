@@ -16,7 +16,10 @@ use std::sync::mpsc::channel;
 
 use glam::Vec2;
 use lolballs::constants::{BALL_SIZE, HEIGHT, WIDTH};
-use lolballs::physics::{PbfParams, Physics, ShareData, Strategy, PHYS_TIME_STEP};
+use lolballs::physics::{
+    DfsphParams, Domain, Drain, Emitter, Motion, MpmMaterial, MpmParams, Obstacle, PbfParams,
+    Phase, Physics, Sdf, Shape, ShareData, Strategy, PHYS_TIME_STEP,
+};
 
 // ---------------------------------------------------------------------------
 // Render configuration
@@ -27,7 +30,7 @@ const PANEL_H: usize = (PANEL_W as f32 * HEIGHT / WIDTH) as usize; // keep aspec
 const MARGIN: usize = 12;
 const GAP: usize = 12; // gap between the two panels
 const TITLE_H: usize = 26; // room for the panel title above each panel
-const DISC_R: i32 = 2; // particle disc radius in render pixels
+const DISC_R: f32 = 2.0; // disc radius of a BALL_SIZE particle, render pixels
 
 const CANVAS_W: usize = MARGIN * 2 + PANEL_W * 2 + GAP;
 const CANVAS_H: usize = MARGIN + TITLE_H + PANEL_H + MARGIN;
@@ -37,8 +40,9 @@ const FRAMES: usize = 110; // ~4.4 s of animation
 const SUBSTEPS_PER_FRAME: usize = 480 / FPS as usize; // physics substeps between frames
 
 // Palette layout: 0 = background, 1 = white text, 2 = divider, 3..=255 = a hue
-// ring at the app's saturation/lightness (particles are colored by a speed hue,
-// so a hue-indexed palette reproduces them almost exactly with no dithering).
+// ring at the app's saturation/lightness (particles are colored by a speed or
+// phase hue, so a hue-indexed palette reproduces them almost exactly with no
+// dithering).
 const PAL_BG: u8 = 0;
 const PAL_TEXT: u8 = 1;
 const PAL_DIV: u8 = 2;
@@ -137,10 +141,31 @@ impl Canvas {
 /// Draw one simulation state into the given panel origin of the canvas.
 fn draw_panel(canvas: &mut Canvas, ox: usize, oy: usize, share: &ShareData) {
     let scale = PANEL_W as f32 / WIDTH;
-    for (p, &hue) in share.c_pos.iter().zip(&share.c_color) {
+    for (i, p) in share.c_pos.iter().enumerate() {
         let x = ox as f32 + p.x * scale;
         let y = oy as f32 + p.y * scale;
-        canvas.disc(x as i32, y as i32, DISC_R, hue_index(hue));
+        let r = (DISC_R * share.radius(i) / BALL_SIZE).round() as i32;
+        canvas.disc(x as i32, y as i32, r, hue_index(share.hue(i)));
+    }
+}
+
+/// Shade the obstacles / container walls at their current pose (nothing to
+/// do for the plain box, so the classic scenarios pay nothing).
+fn draw_boundaries(canvas: &mut Canvas, ox: usize, oy: usize, physics: &Physics) {
+    if physics.obstacles().is_empty()
+        && physics.bodies().is_empty()
+        && physics.container().is_none()
+    {
+        return;
+    }
+    let inv = WIDTH / PANEL_W as f32;
+    for y in 0..PANEL_H {
+        for x in 0..PANEL_W {
+            let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * inv;
+            if physics.boundary_distance(p) < 0.0 {
+                canvas.set((ox + x) as i32, (oy + y) as i32, PAL_DIV);
+            }
+        }
     }
 }
 
@@ -212,9 +237,28 @@ fn draw_text(canvas: &mut Canvas, x: usize, y: usize, text: &str, s: i32, idx: u
 struct Scenario {
     name: &'static str,
     positions: Vec<Vec2>,
-    /// Gravity as a function of frame index (constant for most; the slosh
-    /// scenario reverses it to drive left/right waves).
+    /// Gravity as a function of frame index (constant for all of them now;
+    /// the slosh tank used to reverse it to fake a moving tank).
     gravity: fn(usize) -> Vec2,
+    /// Places the scenario's obstacles / container on a fresh engine.
+    setup: fn(&mut Physics),
+    /// Fluid phase per particle (empty: all phase 0).
+    phase: Vec<u8>,
+    /// Radius per particle (empty: all BALL_SIZE).
+    radius: Vec<f32>,
+}
+
+impl Scenario {
+    /// The shared state the scenario starts from.
+    fn share(&self) -> ShareData {
+        ShareData {
+            c_pos: self.positions.clone(),
+            c_color: vec![0.0; self.positions.len()],
+            c_phase: self.phase.clone(),
+            c_radius: self.radius.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 /// Regular lattice block filling [x0,x1]×[y0,y1] at the PBF/contact spacing.
@@ -233,6 +277,9 @@ fn block(x0: f32, y0: f32, x1: f32, y1: f32) -> Vec<Vec2> {
     v
 }
 
+/// Rest position of the slosh tank's wave-maker paddle.
+const PADDLE_X: f32 = 100.0;
+
 fn scenarios() -> Vec<Scenario> {
     // Dam break: a tall column against the left wall, released under gravity.
     let dam = Scenario {
@@ -244,6 +291,9 @@ fn scenarios() -> Vec<Scenario> {
             HEIGHT - BALL_SIZE - 4.0,
         ),
         gravity: |_| Vec2::new(0.0, 5.0),
+        setup: |_| {},
+        phase: Vec::new(),
+        radius: Vec::new(),
     };
 
     // Drop + splash: a shallow resting pool plus a compact blob dropped above it.
@@ -263,26 +313,249 @@ fn scenarios() -> Vec<Scenario> {
         name: "drop_splash",
         positions: splash_pos,
         gravity: |_| Vec2::new(0.0, 6.0),
+        setup: |_| {},
+        phase: Vec::new(),
+        radius: Vec::new(),
     };
 
-    // Slosh tank: a layer of liquid on the floor, gravity tilted left then right
-    // on a ~1.3 s period so it sloshes back and forth.
+    // Slosh tank: a layer of liquid on the floor driven by a piston wave
+    // maker — a paddle near the left wall swinging ±90 px on a ~1.3 s period,
+    // so waves run down the tank and reflect off the far wall.
     let slosh = Scenario {
         name: "slosh_tank",
         positions: block(
-            BALL_SIZE + 4.0,
+            PADDLE_X + 12.0,
             0.62 * HEIGHT,
             WIDTH - BALL_SIZE - 4.0,
             HEIGHT - BALL_SIZE - 4.0,
         ),
-        gravity: |frame| {
-            let phase = (frame / 32) % 2; // flip every 32 frames (~1.3 s)
-            let gx = if phase == 0 { 3.5 } else { -3.5 };
-            Vec2::new(gx, 4.0)
+        gravity: |_| Vec2::new(0.0, 4.0),
+        setup: |physics| {
+            physics.add_obstacle(Obstacle {
+                motion: Motion::Oscillate {
+                    amplitude: Vec2::new(90.0, 0.0),
+                    period: 1.3,
+                },
+                ..Obstacle::new(Shape::Capsule {
+                    a: Vec2::new(PADDLE_X, 0.45 * HEIGHT),
+                    b: Vec2::new(PADDLE_X, HEIGHT + 20.0),
+                    radius: 8.0,
+                })
+            });
+        },
+        phase: Vec::new(),
+        radius: Vec::new(),
+    };
+
+    // Fountain: a nozzle arcs a jet across the tank into a shallow pool, and
+    // a drain in the far corner takes water out again, so the level settles
+    // where inflow and outflow balance (with a cap as the backstop).
+    let fountain = Scenario {
+        name: "fountain",
+        positions: block(
+            BALL_SIZE + 4.0,
+            0.85 * HEIGHT,
+            WIDTH - BALL_SIZE - 4.0,
+            HEIGHT - BALL_SIZE - 4.0,
+        ),
+        gravity: |_| Vec2::new(0.0, 4.0),
+        setup: |physics| {
+            physics.set_max_particles(Some(14_000));
+            physics.add_emitter(Emitter {
+                spread: 0.08,
+                ..Emitter::new(
+                    Vec2::new(0.12 * WIDTH, 0.35 * HEIGHT),
+                    Vec2::new(420.0, -180.0),
+                    1500.0,
+                )
+            });
+            physics.add_drain(Drain::new(Sdf::Rect {
+                min: Vec2::new(0.9 * WIDTH, 0.93 * HEIGHT),
+                max: Vec2::new(WIDTH + 50.0, HEIGHT + 50.0),
+            }));
+        },
+        phase: Vec::new(),
+        radius: Vec::new(),
+    };
+
+    // Rayleigh–Taylor: a layer of fluid twice as dense resting on a lighter
+    // one, the interface rippled so the overturn starts as fingers. (Only the
+    // multi-phase PBF/DFSPH tell the two apart; the others just color them.)
+    let rt_pos = block(
+        BALL_SIZE + 4.0,
+        0.70 * HEIGHT,
+        WIDTH - BALL_SIZE - 4.0,
+        HEIGHT - BALL_SIZE - 4.0,
+    );
+    let interface = |x: f32| 0.85 * HEIGHT + 8.0 * (x * std::f32::consts::TAU / 300.0).sin();
+    let rt_phase = rt_pos
+        .iter()
+        .map(|p| u8::from(p.y < interface(p.x)))
+        .collect();
+    let rayleigh_taylor = Scenario {
+        name: "rayleigh_taylor",
+        positions: rt_pos,
+        gravity: |_| Vec2::new(0.0, 5.0),
+        setup: |physics| {
+            physics.set_phases(vec![
+                Phase::default(),
+                Phase {
+                    density: 2.0,
+                    ..Phase::default()
+                },
+            ])
+        },
+        phase: rt_phase,
+        radius: Vec::new(),
+    };
+
+    // Honey: a thin, slow stream poured from high up onto a film on the
+    // floor. With DFSPH's implicit viscosity the stream piles and folds over
+    // itself where it lands (the 2D cousin of coiling) instead of splashing.
+    let honey = Scenario {
+        name: "honey",
+        positions: block(
+            BALL_SIZE + 4.0,
+            HEIGHT - BALL_SIZE - 4.0 - 2.0 * BALL_SIZE,
+            WIDTH - BALL_SIZE - 4.0,
+            HEIGHT - BALL_SIZE - 4.0,
+        ),
+        gravity: |_| Vec2::new(0.0, 5.0),
+        setup: |physics| {
+            physics.set_dfsph_params(DfsphParams {
+                viscosity: 1e5,
+                ..Default::default()
+            });
+            physics.set_max_particles(Some(8_000));
+            physics.add_emitter(Emitter {
+                width: 4.0 * BALL_SIZE,
+                ..Emitter::new(
+                    Vec2::new(0.5 * WIDTH, 0.12 * HEIGHT),
+                    Vec2::new(0.0, 60.0),
+                    500.0,
+                )
+            });
+        },
+        phase: Vec::new(),
+        radius: Vec::new(),
+    };
+
+    // Sand pile: a stream poured onto a thin bed. MLS-MPM sand heaps into a
+    // cone at its angle of repose (about 22° at the default φ = 30°); the
+    // fluids spread and the granular beads roll out.
+    let sand_pile = Scenario {
+        name: "sand_pile",
+        positions: block(
+            BALL_SIZE + 4.0,
+            HEIGHT - BALL_SIZE - 4.0 - 2.0 * BALL_SIZE,
+            WIDTH - BALL_SIZE - 4.0,
+            HEIGHT - BALL_SIZE - 4.0,
+        ),
+        gravity: |_| Vec2::new(0.0, 5.0),
+        setup: |physics| {
+            physics.set_mpm_params(MpmParams {
+                material: MpmMaterial::Sand,
+                ..Default::default()
+            });
+            physics.set_max_particles(Some(6_000));
+            physics.add_emitter(Emitter {
+                width: 4.0 * BALL_SIZE,
+                ..Emitter::new(
+                    Vec2::new(0.5 * WIDTH, 0.45 * HEIGHT),
+                    Vec2::new(0.0, 60.0),
+                    1_200.0,
+                )
+            });
+        },
+        phase: Vec::new(),
+        radius: Vec::new(),
+    };
+
+    // Snowball: a block of MPM snow dropped onto a wedge — it packs where it
+    // hits, then fractures and splits into clumps instead of splashing.
+    let snowball = Scenario {
+        name: "snowball",
+        positions: block(0.42 * WIDTH, 0.08 * HEIGHT, 0.58 * WIDTH, 0.24 * HEIGHT),
+        gravity: |_| Vec2::new(0.0, 5.0),
+        setup: |physics| {
+            physics.set_mpm_params(MpmParams {
+                material: MpmMaterial::Snow,
+                ..Default::default()
+            });
+            physics.add_obstacle(Obstacle::new(Shape::Polygon(vec![
+                Vec2::new(0.5 * WIDTH, 0.62 * HEIGHT),
+                Vec2::new(0.62 * WIDTH, HEIGHT),
+                Vec2::new(0.38 * WIDTH, HEIGHT),
+            ])));
+        },
+        phase: Vec::new(),
+        radius: Vec::new(),
+    };
+
+    // Mixed MPM scene: jelly cubes dropped into a pool of water over a sand
+    // bed, each region its own phase and, under MLS-MPM, its own material.
+    // The cubes land intact and come to rest on the sand; the other solvers
+    // just see two heavier fluids.
+    let floor = HEIGHT - BALL_SIZE - 4.0;
+    let mut mixed_pos = block(
+        BALL_SIZE + 4.0,
+        0.78 * HEIGHT,
+        WIDTH - BALL_SIZE - 4.0,
+        floor,
+    );
+    let mut mixed_phase: Vec<u8> = mixed_pos
+        .iter()
+        .map(|p| if p.y > 0.93 * HEIGHT { 2 } else { 0 })
+        .collect();
+    for x0 in [0.25, 0.5, 0.75] {
+        let cube = block(
+            x0 * WIDTH - 40.0,
+            0.15 * HEIGHT,
+            x0 * WIDTH + 40.0,
+            0.15 * HEIGHT + 80.0,
+        );
+        mixed_phase.extend(std::iter::repeat_n(1, cube.len()));
+        mixed_pos.extend(cube);
+    }
+    let jelly_pool = Scenario {
+        name: "jelly_pool",
+        positions: mixed_pos,
+        gravity: |_| Vec2::new(0.0, 5.0),
+        setup: |physics| {
+            physics.set_phases(vec![
+                Phase::default(),
+                Phase {
+                    density: 1.5,
+                    mpm: Some(MpmParams {
+                        material: MpmMaterial::Jelly,
+                        shear: 4e5,
+                        ..Default::default()
+                    }),
+                },
+                Phase {
+                    density: 2.0,
+                    mpm: Some(MpmParams {
+                        material: MpmMaterial::Sand,
+                        ..Default::default()
+                    }),
+                },
+            ])
         },
+        phase: mixed_phase,
+        radius: Vec::new(),
     };
 
-    vec![dam, splash, slosh]
+    vec![
+        dam,
+        splash,
+        slosh,
+        fountain,
+        rayleigh_taylor,
+        honey,
+        sand_pile,
+        snowball,
+        jelly_pool,
+    ]
 }
 
 // ---------------------------------------------------------------------------
@@ -296,12 +569,9 @@ fn simulate(scenario: &Scenario, strategy: Strategy) -> Vec<Vec<u8>> {
     let mut physics = Physics::new(scenario.positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
     physics.toggle_adaptive_dt(); // deterministic fixed dt
     physics.set_strategy(strategy);
+    (scenario.setup)(&mut physics);
 
-    let mut share = ShareData {
-        c_pos: scenario.positions.clone(),
-        c_color: vec![0.0; n],
-        ..Default::default()
-    };
+    let mut share = scenario.share();
 
     let mut panels = Vec::with_capacity(FRAMES);
     for frame in 0..FRAMES {
@@ -310,6 +580,7 @@ fn simulate(scenario: &Scenario, strategy: Strategy) -> Vec<Vec<u8>> {
             physics.step(PHYS_TIME_STEP, &mut share);
         }
         let mut panel = Canvas::new(PANEL_W, PANEL_H);
+        draw_boundaries(&mut panel, 0, 0, &physics);
         draw_panel(&mut panel, 0, 0, &share);
         panels.push(panel.px);
     }
@@ -716,11 +987,8 @@ fn debug_run() {
                 Physics::new(scenario.positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
             physics.set_adaptive_dt(false); // fixed timestep, exactly like the app
             physics.set_strategy(strategy);
-            let mut share = ShareData {
-                c_pos: scenario.positions.clone(),
-                c_color: vec![0.0; n],
-                ..Default::default()
-            };
+            (scenario.setup)(&mut physics);
+            let mut share = scenario.share();
 
             let mut worst_speed = 0.0f32;
             let mut worst_ratio = 0.0f32;
@@ -1551,6 +1819,69 @@ fn two_column_test() {
     );
 }
 
+/// Contact angle of a sessile drop resting on the floor, from its cap: a
+/// circular cap of base width W and height H meets the floor at
+/// θ = 2·atan(2H/W).
+fn contact_angle(p: &[Vec2], s: f32) -> f32 {
+    let floor = HEIGHT;
+    let top = p.iter().map(|q| q.y).fold(f32::INFINITY, f32::min) - BALL_SIZE;
+    let base: Vec<f32> = p
+        .iter()
+        .filter(|q| q.y > floor - BALL_SIZE - s)
+        .map(|q| q.x)
+        .collect();
+    let lo = base.iter().copied().fold(f32::INFINITY, f32::min);
+    let hi = base.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let w = hi - lo + 2.0 * BALL_SIZE;
+    2.0 * (2.0 * (floor - top) / w).atan().to_degrees()
+}
+
+/// Test 6 — Droplet on a floor (wetting). A drop with surface tension
+/// settles on the floor; the floor's adhesion against the drop's cohesion
+/// sets the contact angle (Young: cos θ = (σ_sv − σ_sl)/σ), so raising it
+/// must lower θ steadily from beading toward wetting. PBF's own cohesion
+/// already beads a drop, so the floor needs a β well above gravity to wet.
+fn droplet_on_floor_test() {
+    println!("\n## Test 6: Droplet on a floor (contact angle vs floor adhesion)");
+    let s = 2.0 * BALL_SIZE;
+    let g_y = 5.0;
+    let mut positions = Vec::new();
+    for gy in 0..12 {
+        for gx in 0..12 {
+            positions.push(Vec2::new(
+                0.5 * WIDTH + (gx as f32 - 5.5) * s,
+                HEIGHT - BALL_SIZE - gy as f32 * s,
+            ));
+        }
+    }
+    let mut angles = Vec::new();
+    for beta in [0.0, 40_000.0, 80_000.0, 140_000.0] {
+        let (mut physics, mut share) = new_pbf(&positions);
+        physics.set_gravity(Vec2::new(0.0, g_y));
+        physics.set_pbf_params(PbfParams {
+            surface_tension: 1500.0,
+            ..Default::default()
+        });
+        physics.set_domain(Domain {
+            adhesion: beta,
+            ..Domain::default()
+        });
+        for _ in 0..4 * 480 {
+            physics.step(PHYS_TIME_STEP, &mut share);
+        }
+        let theta = contact_angle(&share.c_pos, s);
+        println!("  β={beta:>6.0} px/s²: contact angle {theta:5.1}°");
+        angles.push(theta);
+    }
+    let (first, last) = (angles[0], angles[angles.len() - 1]);
+    let falls = angles.windows(2).all(|w| w[1] < w[0]);
+    println!("  → θ falls steadily with adhesion: {}", verdict(falls));
+    println!(
+        "  → beads (>90°) to wets (<60°): {first:.0}° → {last:.0}°  {}",
+        verdict(first > 90.0 && last < 60.0)
+    );
+}
+
 fn validate_water() {
     println!("# Water-sim validation against literature benchmarks (PBF defaults)");
     accel_check();
@@ -1559,6 +1890,7 @@ fn validate_water() {
     sloshing_test();
     dispersion_test();
     two_column_test();
+    droplet_on_floor_test();
 }
 
 // ---------------------------------------------------------------------------
@@ -1638,6 +1970,103 @@ fn mpm_diag() {
     println!("\nframes: renders/diag_<solver>_<substep>.png");
 }
 
+// ---------------------------------------------------------------------------
+// Sand angle of repose: pour MLS-MPM sand onto the floor at a few friction
+// angles and measure the flanks of the heap it builds. A Drucker–Prager cone
+// should heap steeper as φ grows, settling somewhat below φ itself.
+// ---------------------------------------------------------------------------
+
+/// Mean flank angle of a heap resting on the floor: least-squares slope of
+/// the surface profile (highest particle per 6 px column) over the part of
+/// each flank between 20% and 80% of the peak height.
+fn repose_angle(p: &[Vec2]) -> f32 {
+    let s = 2.0 * BALL_SIZE;
+    let cols = (WIDTH / s) as usize + 1;
+    let mut h = vec![0.0f32; cols];
+    for q in p {
+        let c = ((q.x / s) as usize).min(cols - 1);
+        h[c] = h[c].max(HEIGHT - q.y);
+    }
+    let (peak, &top) = h
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .unwrap();
+    let mut angles = Vec::new();
+    for flank in [
+        h[..=peak].to_vec(),
+        h[peak..].iter().rev().copied().collect(),
+    ] {
+        let pts: Vec<(f32, f32)> = flank
+            .iter()
+            .enumerate()
+            .rev()
+            .take_while(|(_, &y)| y > 0.0)
+            .filter(|(_, &y)| y > 0.2 * top && y < 0.8 * top)
+            .map(|(i, &y)| (i as f32 * s, y))
+            .collect();
+        if pts.len() < 2 {
+            continue;
+        }
+        let n = pts.len() as f32;
+        let (mx, my) = pts
+            .iter()
+            .fold((0.0, 0.0), |(a, b), &(x, y)| (a + x / n, b + y / n));
+        let (sxy, sxx) = pts.iter().fold((0.0, 0.0), |(a, b), &(x, y)| {
+            (a + (x - mx) * (y - my), b + (x - mx) * (x - mx))
+        });
+        angles.push((sxy / sxx).abs().atan().to_degrees());
+    }
+    angles.iter().sum::<f32>() / angles.len().max(1) as f32
+}
+
+fn repose_mode() {
+    println!("# MLS-MPM sand: angle of repose of a poured heap");
+    let mut last = 0.0;
+    let mut steepens = true;
+    for phi in [20.0, 30.0, 40.0] {
+        let start = vec![Vec2::new(0.5 * WIDTH, HEIGHT - BALL_SIZE)];
+        let (_tx, rx) = channel();
+        let mut physics = Physics::new(start.clone(), vec![Vec2::ZERO], rx, 2000.0);
+        physics.set_adaptive_dt(false);
+        physics.set_strategy(Strategy::Mlsmpm);
+        physics.set_gravity(Vec2::new(0.0, 5.0));
+        physics.set_mpm_params(MpmParams {
+            material: MpmMaterial::Sand,
+            friction_angle: phi,
+            ..Default::default()
+        });
+        physics.set_max_particles(Some(4_000));
+        physics.add_emitter(Emitter {
+            width: 4.0 * BALL_SIZE,
+            ..Emitter::new(
+                Vec2::new(0.5 * WIDTH, 0.5 * HEIGHT),
+                Vec2::new(0.0, 60.0),
+                800.0,
+            )
+        });
+        let mut share = ShareData {
+            c_pos: start,
+            c_color: vec![0.0],
+            ..Default::default()
+        };
+        for _ in 0..10 * 480 {
+            physics.step(PHYS_TIME_STEP, &mut share);
+        }
+        let angle = repose_angle(&share.c_pos);
+        println!(
+            "  φ={phi:>4.0}°: heap flanks at {angle:5.1}° (residual speed {:.2})",
+            share.perf_stats.mean_speed
+        );
+        steepens &= angle > last;
+        last = angle;
+    }
+    println!(
+        "  → heap steepens with friction angle: {}",
+        verdict(steepens)
+    );
+}
+
 // ---------------------------------------------------------------------------
 // Behaviour comparison: drive each scenario for the clip length and report how
 // each solver *behaves* — peak speed (how lively/splashy), the settled residual
@@ -1658,11 +2087,8 @@ fn behavior_mode() {
                 Physics::new(scenario.positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
             physics.set_adaptive_dt(false);
             physics.set_strategy(strat);
-            let mut share = ShareData {
-                c_pos: scenario.positions.clone(),
-                c_color: vec![0.0; n],
-                ..Default::default()
-            };
+            (scenario.setup)(&mut physics);
+            let mut share = scenario.share();
             let mut peak = 0.0f32;
             let mut rho_sum = 0.0f64;
             let mut rho_cnt = 0usize;
@@ -1717,12 +2143,9 @@ fn perf_mode() {
                 Physics::new(scenario.positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
             physics.set_adaptive_dt(false);
             physics.set_strategy(strat);
+            (scenario.setup)(&mut physics);
             physics.set_gravity((scenario.gravity)(0));
-            let mut share = ShareData {
-                c_pos: scenario.positions.clone(),
-                c_color: vec![0.0; n],
-                ..Default::default()
-            };
+            let mut share = scenario.share();
             for _ in 0..WARMUP {
                 physics.step(PHYS_TIME_STEP, &mut share);
             }
@@ -1818,6 +2241,10 @@ fn main() {
         mpm_diag();
         return;
     }
+    if std::env::args().any(|a| a == "--repose") {
+        repose_mode();
+        return;
+    }
     if std::env::args().any(|a| a == "--behavior") {
         behavior_mode();
         return;
@@ -15,6 +15,9 @@ use ggez::{event, graphics, Context, ContextBuilder, GameResult};
 use physics::{EventToPthread, Physics, ShareData, Strategy, PHYS_TIME_STEP};
 
 const BACKGROUND_COLOR: Color = Color::new(0., 0., 0., 0.0);
+/// Radius the particle disc mesh extends past the ball itself, for the blur
+/// shader to smear into a liquid surface.
+const GLOW: f32 = 10.0;
 
 /// Parse `--sim granular|pbf` (default granular) from the command line.
 fn parse_strategy() -> Strategy {
@@ -168,7 +171,7 @@ impl MainState {
             ctx,
             DrawMode::fill(),
             Vec2::default(),
-            BALL_SIZE + 10.0,
+            BALL_SIZE + GLOW,
             0.0001,
             Color::WHITE,
         )?;
@@ -201,18 +204,23 @@ impl event::EventHandler<ggez::GameError> for MainState {
                 return Ok(());
             };
 
+            // The circle mesh is a BALL_SIZE ball plus its glow; scaling it
+            // by the particle's radius keeps the glow margin.
+            let param = |i: usize, pos: Vec2| {
+                let scale = (share_data.radius(i) + GLOW) / (BALL_SIZE + GLOW);
+                DrawParam::new()
+                    .dest(pos)
+                    .scale(Vec2::splat(scale))
+                    .color(hsl_to_rgb(share_data.hue(i), 0.75, 0.5))
+            };
             if share_data.c_pos.len() == self.nb_obj {
-                for ((i, pos), h) in share_data.c_pos.iter().enumerate().zip(&share_data.c_color) {
-                    self.circles.update(
-                        i as _,
-                        DrawParam::new().dest(*pos).color(hsl_to_rgb(*h, 0.75, 0.5)),
-                    );
+                for (i, pos) in share_data.c_pos.iter().enumerate() {
+                    self.circles.update(i as _, param(i, *pos));
                 }
             } else {
                 self.circles.clear();
-                for (pos, h) in share_data.c_pos.iter().zip(&share_data.c_color) {
-                    self.circles
-                        .push(DrawParam::new().dest(*pos).color(hsl_to_rgb(*h, 0.75, 0.5)));
+                for (i, pos) in share_data.c_pos.iter().enumerate() {
+                    self.circles.push(param(i, *pos));
                 }
             }
 
@@ -0,0 +1,992 @@
+//! Solid boundaries inside the tank, shared by every `FluidSolver` strategy.
+//!
+//! The box walls enclose the [`Domain`] (`clamp_wall` /
+//! `resolve_wall_collision` and the MPM grid BCs); this module carries it to
+//! the solvers and adds *obstacles* — circles, capsules,
+//! thin segments and solid polygons placed anywhere in the box (weirs,
+//! pillars, funnels). Every shape reduces to one query, a signed distance to
+//! its surface plus the outward normal, so a solver only ever needs two
+//! operations:
+//!
+//!   - **project** — push a particle of radius r out along the normal until it
+//!     sits at distance r from the surface (position-based solvers call this
+//!     inside their iterations, exactly like `clamp_wall`);
+//!   - **respond** — for a particle touching a surface, reflect the inward
+//!     normal velocity by the obstacle's restitution and shave the tangential
+//!     velocity by Coulomb friction, so particles slide or bounce instead of
+//!     sticking.
+//!
+//! Obstacles are few and large, so each keeps a bounding box and the queries
+//! reject on it before evaluating the distance function.
+//!
+//! The tank itself can also be reshaped: a [`Container`] is a signed distance
+//! field ([`Sdf`]) describing the space the fluid may occupy (a round tank, a
+//! sloped or stepped floor). It goes through the same two operations with the
+//! roles flipped — distance to the wall is −φ, the normal is −∇φ — so every
+//! strategy that handles obstacles handles containers too. The box walls
+//! still apply around it.
+//!
+//! Obstacles and the container can also be *kinematic*: a [`Motion`]
+//! prescribes their pose over time (a piston wave maker, a rotating drum, a
+//! lid that moves). The coordinator poses them at the current simulation time
+//! before each substep, and every contact response works on the particle
+//! velocity relative to the moving surface, so the boundary hands its
+//! momentum to the fluid instead of just shoving particles out of the way.
+//!
+//! Rigid bodies ([`RigidBody`], see `rigid.rs`) are colliders too, posed from
+//! their own state; the contact calls book the momentum they give the fluid
+//! against the body they touched, which is what makes the coupling two-way.
+
+use glam::Vec2;
+
+use super::rigid::{RigidBody, FLUID_PARTICLE_MASS};
+use super::{Domain, Sdf, PHYS_TIME_STEP};
+
+/// An obstacle's geometry, in world coordinates.
+#[derive(Clone, Debug)]
+pub enum Shape {
+    /// Solid disc.
+    Circle { center: Vec2, radius: f32 },
+    /// Solid stadium: every point within `radius` of the segment a–b.
+    Capsule { a: Vec2, b: Vec2, radius: f32 },
+    /// Zero-thickness two-sided wall (a capsule of radius 0). Particles keep
+    /// their radius off either face; a particle moving more than its radius
+    /// per substep can tunnel through, so prefer a capsule for fast flows.
+    Segment { a: Vec2, b: Vec2 },
+    /// Solid simple polygon (any winding, convex or not).
+    Polygon(Vec<Vec2>),
+}
+
+impl Shape {
+    /// Signed distance from `p` to the surface (negative inside the solid)
+    /// and the unit normal pointing out of the solid at the closest point.
+    pub fn distance(&self, p: Vec2) -> (f32, Vec2) {
+        match self {
+            Shape::Circle { center, radius } => {
+                let d = p - *center;
+                let len = d.length();
+                let n = if len > 1e-6 { d / len } else { Vec2::Y };
+                (len - radius, n)
+            }
+            Shape::Capsule { a, b, radius } => {
+                let (dist, n) = segment_distance(p, *a, *b);
+                (dist - radius, n)
+            }
+            Shape::Segment { a, b } => segment_distance(p, *a, *b),
+            Shape::Polygon(verts) => polygon_distance(p, verts),
+        }
+    }
+
+    /// Axis-aligned bounds of the solid, as (min, max).
+    pub fn aabb(&self) -> (Vec2, Vec2) {
+        match self {
+            Shape::Circle { center, radius } => (
+                *center - Vec2::splat(*radius),
+                *center + Vec2::splat(*radius),
+            ),
+            Shape::Capsule { a, b, radius } => (
+                a.min(*b) - Vec2::splat(*radius),
+                a.max(*b) + Vec2::splat(*radius),
+            ),
+            Shape::Segment { a, b } => (a.min(*b), a.max(*b)),
+            Shape::Polygon(verts) => verts.iter().fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(lo, hi), v| (lo.min(*v), hi.max(*v)),
+            ),
+        }
+    }
+}
+
+/// Unsigned distance to the segment a–b and the direction from its closest
+/// point to `p`. A point exactly on the segment gets the segment's left
+/// normal, so the projection still has an axis.
+#[inline]
+fn segment_distance(p: Vec2, a: Vec2, b: Vec2) -> (f32, Vec2) {
+    let ab = b - a;
+    let len2 = ab.length_squared();
+    let t = if len2 > 0.0 {
+        ((p - a).dot(ab) / len2).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let d = p - (a + ab * t);
+    let len = d.length();
+    if len > 1e-6 {
+        (len, d / len)
+    } else {
+        (0.0, ab.perp().try_normalize().unwrap_or(Vec2::Y))
+    }
+}
+
+/// Signed distance to a simple polygon: the closest edge gives the magnitude,
+/// a crossing-number test gives the sign (Quílez's 2D `sdPolygon`).
+fn polygon_distance(p: Vec2, verts: &[Vec2]) -> (f32, Vec2) {
+    if verts.len() < 2 {
+        return (f32::MAX, Vec2::Y);
+    }
+    let mut best = (f32::MAX, Vec2::Y);
+    let mut inside = false;
+    let mut j = verts.len() - 1;
+    for i in 0..verts.len() {
+        let (a, b) = (verts[j], verts[i]);
+        let (dist, n) = segment_distance(p, a, b);
+        if dist < best.0 {
+            best = (dist, n);
+        }
+        if (b.y > p.y) != (a.y > p.y) && p.x < (a.x - b.x) * (p.y - b.y) / (a.y - b.y) + b.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    if inside {
+        // From inside, the closest-point direction points inward; flip both
+        // so the distance is negative and the normal still points out.
+        (-best.0, -best.1)
+    } else {
+        best
+    }
+}
+
+/// How a boundary moves. A pose places the shape as it was defined: rotated
+/// by `angle` about `pivot`, then translated by `offset`.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Motion {
+    /// Fixed in place.
+    #[default]
+    Static,
+    /// Back-and-forth translation, offset = amplitude·sin(2π·t/period) — a
+    /// piston wave maker or a shaken tank.
+    Oscillate { amplitude: Vec2, period: f32 },
+    /// Constant spin at `omega` rad/s about `pivot` — a rotating drum.
+    Rotate { pivot: Vec2, omega: f32 },
+    /// Any prescribed pose over time (a lid that opens, a scripted paddle).
+    /// Its velocity is taken by central differences of the pose.
+    Scripted(fn(f32) -> Pose),
+}
+
+/// A rigid placement of a boundary shape (see [`Motion`]).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Pose {
+    pub pivot: Vec2,
+    pub offset: Vec2,
+    pub angle: f32,
+}
+
+/// A boundary's pose and rigid-body velocity at the current time, with the
+/// rotation kept as (cos, sin).
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    center: Vec2, // pivot + offset: the world point the shape rotates about
+    pivot: Vec2,
+    rot: Vec2,
+    vel: Vec2,
+    omega: f32,
+}
+
+impl Frame {
+    const STILL: Frame = Frame {
+        center: Vec2::ZERO,
+        pivot: Vec2::ZERO,
+        rot: Vec2::X,
+        vel: Vec2::ZERO,
+        omega: 0.0,
+    };
+
+    fn at(motion: &Motion, t: f32) -> Frame {
+        match *motion {
+            Motion::Static => Frame::STILL,
+            Motion::Oscillate { amplitude, period } => {
+                let w = std::f32::consts::TAU / period;
+                Frame {
+                    center: amplitude * (w * t).sin(),
+                    vel: amplitude * (w * (w * t).cos()),
+                    ..Frame::STILL
+                }
+            }
+            Motion::Rotate { pivot, omega } => Frame {
+                center: pivot,
+                pivot,
+                rot: Vec2::from_angle(omega * t),
+                omega,
+                ..Frame::STILL
+            },
+            Motion::Scripted(pose) => {
+                const H: f32 = 1e-3;
+                let (p, a, b) = (pose(t), pose(t - H), pose(t + H));
+                Frame {
+                    center: p.pivot + p.offset,
+                    pivot: p.pivot,
+                    rot: Vec2::from_angle(p.angle),
+                    vel: (b.offset - a.offset) / (2.0 * H),
+                    omega: (b.angle - a.angle) / (2.0 * H),
+                }
+            }
+        }
+    }
+
+    /// World point → the shape's own (as-defined) coordinates.
+    #[inline]
+    fn local(&self, p: Vec2) -> Vec2 {
+        Vec2::new(self.rot.x, -self.rot.y).rotate(p - self.center) + self.pivot
+    }
+
+    /// Local direction → world.
+    #[inline]
+    fn world_dir(&self, n: Vec2) -> Vec2 {
+        self.rot.rotate(n)
+    }
+
+    /// Velocity of the boundary material at world point `x`.
+    #[inline]
+    fn velocity_at(&self, x: Vec2) -> Vec2 {
+        self.vel + (x - self.center).perp() * self.omega
+    }
+
+    /// A rigid body's current pose and velocity (its shape is defined
+    /// around the center of mass, so the pivot is the origin).
+    fn of_body(b: &RigidBody) -> Frame {
+        Frame {
+            center: b.position,
+            pivot: Vec2::ZERO,
+            rot: Vec2::from_angle(b.angle),
+            vel: b.velocity,
+            omega: b.omega,
+        }
+    }
+
+    /// World bounds of local bounds (lo, hi) under this pose.
+    fn bounds(&self, (lo, hi): (Vec2, Vec2)) -> (Vec2, Vec2) {
+        [lo, Vec2::new(hi.x, lo.y), hi, Vec2::new(lo.x, hi.y)]
+            .iter()
+            .map(|&c| self.world_dir(c - self.pivot) + self.center)
+            .fold(
+                (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+                |(lo, hi), c| (lo.min(c), hi.max(c)),
+            )
+    }
+}
+
+/// A collider: a shape plus the contact material particles see, and how it
+/// moves (static by default).
+#[derive(Clone, Debug)]
+pub struct Obstacle {
+    pub shape: Shape,
+    /// Fraction of the inward normal speed returned as a bounce (0 = the
+    /// particle stops against the surface, 1 = elastic).
+    pub restitution: f32,
+    /// Coulomb friction coefficient: the tangential speed lost per contact is
+    /// at most `friction` × the normal speed change (0 = free slip).
+    pub friction: f32,
+    /// Akinci adhesion strength β, px/s² (0 = none): how hard the SPH
+    /// strategies pull fluid within a kernel radius toward the surface.
+    /// Against the fluid's own surface tension this sets the contact angle —
+    /// a drop beads up on a surface without it and spreads out as β grows.
+    pub adhesion: f32,
+    pub motion: Motion,
+}
+
+impl Obstacle {
+    /// A static, free-slip, non-bouncing, non-adhesive obstacle — the same
+    /// response the box walls give the SPH-family models.
+    pub fn new(shape: Shape) -> Self {
+        Self {
+            shape,
+            restitution: 0.0,
+            friction: 0.0,
+            adhesion: 0.0,
+            motion: Motion::Static,
+        }
+    }
+}
+
+/// A container: the fluid is kept where `sdf` is negative.
+#[derive(Clone, Debug)]
+pub struct Container {
+    pub sdf: Sdf,
+    /// Same meaning as on [`Obstacle`].
+    pub restitution: f32,
+    pub friction: f32,
+    pub adhesion: f32,
+    pub motion: Motion,
+}
+
+impl Container {
+    /// A static, frictionless, non-bouncing, non-adhesive container.
+    pub fn new(sdf: Sdf) -> Self {
+        Self {
+            sdf,
+            restitution: 0.0,
+            friction: 0.0,
+            adhesion: 0.0,
+            motion: Motion::Static,
+        }
+    }
+}
+
+/// The domain, the obstacle set, the rigid bodies and the optional container
+/// the coordinator hands every solver each substep, posed at the current
+/// simulation time.
+///
+/// Obstacles and bodies are both *colliders*, indexed obstacles first:
+/// collider `k` is obstacle `k` for `k < obstacles().len()`, body
+/// `k − obstacles().len()` after that.
+pub struct Boundaries {
+    domain: Domain,
+    obstacles: Vec<Obstacle>,
+    bodies: Vec<RigidBody>,
+    bounds: Vec<(Vec2, Vec2)>, // world bounds per collider at the current pose
+    frames: Vec<Frame>,
+    // Linear and angular impulse the fluid gave each body this substep.
+    impulses: Vec<(Vec2, f32)>,
+    container: Option<Container>,
+    container_frame: Frame,
+    time: f32,
+    dt: f32,
+    // Bumped on every change to the domain or the container (including it
+    // moving), so solvers that cache per-cell data derived from them know to
+    // rebuild.
+    generation: u64,
+}
+
+impl Default for Boundaries {
+    fn default() -> Self {
+        Self {
+            domain: Domain::default(),
+            obstacles: Vec::new(),
+            bodies: Vec::new(),
+            bounds: Vec::new(),
+            frames: Vec::new(),
+            impulses: Vec::new(),
+            container: None,
+            container_frame: Frame::STILL,
+            time: 0.0,
+            dt: PHYS_TIME_STEP,
+            generation: 0,
+        }
+    }
+}
+
+/// A particle within this fraction of its radius beyond contact still counts
+/// as touching for the velocity response: position projection leaves it at
+/// exactly distance r, which rounding can put a hair outside.
+const CONTACT_SLOP: f32 = 0.01;
+
+impl Boundaries {
+    pub fn is_empty(&self) -> bool {
+        self.obstacles.is_empty() && self.bodies.is_empty() && self.container.is_none()
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
+    }
+
+    pub fn bodies(&self) -> &[RigidBody] {
+        &self.bodies
+    }
+
+    /// Add an obstacle; returns its index.
+    pub fn add(&mut self, obstacle: Obstacle) -> usize {
+        let k = self.obstacles.len();
+        let frame = Frame::at(&obstacle.motion, self.time);
+        self.bounds.insert(k, frame.bounds(obstacle.shape.aabb()));
+        self.frames.insert(k, frame);
+        self.obstacles.push(obstacle);
+        k
+    }
+
+    /// Add a rigid body; returns its index in [`Boundaries::bodies`].
+    pub fn add_body(&mut self, body: RigidBody) -> usize {
+        let frame = Frame::of_body(&body);
+        self.bounds.push(frame.bounds(body.shape.aabb()));
+        self.frames.push(frame);
+        self.impulses.push((Vec2::ZERO, 0.0));
+        self.bodies.push(body);
+        self.bodies.len() - 1
+    }
+
+    /// Remove every obstacle (the bodies and the container stay).
+    pub fn clear(&mut self) {
+        let n = self.obstacles.len();
+        self.obstacles.clear();
+        self.bounds.drain(..n);
+        self.frames.drain(..n);
+    }
+
+    /// Remove every rigid body.
+    pub fn clear_bodies(&mut self) {
+        let n = self.obstacles.len();
+        self.bodies.clear();
+        self.impulses.clear();
+        self.bounds.truncate(n);
+        self.frames.truncate(n);
+    }
+
+    pub fn domain(&self) -> &Domain {
+        &self.domain
+    }
+
+    pub(crate) fn set_domain(&mut self, domain: Domain) {
+        self.domain = domain;
+        self.generation += 1;
+    }
+
+    pub fn container(&self) -> Option<&Container> {
+        self.container.as_ref()
+    }
+
+    /// Replace the container (`None` = just the box).
+    pub fn set_container(&mut self, container: Option<Container>) {
+        self.container_frame = container
+            .as_ref()
+            .map_or(Frame::STILL, |c| Frame::at(&c.motion, self.time));
+        self.container = container;
+        self.generation += 1;
+    }
+
+    /// Start a substep ending at time `t` (seconds of simulated time) that
+    /// is `dt` long: pose every kinematic boundary at `t`, and advance the
+    /// bodies under `accel` (gravity) to their predicted pose, which the
+    /// fluid then sees. Clears the impulse accumulators.
+    pub(crate) fn advance(&mut self, t: f32, dt: f32, accel: Vec2) {
+        self.time = t;
+        self.dt = dt;
+        for (k, o) in self.obstacles.iter().enumerate() {
+            if !matches!(o.motion, Motion::Static) {
+                self.frames[k] = Frame::at(&o.motion, t);
+                self.bounds[k] = self.frames[k].bounds(o.shape.aabb());
+            }
+        }
+        if let Some(c) = &self.container {
+            if !matches!(c.motion, Motion::Static) {
+                self.container_frame = Frame::at(&c.motion, t);
+                self.generation += 1;
+            }
+        }
+        for b in &mut self.bodies {
+            b.velocity += accel * dt;
+            b.position += b.velocity * dt;
+            b.angle += b.omega * dt;
+        }
+        self.impulses.fill((Vec2::ZERO, 0.0));
+        self.pose_bodies();
+    }
+
+    /// Finish a substep: hand each body the impulse the fluid gave it, then
+    /// resolve its contacts with the box walls, the container and the
+    /// obstacles, and wrap it across the periodic axes.
+    pub(crate) fn finish_bodies(&mut self) {
+        let n = self.obstacles.len();
+        for (b, &(j, l)) in self.bodies.iter_mut().zip(&self.impulses) {
+            b.velocity += j / b.mass;
+            b.omega += l / b.inertia;
+        }
+        let domain = self.domain;
+        let (left, right) = domain.walls_x();
+        let (top, bottom) = domain.walls_y();
+        for i in 0..self.bodies.len() {
+            let mut b = self.bodies[i].clone();
+            for (local, rad) in b.contact_points() {
+                let q = b.world_point(local);
+                for (d, nrm, walled) in [
+                    (q.x, Vec2::X, left),
+                    (domain.width - q.x, Vec2::NEG_X, right),
+                    (q.y, Vec2::Y, top),
+                    (domain.height - q.y, Vec2::NEG_Y, bottom),
+                ] {
+                    if walled {
+                        b.resolve_contact(q, rad, d, nrm, Vec2::ZERO);
+                    }
+                }
+                let q = b.world_point(local);
+                if let Some((d, nrm, vb, _)) = self.container_probe(q, rad) {
+                    b.resolve_contact(q, rad, d, nrm, vb);
+                }
+                for k in 0..n {
+                    let q = b.world_point(local);
+                    if let Some((d, nrm, vb)) = self.probe(k, q, rad) {
+                        b.resolve_contact(q, rad, d, nrm, vb);
+                    }
+                }
+            }
+            // A body is one object (no periodic images): its center wraps.
+            b.position = domain.wrap(b.position);
+            self.bodies[i] = b;
+        }
+        self.pose_bodies();
+    }
+
+    fn pose_bodies(&mut self) {
+        let n = self.obstacles.len();
+        for (i, b) in self.bodies.iter().enumerate() {
+            self.frames[n + i] = Frame::of_body(b);
+            self.bounds[n + i] = self.frames[n + i].bounds(b.shape.aabb());
+        }
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Number of colliders (obstacles, then bodies).
+    pub(crate) fn colliders(&self) -> usize {
+        self.obstacles.len() + self.bodies.len()
+    }
+
+    /// Shape, restitution and friction of collider `k`.
+    #[inline]
+    fn collider(&self, k: usize) -> (&Shape, f32, f32) {
+        match self.obstacles.get(k) {
+            Some(o) => (&o.shape, o.restitution, o.friction),
+            None => {
+                let b = &self.bodies[k - self.obstacles.len()];
+                (&b.shape, b.restitution, b.friction)
+            }
+        }
+    }
+
+    /// Adhesion of collider `k`.
+    #[inline]
+    fn adhesion_of(&self, k: usize) -> f32 {
+        match self.obstacles.get(k) {
+            Some(o) => o.adhesion,
+            None => self.bodies[k - self.obstacles.len()].adhesion,
+        }
+    }
+
+    /// Book the momentum change `dp` a contact with collider `k` gave the
+    /// fluid at `p`: if the collider is a body, it takes the opposite.
+    #[inline]
+    fn record(&mut self, k: usize, p: Vec2, dp: Vec2) {
+        if let Some(i) = k.checked_sub(self.obstacles.len()) {
+            let center = self.frames[k].center;
+            let (j, l) = &mut self.impulses[i];
+            *j -= dp;
+            *l -= (p - center).perp_dot(dp);
+        }
+    }
+
+    /// Signed distance from `p` to the nearest solid surface — an obstacle,
+    /// a body or the container wall — at the current pose (negative inside
+    /// the solid; `f32::MAX` when there is none). For drawing and
+    /// diagnostics.
+    pub fn distance(&self, p: Vec2) -> f32 {
+        let mut best = f32::MAX;
+        for k in 0..self.colliders() {
+            best = best.min(self.collider(k).0.distance(self.frames[k].local(p)).0);
+        }
+        if let Some(c) = &self.container {
+            best = best.min(-c.sdf.eval(self.container_frame.local(p)).0);
+        }
+        best
+    }
+
+    /// Whether a point is within `reach` of the container wall (or outside
+    /// it). Lets grid-based solvers precompute which cells need the
+    /// container test; always false without a container.
+    pub(crate) fn near_container(&self, p: Vec2, reach: f32) -> bool {
+        self.container_probe(p, reach).is_some()
+    }
+
+    /// Bounds of collider `k` grown by `margin`, as (min, max). Lets
+    /// grid-based solvers visit only the cells near each collider.
+    pub(crate) fn bounds_of(&self, k: usize, margin: f32) -> (Vec2, Vec2) {
+        let (lo, hi) = self.bounds[k];
+        (lo - Vec2::splat(margin), hi + Vec2::splat(margin))
+    }
+
+    /// Distance, normal and surface velocity of collider `k` at `p`, or
+    /// `None` when `p` is not within `reach` of its surface (the bounds
+    /// reject most misses first).
+    #[inline]
+    fn probe(&self, k: usize, p: Vec2, reach: f32) -> Option<(f32, Vec2, Vec2)> {
+        let (lo, hi) = self.bounds[k];
+        if p.x < lo.x - reach || p.y < lo.y - reach || p.x > hi.x + reach || p.y > hi.y + reach {
+            return None;
+        }
+        let f = &self.frames[k];
+        let (d, n) = self.collider(k).0.distance(f.local(p));
+        (d < reach).then(|| (d, f.world_dir(n), f.velocity_at(p)))
+    }
+
+    /// `probe` for the container: distance to its wall (−φ), the normal
+    /// pointing back into the fluid region (−∇φ) and the wall velocity.
+    #[inline]
+    fn container_probe(&self, p: Vec2, reach: f32) -> Option<(f32, Vec2, Vec2, &Container)> {
+        let c = self.container.as_ref()?;
+        let f = &self.container_frame;
+        let (phi, grad) = c.sdf.eval(f.local(p));
+        (-phi < reach).then(|| (-phi, -f.world_dir(grad), f.velocity_at(p), c))
+    }
+
+    /// Position pass: push a particle of radius `r` out of every collider it
+    /// penetrates. Returns whether it moved. For position-based solvers,
+    /// where the push becomes velocity (Δx/Δt), so it is booked against the
+    /// bodies as momentum.
+    #[inline]
+    pub(crate) fn project(&mut self, p: &mut Vec2, r: f32) -> bool {
+        let mut moved = false;
+        for k in 0..self.colliders() {
+            if let Some((d, n, _)) = self.probe(k, *p, r) {
+                let dx = n * (r - d);
+                *p += dx;
+                self.record(k, *p, dx * (FLUID_PARTICLE_MASS / self.dt));
+                moved = true;
+            }
+        }
+        if let Some((d, n, _, _)) = self.container_probe(*p, r) {
+            *p += n * (r - d);
+            moved = true;
+        }
+        moved
+    }
+
+    /// `project` without the momentum bookkeeping, for a solver whose
+    /// position clamp does not feed back into its velocities (MPM's G2P,
+    /// whose coupling runs through the grid instead).
+    #[inline]
+    pub(crate) fn clamp(&self, p: &mut Vec2, r: f32) {
+        for k in 0..self.colliders() {
+            if let Some((d, n, _)) = self.probe(k, *p, r) {
+                *p += n * (r - d);
+            }
+        }
+        if let Some((d, n, _, _)) = self.container_probe(*p, r) {
+            *p += n * (r - d);
+        }
+    }
+
+    /// Velocity pass for a particle at `p` touching a collider: `v_in` is
+    /// the velocity it approached with, `v` the velocity to correct (the same
+    /// vector for velocity-based solvers; for position-based ones `v` is the
+    /// velocity derived after projection, which has already lost the normal
+    /// component the restitution needs). Velocities are in world units per
+    /// second; against a moving boundary the response acts on the velocity
+    /// relative to the surface, which is how it passes on its momentum.
+    #[inline]
+    pub(crate) fn respond(&mut self, p: Vec2, v: &mut Vec2, v_in: Vec2, r: f32) {
+        for k in 0..self.colliders() {
+            if let Some((_, n, vb)) = self.probe(k, p, r * (1.0 + CONTACT_SLOP)) {
+                let (_, restitution, friction) = self.collider(k);
+                let out = contact_response(*v, v_in, n, vb, restitution, friction);
+                self.record(k, p, (out - *v) * FLUID_PARTICLE_MASS);
+                *v = out;
+            }
+        }
+        if let Some((_, n, vb, c)) = self.container_probe(p, r * (1.0 + CONTACT_SLOP)) {
+            *v = contact_response(*v, v_in, n, vb, c.restitution, c.friction);
+        }
+    }
+
+    /// Project and respond in one pass, for velocity-based solvers.
+    #[inline]
+    pub(crate) fn collide(&mut self, p: &mut Vec2, v: &mut Vec2, r: f32) {
+        for k in 0..self.colliders() {
+            self.collide_with(k, p, v, r);
+        }
+        self.collide_container(p, v, r);
+    }
+
+    /// `collide` against collider `k` only. Returns whether it touched, so
+    /// position-based callers can skip writing back untouched particles.
+    #[inline]
+    pub(crate) fn collide_with(&mut self, k: usize, p: &mut Vec2, v: &mut Vec2, r: f32) -> bool {
+        let Some((d, n, vb)) = self.probe(k, *p, r * (1.0 + CONTACT_SLOP)) else {
+            return false;
+        };
+        if d < r {
+            *p += n * (r - d);
+        }
+        let (_, restitution, friction) = self.collider(k);
+        let out = contact_response(*v, *v, n, vb, restitution, friction);
+        self.record(k, *p, (out - *v) * FLUID_PARTICLE_MASS);
+        *v = out;
+        true
+    }
+
+    /// `collide` against the container only.
+    #[inline]
+    pub(crate) fn collide_container(&self, p: &mut Vec2, v: &mut Vec2, r: f32) -> bool {
+        let Some((d, n, vb, c)) = self.container_probe(*p, r * (1.0 + CONTACT_SLOP)) else {
+            return false;
+        };
+        if d < r {
+            *p += n * (r - d);
+        }
+        *v = contact_response(*v, *v, n, vb, c.restitution, c.friction);
+        true
+    }
+
+    /// Whether any wall, collider or the container is adhesive, so solvers
+    /// can skip [`Boundaries::adhesion`] entirely when none is.
+    pub(crate) fn adhesive(&self) -> bool {
+        let (left, right) = self.domain.walls_x();
+        let (top, bottom) = self.domain.walls_y();
+        (self.domain.adhesion != 0.0 && (left || right || top || bottom))
+            || (0..self.colliders()).any(|k| self.adhesion_of(k) != 0.0)
+            || self.container.as_ref().is_some_and(|c| c.adhesion != 0.0)
+    }
+
+    /// Adhesion acceleration of a fluid particle at `p`: every adhesive
+    /// surface within `reach` pulls it in along its normal by its β ×
+    /// `profile(d)` at distance `d`. The pull on a body is booked against it
+    /// as the momentum of a particle over this substep.
+    pub(crate) fn adhesion(&mut self, p: Vec2, reach: f32, profile: impl Fn(f32) -> f32) -> Vec2 {
+        let mut a = Vec2::ZERO;
+        let domain = self.domain;
+        if domain.adhesion != 0.0 {
+            let (left, right) = domain.walls_x();
+            let (top, bottom) = domain.walls_y();
+            for (d, n, walled) in [
+                (p.x, Vec2::X, left),
+                (domain.width - p.x, Vec2::NEG_X, right),
+                (p.y, Vec2::Y, top),
+                (domain.height - p.y, Vec2::NEG_Y, bottom),
+            ] {
+                if walled && d < reach {
+                    a -= n * (domain.adhesion * profile(d));
+                }
+            }
+        }
+        for k in 0..self.colliders() {
+            let beta = self.adhesion_of(k);
+            if beta == 0.0 {
+                continue;
+            }
+            if let Some((d, n, _)) = self.probe(k, p, reach) {
+                let pull = n * (-beta * profile(d));
+                self.record(k, p, pull * (FLUID_PARTICLE_MASS * self.dt));
+                a += pull;
+            }
+        }
+        if let Some((d, n, _, c)) = self.container_probe(p, reach) {
+            a -= n * (c.adhesion * profile(d));
+        }
+        a
+    }
+
+    /// Grid boundary condition for a node at `x` carrying `mass` (MPM):
+    /// within `band` of a collider or the container wall, remove the
+    /// into-surface velocity (relative to the surface) and apply friction,
+    /// like the box-wall BCs but along the surface normal. The node's
+    /// momentum change is booked against the bodies — the grid-side rigid
+    /// coupling of the MLS-MPM paper, without its CPIC cut-cell treatment.
+    #[inline]
+    pub(crate) fn grid_bc(&mut self, x: Vec2, v: &mut Vec2, band: f32, mass: f32) {
+        for k in 0..self.colliders() {
+            if let Some((_, n, vb)) = self.probe(k, x, band) {
+                let (_, _, friction) = self.collider(k);
+                let out = contact_response(*v, *v, n, vb, 0.0, friction);
+                self.record(k, x, (out - *v) * mass);
+                *v = out;
+            }
+        }
+        if let Some((_, n, vb, c)) = self.container_probe(x, band) {
+            *v = contact_response(*v, *v, n, vb, 0.0, c.friction);
+        }
+    }
+
+    /// [`Boundaries::grid_bc`] for one face of a staggered (MAC) grid, which
+    /// stores a single velocity component (FLIP): `v` is the full velocity
+    /// interpolated at the face, and only its `axis` component (0 = x,
+    /// 1 = y) is corrected, returned and booked against the bodies — the
+    /// other component belongs to the neighboring faces.
+    #[inline]
+    pub(crate) fn face_bc(
+        &mut self,
+        x: Vec2,
+        mut v: Vec2,
+        axis: usize,
+        band: f32,
+        mass: f32,
+    ) -> f32 {
+        for k in 0..self.colliders() {
+            if let Some((_, n, vb)) = self.probe(k, x, band) {
+                let (_, _, friction) = self.collider(k);
+                let out = contact_response(v, v, n, vb, 0.0, friction);
+                let mut dv = Vec2::ZERO;
+                dv[axis] = out[axis] - v[axis];
+                self.record(k, x, dv * mass);
+                v[axis] = out[axis];
+            }
+        }
+        if let Some((_, n, vb, c)) = self.container_probe(x, band) {
+            v[axis] = contact_response(v, v, n, vb, 0.0, c.friction)[axis];
+        }
+        v[axis]
+    }
+
+    /// Velocity of the surface nearest `x` — the first collider within
+    /// `reach`, else the container wall — or zero when there is none. For
+    /// lattice solvers, whose bounce-back links move with the solid.
+    pub(crate) fn surface_velocity(&self, x: Vec2, reach: f32) -> Vec2 {
+        if let Some((_, _, vb)) = (0..self.colliders()).find_map(|k| self.probe(k, x, reach)) {
+            return vb;
+        }
+        self.container_probe(x, reach)
+            .map_or(Vec2::ZERO, |(_, _, vb, _)| vb)
+    }
+
+    /// Book the momentum change `dp` the fluid got at `x` against the first
+    /// collider within `reach` of it — for loads the solver applies itself,
+    /// like FLIP's pressure on the faces it holds fixed against a solid.
+    #[inline]
+    pub(crate) fn face_load(&mut self, x: Vec2, dp: Vec2, reach: f32) {
+        if let Some(k) = (0..self.colliders()).find(|&k| self.probe(k, x, reach).is_some()) {
+            self.record(k, x, dp);
+        }
+    }
+}
+
+/// Restitution + Coulomb friction for a contact with normal `n` on a surface
+/// moving at `vb`, all in the surface's rest frame. `v_in` is the velocity
+/// the particle approached with; nothing happens if it was separating. The
+/// relative normal component becomes `−restitution` × the approach speed
+/// (unless the particle already leaves faster), and the relative tangential
+/// component shrinks by at most `friction` × the normal speed change —
+/// slipping if the tangential speed exceeds that, stopping if not.
+#[inline]
+fn contact_response(
+    v: Vec2,
+    v_in: Vec2,
+    n: Vec2,
+    vb: Vec2,
+    restitution: f32,
+    friction: f32,
+) -> Vec2 {
+    let vn_in = (v_in - vb).dot(n);
+    if vn_in >= 0.0 {
+        return v;
+    }
+    let v = v - vb;
+    let vn = v.dot(n);
+    let vt = v - n * vn;
+    let vn_out = vn.max(-restitution * vn_in);
+    let vt_len = vt.length();
+    let vt_out = if friction > 0.0 && vt_len > 1e-9 {
+        let dvn = vn_out - vn_in;
+        vt * (1.0 - friction * dvn / vt_len).max(0.0)
+    } else {
+        vt
+    };
+    vt_out + n * vn_out + vb
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polygon_sign_and_normal() {
+        let square = Shape::Polygon(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+        ]);
+        let (d, n) = square.distance(Vec2::new(5.0, 1.0));
+        assert!((d + 1.0).abs() < 1e-5, "inside depth: {d}");
+        assert!(
+            (n - Vec2::new(0.0, -1.0)).length() < 1e-5,
+            "inside normal: {n}"
+        );
+        let (d, n) = square.distance(Vec2::new(13.0, 5.0));
+        assert!((d - 3.0).abs() < 1e-5, "outside distance: {d}");
+        assert!((n - Vec2::X).length() < 1e-5, "outside normal: {n}");
+    }
+
+    /// A particle driven into a frictionless obstacle keeps its tangential
+    /// speed (slides); with friction it loses at most μ × the normal change.
+    #[test]
+    fn contact_slides_and_bounces() {
+        let mut b = Boundaries::default();
+        b.add(Obstacle::new(Shape::Segment {
+            a: Vec2::new(-100.0, 0.0),
+            b: Vec2::new(100.0, 0.0),
+        }));
+        let mut p = Vec2::new(0.0, 1.0);
+        let mut v = Vec2::new(5.0, -2.0);
+        b.collide(&mut p, &mut v, 3.0);
+        assert!((p.y - 3.0).abs() < 1e-5);
+        assert_eq!(v, Vec2::new(5.0, 0.0));
+
+        b.clear();
+        b.add(Obstacle {
+            restitution: 0.5,
+            friction: 0.5,
+            ..Obstacle::new(Shape::Circle {
+                center: Vec2::ZERO,
+                radius: 10.0,
+            })
+        });
+        let mut p = Vec2::new(0.0, 12.0);
+        let mut v = Vec2::new(3.0, -4.0);
+        b.collide(&mut p, &mut v, 3.0);
+        assert!((p.y - 13.0).abs() < 1e-5);
+        // vn: −4 → +2 (Δ = 6), tangential 3 − 0.5·6 = 0: stops sliding.
+        assert!((v - Vec2::new(0.0, 2.0)).length() < 1e-5, "v = {v}");
+    }
+
+    /// A wall moving into a resting particle hands it its own velocity; a
+    /// posed (rotated) shape is queried in its own frame.
+    #[test]
+    fn moving_wall_transfers_momentum() {
+        let mut b = Boundaries::default();
+        b.add(Obstacle {
+            motion: Motion::Rotate {
+                pivot: Vec2::ZERO,
+                omega: 1.0,
+            },
+            ..Obstacle::new(Shape::Capsule {
+                a: Vec2::ZERO,
+                b: Vec2::new(100.0, 0.0),
+                radius: 2.0,
+            })
+        });
+        // A quarter turn later the capsule points along +y and sweeps −x.
+        b.advance(std::f32::consts::FRAC_PI_2, PHYS_TIME_STEP, Vec2::ZERO);
+        let mut p = Vec2::new(-4.0, 50.0);
+        let mut v = Vec2::ZERO;
+        b.collide(&mut p, &mut v, 3.0);
+        assert!((p.x + 5.0).abs() < 1e-3, "p = {p}");
+        // Surface speed ω·r = 50 at r = 50, into the particle (−x).
+        assert!((v - Vec2::new(-50.0, 0.0)).length() < 1e-2, "v = {v}");
+    }
+
+    /// An adhesive wall pulls a nearby particle in along its normal, only
+    /// within reach, and a body it pulls on takes the opposite momentum.
+    #[test]
+    fn adhesive_surface_pulls_fluid_in() {
+        let mut b = Boundaries::default();
+        let profile = |d: f32| if d < 10.0 { 1.0 } else { 0.0 };
+        assert!(!b.adhesive());
+        b.set_domain(Domain {
+            adhesion: 100.0,
+            ..Domain::new(200.0, 200.0)
+        });
+        assert!(b.adhesive());
+        let a = b.adhesion(Vec2::new(100.0, 195.0), 10.0, profile);
+        assert!((a - Vec2::new(0.0, 100.0)).length() < 1e-5, "floor: {a}");
+        assert_eq!(
+            b.adhesion(Vec2::new(100.0, 100.0), 10.0, profile),
+            Vec2::ZERO
+        );
+
+        b.set_domain(Domain::new(200.0, 200.0));
+        b.add_body(RigidBody {
+            adhesion: 50.0,
+            ..RigidBody::new(
+                Shape::Circle {
+                    center: Vec2::ZERO,
+                    radius: 10.0,
+                },
+                100.0,
+                100.0,
+                Vec2::new(100.0, 100.0),
+            )
+        });
+        let a = b.adhesion(Vec2::new(100.0, 115.0), 10.0, profile);
+        assert!((a - Vec2::new(0.0, -50.0)).length() < 1e-4, "body: {a}");
+        let (j, _) = b.impulses[0];
+        assert!(j.y > 0.0 && j.x.abs() < 1e-4, "body impulse: {j}");
+    }
+}
@@ -13,19 +13,34 @@
 //!   2. **divergence-free solve** — drive Dρ/Dt → 0 by correcting velocities
 //!      (Algorithm 2): kᵛ_i = (1/Δt)(Dρ_i/Dt) α_i, then
 //!      v_i −= Δt Σ_j (kᵛ_i/ρ_i + kᵛ_j/ρ_j) ∇W_ij
-//!   3. apply non-pressure accelerations (gravity, XSPH viscosity)
+//!   3. apply non-pressure accelerations (gravity, optional Akinci cohesion
+//!      and wall adhesion), the optional implicit viscosity solve (Weiler et
+//!      al. 2018, see [`DfsphParams::viscosity`]), then XSPH viscosity
 //!   4. **constant-density solve** — drive ρ* → ρ0 (Algorithm 3):
 //!      k_i = (1/Δt²)(ρ*_i − ρ0) α_i, same velocity update form
 //!   5. advect x += v·Δt, project onto the box (killing the into-wall velocity)
+//!      and out of the obstacles (restitution/friction contact response)
 //!
 //! Masses are uniform (mⱼ = 1), matching the codebase's kernel unit system, so
 //! ρ_i = Σ_j W_ij exactly as in PBF and the two fluids fill comparable volume.
+//!
+//! Multi-phase: particles of a heavier [`Phase`] get mass m_i = its relative
+//! density. The solves keep driving the number density Σ_j W_ij (the same
+//! rest value for every phase, so interfaces stay free of spurious pressure),
+//! and the pressure acceleration picks up the inverse mass w_i = 1/m_i:
+//!   v_i −= Δt w_i Σ_j (κ_i + κ_j) ∇W_ij,
+//!   α_i = 1 / (w_i |Σ_j ∇W_ij|² + Σ_j w_j |∇W_ij|²)
+//! which reduces to the single-phase form when every w is 1.
 
-use glam::Vec2;
+use glam::{Mat2, Vec2};
 use rayon::prelude::*;
 
-use super::sph::{SphGrid, SphKernel};
-use super::{FluidSolver, ShareData, BOTTOM_WALL, LEFT_WALL, PHYS_TIME_STEP, RIGHT_WALL, TOP_WALL};
+use super::phase::inv_masses;
+use super::sph::{
+    advect, effective_gravity, fit_particles, sampled_density_ratio, SphGrid, SphKernel,
+    SurfaceTension, WallAdhesion, WallBox,
+};
+use super::{report, retain_flagged, Boundaries, Domain, FluidSolver, Phase, ShareData};
 use crate::constants::BALL_SIZE;
 
 // Smoothing radius and rest spacing shared with PBF so a DFSPH fluid packs to
@@ -46,6 +61,21 @@ pub struct DfsphParams {
     /// Per-substep velocity clamp as a fraction of h/Δt (stability safety net,
     /// like PBF's — bounds a single bad step so a transient can't cascade).
     pub vmax_frac: f32,
+    /// Akinci surface-tension strength γ, px/s² (0 = off, as PBF's
+    /// [`PbfParams::surface_tension`](super::PbfParams::surface_tension)).
+    /// Without it a DFSPH fluid has no cohesion at all. Unlike PBF's, this is
+    /// the cohesion term only, with no curvature term: the pressure here
+    /// can't pull the surface back against the curvature push, which peels
+    /// it off. Stable to several hundred.
+    pub surface_tension: f32,
+    /// Dynamic viscosity μ of the implicit viscosity solve (0 = off). The
+    /// default fluid has unit area density (as for rigid bodies), so μ is
+    /// also its kinematic viscosity ν in px²/s; a heavier phase gets
+    /// ν = μ/density. At 1 px = 1 mm, honey is ~10⁴ and lava more.
+    pub viscosity: f32,
+    /// Iteration cap for the viscosity solve, which stops early once its
+    /// residual is below `VISCOSITY_TOL` of the right-hand side.
+    pub viscosity_iters: usize,
 }
 
 impl Default for DfsphParams {
@@ -58,10 +88,155 @@ impl Default for DfsphParams {
             divergence_iters: 2,
             xsph_c: 0.05,
             vmax_frac: 0.5,
+            surface_tension: 0.0,
+            viscosity: 0.0,
+            viscosity_iters: 50,
         }
     }
 }
 
+/// Relative residual at which the viscosity solve stops.
+const VISCOSITY_TOL: f32 = 1e-3;
+
+/// Implicit viscosity after Weiler, Koschier, Brand & Bender (CGF 2018):
+/// the viscous velocities solve
+///   v_i − Δt ν_i ∇²v_i = v*_i,
+///   ∇²v_i = 2(d+2) Σ_j V_j (v_ij·x_ij)/(|x_ij|² + 0.01h²) ∇W_ij
+/// with d = 2 and V_j = 1/ρ_j, so the viscous stress is integrated
+/// unconditionally stably at any ν — XSPH's explicit smoothing diverges long
+/// before honey. The system is solved matrix-free by conjugate gradients,
+/// preconditioned with the inverse 2×2 diagonal blocks. V_j ≠ V_i makes it
+/// slightly unsymmetric, which CG tolerates in practice (as in the authors'
+/// SPlisHSPlasH).
+struct ImplicitViscosity {
+    kernel: SphKernel,
+    b: Vec<Vec2>,
+    r: Vec<Vec2>,
+    z: Vec<Vec2>,
+    p: Vec<Vec2>,
+    ap: Vec<Vec2>,
+    precond: Vec<Mat2>,
+}
+
+/// Σ over a slice pair of the component-wise dot product.
+fn dot(a: &[Vec2], b: &[Vec2]) -> f32 {
+    a.par_iter().zip(b).map(|(a, b)| a.dot(*b)).sum()
+}
+
+impl ImplicitViscosity {
+    fn new(h: f32) -> Self {
+        Self {
+            kernel: SphKernel::new(h),
+            b: Vec::new(),
+            r: Vec::new(),
+            z: Vec::new(),
+            p: Vec::new(),
+            ap: Vec::new(),
+            precond: Vec::new(),
+        }
+    }
+
+    /// Solve for the viscous velocities in place of `vel` (the right-hand
+    /// side v*, also the initial guess); `dt_nu[i]` is Δt ν_i for particle
+    /// i. Returns the iterations used.
+    fn solve(
+        &mut self,
+        grid: &SphGrid,
+        x: &[Vec2],
+        density: &[f32],
+        dt_nu: &[f32],
+        iters: usize,
+        vel: &mut [Vec2],
+    ) -> usize {
+        let n = x.len();
+        let kernel = &self.kernel;
+        let eps = 0.01 * kernel.h() * kernel.h();
+        // −Δt ν_i 2(d+2) V_j / (|x_ij|² + ε): the weight of pair ij in row i.
+        let weight = |i: usize, j: usize, d: Vec2| {
+            -8.0 * dt_nu[i] / (density[j] * (d.length_squared() + eps))
+        };
+        let apply = |v: &[Vec2], out: &mut [Vec2]| {
+            out.par_iter_mut().enumerate().for_each(|(i, o)| {
+                let xi = x[i];
+                let vi = v[i];
+                let mut acc = Vec2::ZERO;
+                grid.for_neighbors(xi, |j, off| {
+                    if j == i {
+                        return;
+                    }
+                    let d = xi - x[j] - off;
+                    acc += kernel.grad(d) * ((vi - v[j]).dot(d) * weight(i, j, d));
+                });
+                *o = vi + acc;
+            });
+        };
+
+        self.b.clear();
+        self.b.extend_from_slice(vel);
+        for buf in [&mut self.r, &mut self.z, &mut self.p, &mut self.ap] {
+            buf.resize(n, Vec2::ZERO);
+        }
+        self.precond.resize(n, Mat2::IDENTITY);
+        self.precond.par_iter_mut().enumerate().for_each(|(i, m)| {
+            let xi = x[i];
+            let mut diag = Mat2::IDENTITY;
+            grid.for_neighbors(xi, |j, off| {
+                if j == i {
+                    return;
+                }
+                let d = xi - x[j] - off;
+                let g = kernel.grad(d) * weight(i, j, d);
+                diag += Mat2::from_cols(g * d.x, g * d.y);
+            });
+            *m = diag.inverse();
+        });
+
+        apply(vel, &mut self.ap);
+        self.r
+            .par_iter_mut()
+            .zip(&self.b)
+            .zip(&self.ap)
+            .for_each(|((r, b), ap)| *r = *b - *ap);
+        let precondition = |precond: &[Mat2], r: &[Vec2], z: &mut [Vec2]| {
+            z.par_iter_mut()
+                .zip(precond)
+                .zip(r)
+                .for_each(|((z, m), r)| *z = *m * *r);
+        };
+        precondition(&self.precond, &self.r, &mut self.z);
+        self.p.copy_from_slice(&self.z);
+        let tol2 = VISCOSITY_TOL * VISCOSITY_TOL * dot(&self.b, &self.b);
+        let mut rz = dot(&self.r, &self.z);
+        for k in 0..iters {
+            if dot(&self.r, &self.r) <= tol2 {
+                return k;
+            }
+            apply(&self.p, &mut self.ap);
+            let pap = dot(&self.p, &self.ap);
+            if pap <= 0.0 {
+                return k;
+            }
+            let a = rz / pap;
+            vel.par_iter_mut()
+                .zip(&self.p)
+                .for_each(|(v, p)| *v += *p * a);
+            self.r
+                .par_iter_mut()
+                .zip(&self.ap)
+                .for_each(|(r, ap)| *r -= *ap * a);
+            precondition(&self.precond, &self.r, &mut self.z);
+            let rz_next = dot(&self.r, &self.z);
+            let beta = rz_next / rz;
+            rz = rz_next;
+            self.p
+                .par_iter_mut()
+                .zip(&self.z)
+                .for_each(|(p, z)| *p = *z + *p * beta);
+        }
+        iters
+    }
+}
+
 /// Divergence-Free SPH solver state. Velocities are explicit (DFSPH is a
 /// velocity/force method, not position projection); ρ_i and α_i are the
 /// per-step precompute reused across both solvers.
@@ -76,6 +251,12 @@ pub struct Dfsph {
     kappa: Vec<f32>,     // per-iteration stiffness k_i / kᵛ_i
     dv: Vec<Vec2>,       // velocity delta (Jacobi double-buffer)
     vscratch: Vec<Vec2>, // XSPH double-buffer
+    phases: Vec<Phase>,
+    inv_mass: Vec<f32>, // w_i = 1/m_i from the particle's phase
+    tension: SurfaceTension,
+    adhesion: WallAdhesion,
+    viscosity: ImplicitViscosity,
+    dt_nu: Vec<f32>, // Δt ν_i = Δt μ w_i for the viscosity solve
 }
 
 impl Default for Dfsph {
@@ -88,7 +269,7 @@ impl Dfsph {
     pub fn new() -> Self {
         let kernel = SphKernel::new(DFSPH_H);
         Self {
-            grid: SphGrid::new(DFSPH_H),
+            grid: SphGrid::new(DFSPH_H, &Domain::default()),
             rest_density: kernel.rest_density(DFSPH_REST_SPACING),
             kernel,
             params: DfsphParams::default(),
@@ -98,18 +279,18 @@ impl Dfsph {
             kappa: Vec::new(),
             dv: Vec::new(),
             vscratch: Vec::new(),
+            phases: Vec::new(),
+            inv_mass: Vec::new(),
+            // Cohesion only (see `DfsphParams::surface_tension`).
+            tension: SurfaceTension::new(DFSPH_H, DFSPH_REST_SPACING, false),
+            adhesion: WallAdhesion::new(DFSPH_H, DFSPH_REST_SPACING),
+            viscosity: ImplicitViscosity::new(DFSPH_H),
+            dt_nu: Vec::new(),
         }
     }
 
-    fn ensure_sized(&mut self, n: usize) {
-        if self.vel.len() != n {
-            self.vel.resize(n, Vec2::ZERO);
-        }
-        self.density.resize(n, 0.0);
-        self.alpha.resize(n, 0.0);
-        self.kappa.resize(n, 0.0);
-        self.dv.resize(n, Vec2::ZERO);
-        self.vscratch.resize(n, Vec2::ZERO);
+    pub fn set_params(&mut self, params: DfsphParams) {
+        self.params = params;
     }
 
     /// ρ_i (incl. self term) and the DFSPH factor α_i, both read-only over the
@@ -117,6 +298,7 @@ impl Dfsph {
     fn compute_density_alpha(&mut self, x: &[Vec2]) {
         let grid = &self.grid;
         let kernel = &self.kernel;
+        let inv_mass = &self.inv_mass;
         let density = &mut self.density;
         let alpha = &mut self.alpha;
         density
@@ -127,18 +309,18 @@ impl Dfsph {
                 let xi = x[i];
                 let mut rho = 0.0f32;
                 let mut grad_sum = Vec2::ZERO; // Σ_j ∇W_ij
-                let mut grad2_sum = 0.0f32; // Σ_j |∇W_ij|²
-                grid.for_neighbors(xi, |j| {
-                    let d = xi - x[j];
+                let mut grad2_sum = 0.0f32; // Σ_j w_j |∇W_ij|²
+                grid.for_neighbors(xi, |j, o| {
+                    let d = xi - x[j] - o;
                     rho += kernel.w(d.length_squared());
                     if j != i {
                         let g = kernel.grad(d);
                         grad_sum += g;
-                        grad2_sum += g.length_squared();
+                        grad2_sum += inv_mass[j] * g.length_squared();
                     }
                 });
                 *rho_i = rho;
-                let denom = grad_sum.length_squared() + grad2_sum;
+                let denom = inv_mass[i] * grad_sum.length_squared() + grad2_sum;
                 // α_i = 1/denom, guarded: a poorly-supported particle (few
                 // neighbors) has a tiny denom and would get a runaway stiffness
                 // from a single kick — zero its pressure instead, as
@@ -152,7 +334,7 @@ impl Dfsph {
 
     /// One velocity-projection sweep shared by both solvers: given per-particle
     /// stiffness `kappa` = k_i/ρ_i (already read into `self.kappa`), apply
-    /// v_i −= Δt Σ_j (k_i/ρ_i + k_j/ρ_j) ∇W_ij as a Jacobi update.
+    /// v_i −= Δt w_i Σ_j (k_i/ρ_i + k_j/ρ_j) ∇W_ij as a Jacobi update.
     ///
     /// The ρ_i in DFSPH's factor α_i (Bender & Koschier eq. 8) cancels the 1/ρ_i
     /// of the pressure force, so `kappa` already carries the k/ρ ratio and is
@@ -163,18 +345,19 @@ impl Dfsph {
             let grid = &self.grid;
             let kernel = &self.kernel;
             let kappa = &self.kappa;
+            let inv_mass = &self.inv_mass;
             self.dv.par_iter_mut().enumerate().for_each(|(i, dvi)| {
                 let xi = x[i];
                 let ki = kappa[i];
                 let mut acc = Vec2::ZERO;
-                grid.for_neighbors(xi, |j| {
+                grid.for_neighbors(xi, |j, o| {
                     if j == i {
                         return;
                     }
-                    let g = kernel.grad(xi - x[j]);
+                    let g = kernel.grad(xi - x[j] - o);
                     acc += g * (ki + kappa[j]);
                 });
-                *dvi = acc * (-dt);
+                *dvi = acc * (-dt * inv_mass[i]);
             });
         }
         for (v, dv) in self.vel.iter_mut().zip(&self.dv) {
@@ -199,11 +382,11 @@ impl Dfsph {
                     let xi = x[i];
                     let vi = vel[i];
                     let mut drho = 0.0f32; // Dρ_i/Dt = Σ_j (v_i − v_j)·∇W_ij
-                    grid.for_neighbors(xi, |j| {
+                    grid.for_neighbors(xi, |j, o| {
                         if j == i {
                             return;
                         }
-                        drho += (vi - vel[j]).dot(kernel.grad(xi - x[j]));
+                        drho += (vi - vel[j]).dot(kernel.grad(xi - x[j] - o));
                     });
                     let rho_star = (density[i] + dt * drho).max(rho0);
                     *ki = (rho_star - rho0) * alpha[i] * inv_dt2;
@@ -234,11 +417,11 @@ impl Dfsph {
                     let xi = x[i];
                     let vi = vel[i];
                     let mut drho = 0.0f32;
-                    grid.for_neighbors(xi, |j| {
+                    grid.for_neighbors(xi, |j, o| {
                         if j == i {
                             return;
                         }
-                        drho += (vi - vel[j]).dot(kernel.grad(xi - x[j]));
+                        drho += (vi - vel[j]).dot(kernel.grad(xi - x[j] - o));
                     });
                     // Only resist positive divergence (compression rate).
                     *ki = drho.max(0.0) * alpha[i] * inv_dt;
@@ -265,11 +448,11 @@ impl Dfsph {
                 let xi = x[i];
                 let vi = vel[i];
                 let mut acc = Vec2::ZERO;
-                grid.for_neighbors(xi, |j| {
+                grid.for_neighbors(xi, |j, o| {
                     if j == i {
                         return;
                     }
-                    acc += (vel[j] - vi) * kernel.w((xi - x[j]).length_squared());
+                    acc += (vel[j] - vi) * kernel.w((xi - x[j] - o).length_squared());
                 });
                 *o = vi + acc * (c * inv_rho0);
             });
@@ -283,66 +466,100 @@ impl FluidSolver for Dfsph {
         "DFSPH"
     }
 
-    fn substep(&mut self, dt: f32, gravity: Vec2, share: &mut ShareData, c_opos: &mut Vec<Vec2>) {
+    fn set_dfsph_params(&mut self, params: DfsphParams) {
+        self.set_params(params);
+    }
+
+    fn set_phases(&mut self, phases: &[Phase]) {
+        self.phases = phases.to_vec();
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.vel, keep);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
         let n = share.c_pos.len();
         if n == 0 {
             return;
         }
-        self.ensure_sized(n);
         c_opos.resize(n, Vec2::ZERO);
+        fit_particles(
+            &mut self.vel,
+            &share.c_pos,
+            c_opos,
+            dt,
+            &mut [
+                &mut self.density,
+                &mut self.alpha,
+                &mut self.kappa,
+                &mut self.dv,
+                &mut self.vscratch,
+            ],
+        );
+        inv_masses(&self.phases, &share.c_phase, n, &mut self.inv_mass);
 
-        // Match the granular Verlet integrator's effective acceleration so all
-        // models fall the same way (x += a·dt²·INV_PHYS_DT there ⇒ a·INV here).
-        const INV_PHYS_DT: f32 = 1.0 / PHYS_TIME_STEP;
-        let accel = gravity * INV_PHYS_DT;
+        let accel = effective_gravity(gravity);
 
         let x = &share.c_pos;
 
         // 1. Neighbor grid + per-step precompute (ρ_i, α_i).
+        self.grid.fit(bounds.domain());
         self.grid.build(x);
         self.compute_density_alpha(x);
 
-        // 2. Non-pressure accelerations: gravity, then XSPH viscosity.
+        // 2. Non-pressure accelerations: gravity, surface tension and wall
+        // adhesion, then the implicit viscosity solve and XSPH.
         for v in &mut self.vel {
             *v += accel * dt;
         }
+        if self.params.surface_tension > 0.0 {
+            let gamma = self.params.surface_tension;
+            let tension = self
+                .tension
+                .accelerations(&self.grid, x, &self.inv_mass, gamma);
+            for (v, a) in self.vel.iter_mut().zip(tension) {
+                *v += *a * dt;
+            }
+        }
+        if bounds.adhesive() {
+            let reach = self.adhesion.reach();
+            for (v, &p) in self.vel.iter_mut().zip(x.iter()) {
+                *v += bounds.adhesion(p, reach, |d| self.adhesion.profile(d)) * dt;
+            }
+        }
+        let mut viscosity_iters = 0;
+        if self.params.viscosity > 0.0 {
+            let dt_mu = dt * self.params.viscosity;
+            self.dt_nu.clear();
+            self.dt_nu.extend(self.inv_mass.iter().map(|w| dt_mu * w));
+            viscosity_iters = self.viscosity.solve(
+                &self.grid,
+                x,
+                &self.density,
+                &self.dt_nu,
+                self.params.viscosity_iters,
+                &mut self.vel,
+            );
+        }
         self.apply_xsph(x);
 
         // 3. Constant-density solve (drive ρ* → ρ0 by correcting velocities).
         self.density_solve(x, dt);
 
-        // 4. Advect and project onto the box, killing the into-wall velocity.
-        let lo_x = LEFT_WALL + BALL_SIZE;
-        let hi_x = RIGHT_WALL - BALL_SIZE;
-        let lo_y = BOTTOM_WALL + BALL_SIZE;
-        let hi_y = TOP_WALL - BALL_SIZE;
+        // 4. Advect and project onto the box, killing the into-wall velocity,
+        // then out of the obstacles; open and periodic edges have no wall
+        // (periodic axes wrap).
+        let walls = WallBox::new(bounds.domain());
         let vmax = self.params.vmax_frac * DFSPH_H / dt;
-        let vmax2 = vmax * vmax;
-        let x = &mut share.c_pos;
-        for i in 0..n {
-            let mut v = self.vel[i];
-            let s2 = v.length_squared();
-            if s2 > vmax2 {
-                v *= vmax / s2.sqrt();
-            }
-            let mut p = x[i] + v * dt;
-            if p.x < lo_x {
-                p.x = lo_x;
-                v.x = v.x.max(0.0);
-            } else if p.x > hi_x {
-                p.x = hi_x;
-                v.x = v.x.min(0.0);
-            }
-            if p.y < lo_y {
-                p.y = lo_y;
-                v.y = v.y.max(0.0);
-            } else if p.y > hi_y {
-                p.y = hi_y;
-                v.y = v.y.min(0.0);
-            }
-            x[i] = p;
-            self.vel[i] = v;
-        }
+        advect(&mut share.c_pos, &mut self.vel, dt, vmax, &walls, bounds);
 
         // 5. Divergence-free solve at the *new* positions, so the velocity
         // field entering the next substep has Dρ/Dt ≈ 0 (paper Algorithm 1
@@ -353,34 +570,11 @@ impl FluidSolver for Dfsph {
         self.compute_density_alpha(x);
         self.divergence_solve(x, dt);
 
-        // 6. Shared/rendered bookkeeping, mirroring the granular/PBF convention:
-        // c_opos = pos − v·dt (so external velocity readers agree) and c_color
-        // encodes speed as a hue.
-        let mut speed_sum = 0.0f32;
-        let mut max_speed = 0.0f32;
-        for i in 0..n {
-            c_opos[i] = x[i] - self.vel[i] * dt;
-            let speed = (self.vel[i] * dt * 20.0).length();
-            share.c_color[i] = (speed + 198.0) % 360.0;
-            speed_sum += speed;
-            max_speed = max_speed.max(speed);
-        }
-
-        // Diagnostics: mean ρ/ρ0 over a subsample (1.0 = incompressible).
-        let stride = (n / 256).max(1);
-        let mut dsum = 0.0f64;
-        let mut cnt = 0usize;
-        let mut i = 0;
-        while i < n {
-            dsum += (self.density[i] / self.rest_density) as f64;
-            cnt += 1;
-            i += stride;
-        }
-
-        let ps = &mut share.perf_stats;
-        ps.mean_speed = speed_sum / n as f32;
-        ps.max_speed = max_speed;
-        ps.pbf_density_ratio = (dsum / cnt.max(1) as f64) as f32;
+        // 6. Shared/rendered bookkeeping and diagnostics.
+        let (density, rho0) = (&self.density, self.rest_density);
+        let ratio = sampled_density_ratio(n, |i| density[i] / rho0);
+        report(share, c_opos, &self.vel, dt, ratio);
+        share.perf_stats.viscosity_iters = viscosity_iters;
     }
 }
 
@@ -388,6 +582,7 @@ impl FluidSolver for Dfsph {
 mod tests {
     use super::*;
     use crate::constants::{HEIGHT, WIDTH};
+    use crate::physics::tests::block_scene;
     use crate::physics::{Physics, ShareData, Strategy, PHYS_TIME_STEP};
     use std::sync::mpsc::channel;
 
@@ -438,4 +633,45 @@ mod tests {
             share.perf_stats.mean_speed
         );
     }
+
+    /// A column of honey-like DFSPH fluid (μ = 10⁵) must creep out as a
+    /// viscous gravity current where the inviscid one surges across the box,
+    /// with the implicit solve converging under its iteration cap.
+    #[test]
+    fn viscous_dam_break_creeps() {
+        let front = |viscosity: f32| {
+            let (mut physics, mut share) = block_scene(
+                Strategy::Dfsph,
+                Domain::default(),
+                Vec2::new(3.0, HEIGHT - 237.0),
+                (20, 40),
+                Vec2::ZERO,
+            );
+            physics.set_dfsph_params(DfsphParams {
+                viscosity,
+                ..Default::default()
+            });
+            for _ in 0..480 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            assert!(
+                share.c_pos.iter().all(|p| p.is_finite()),
+                "μ={viscosity}: non-finite positions"
+            );
+            if viscosity > 0.0 {
+                let iters = share.perf_stats.viscosity_iters;
+                assert!(
+                    iters > 0 && iters < DfsphParams::default().viscosity_iters,
+                    "μ={viscosity}: viscosity solve took {iters} iterations"
+                );
+            }
+            share.c_pos.iter().map(|p| p.x).fold(0.0, f32::max)
+        };
+        let (water, honey) = (front(0.0), front(1e5));
+        assert!(water > 1000.0, "inviscid surge stalled at x={water}");
+        assert!(
+            honey > 200.0 && honey < 0.5 * water,
+            "viscous front at x={honey} (inviscid {water})"
+        );
+    }
 }
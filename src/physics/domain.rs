@@ -0,0 +1,213 @@
+//! The simulation box.
+//!
+//! Particles live in `[0, width] × [0, height]` (y down, like the screen). The
+//! default is the window-sized `WIDTH × HEIGHT` box, but the size is a runtime
+//! parameter of [`Physics`](super::Physics): every strategy sizes its grid from
+//! the [`Domain`] it is handed through [`Boundaries`](super::Boundaries) and
+//! rebuilds it when the domain changes.
+//!
+//! Each edge of the box has an [`Edge`] type. A `Wall` is the solid box wall.
+//! An `Outflow` edge is open: nothing holds the fluid in, and
+//! [`Physics`](super::Physics) deletes every particle whose center has crossed
+//! it at the end of each step, tallying them per edge in an [`Outflow`]
+//! (a waterfall off the side of the box).
+//!
+//! An axis can also be *periodic*: its two walls vanish and a particle leaving
+//! one side re-enters from the other (channel flow, mixing). The solvers wrap
+//! positions back into the box once per substep; their neighbor grids tile a
+//! periodic axis exactly and wrap the stencil around it, handing each
+//! neighbor found across the seam together with the offset that carries it to
+//! its image beside the querying particle, so every kernel sees
+//! minimum-image distances. Obstacles, rigid bodies and the container have no
+//! periodic images.
+
+use glam::Vec2;
+
+use crate::constants::{HEIGHT, WIDTH};
+
+/// What happens at one edge of the box.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Edge {
+    /// A solid wall.
+    #[default]
+    Wall,
+    /// Wraps onto the opposite edge. Periodicity is a property of the axis:
+    /// marking either edge `Periodic` makes both of them wrap.
+    Periodic,
+    /// Open: particles that cross it are deleted.
+    Outflow,
+}
+
+/// Size of the box, px, and the type of each of its edges. `top` is the
+/// `y = 0` edge and `bottom` the `y = height` floor gravity pulls toward.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Domain {
+    pub width: f32,
+    pub height: f32,
+    pub left: Edge,
+    pub right: Edge,
+    pub top: Edge,
+    pub bottom: Edge,
+    /// Adhesion of the `Wall` edges (see
+    /// [`Obstacle::adhesion`](super::Obstacle::adhesion)).
+    pub adhesion: f32,
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Self::new(WIDTH, HEIGHT)
+    }
+}
+
+impl Domain {
+    /// A closed `width × height` box (walls on every side, no adhesion).
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            left: Edge::Wall,
+            right: Edge::Wall,
+            top: Edge::Wall,
+            bottom: Edge::Wall,
+            adhesion: 0.0,
+        }
+    }
+
+    /// The left and right edges wrap onto each other.
+    #[inline(always)]
+    pub fn periodic_x(&self) -> bool {
+        self.left == Edge::Periodic || self.right == Edge::Periodic
+    }
+
+    /// The top and bottom edges wrap onto each other.
+    #[inline(always)]
+    pub fn periodic_y(&self) -> bool {
+        self.top == Edge::Periodic || self.bottom == Edge::Periodic
+    }
+
+    /// Whether the left and right edges are solid walls.
+    #[inline(always)]
+    pub(crate) fn walls_x(&self) -> (bool, bool) {
+        let open = self.periodic_x();
+        (
+            !open && self.left == Edge::Wall,
+            !open && self.right == Edge::Wall,
+        )
+    }
+
+    /// Whether the top and bottom edges are solid walls.
+    #[inline(always)]
+    pub(crate) fn walls_y(&self) -> (bool, bool) {
+        let open = self.periodic_y();
+        (
+            !open && self.top == Edge::Wall,
+            !open && self.bottom == Edge::Wall,
+        )
+    }
+
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width, self.height)
+    }
+
+    /// `p` moved by whole periods back into the box along the periodic axes.
+    #[inline(always)]
+    pub fn wrap(&self, mut p: Vec2) -> Vec2 {
+        if self.periodic_x() {
+            p.x = p.x.rem_euclid(self.width);
+        }
+        if self.periodic_y() {
+            p.y = p.y.rem_euclid(self.height);
+        }
+        p
+    }
+
+    /// Minimum-image separation: `d` shortened by whole periods along the
+    /// periodic axes to the nearest copy.
+    #[inline(always)]
+    pub fn min_image(&self, mut d: Vec2) -> Vec2 {
+        if self.periodic_x() {
+            d.x -= self.width * (d.x / self.width).round();
+        }
+        if self.periodic_y() {
+            d.y -= self.height * (d.y / self.height).round();
+        }
+        d
+    }
+
+    /// The periodic copy of `q` nearest `p` (`q` itself in a closed box).
+    #[inline(always)]
+    pub fn nearest_image(&self, p: Vec2, q: Vec2) -> Vec2 {
+        p - self.min_image(p - q)
+    }
+
+    /// Column `x + dx` of a grid of `n` columns tiling the width exactly:
+    /// the column index and the x offset that carries its particles to their
+    /// images beside column `x` (nonzero only across a periodic seam), or
+    /// `None` past a wall.
+    #[inline(always)]
+    pub(crate) fn step_x(&self, x: usize, dx: isize, n: usize) -> Option<(usize, f32)> {
+        step(x, dx, n, self.periodic_x(), self.width)
+    }
+
+    /// Row `y + dy`, as [`Domain::step_x`].
+    #[inline(always)]
+    pub(crate) fn step_y(&self, y: usize, dy: isize, n: usize) -> Option<(usize, f32)> {
+        step(y, dy, n, self.periodic_y(), self.height)
+    }
+
+    /// Cells per axis when tiling the box with cells of at least `cell` px:
+    /// the count is rounded down and each cell stretched to fit, so the
+    /// cells never shrink below `cell` and the last row and column end
+    /// exactly on the walls.
+    pub(crate) fn tiling(&self, cell: f32) -> (usize, usize) {
+        let w = ((self.width / cell) as usize).max(1);
+        let h = ((self.height / cell) as usize).max(1);
+        (w, h)
+    }
+}
+
+/// Particles removed through each outflow edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Outflow {
+    pub left: u64,
+    pub right: u64,
+    pub top: u64,
+    pub bottom: u64,
+}
+
+impl Outflow {
+    pub fn total(&self) -> u64 {
+        self.left + self.right + self.top + self.bottom
+    }
+
+    /// Whether `p` has left `domain` through an outflow edge; if so it is
+    /// counted against that edge.
+    pub(crate) fn record(&mut self, domain: &Domain, p: Vec2) -> bool {
+        let edge = if p.x < 0.0 && domain.left == Edge::Outflow && !domain.periodic_x() {
+            &mut self.left
+        } else if p.x > domain.width && domain.right == Edge::Outflow && !domain.periodic_x() {
+            &mut self.right
+        } else if p.y < 0.0 && domain.top == Edge::Outflow && !domain.periodic_y() {
+            &mut self.top
+        } else if p.y > domain.height && domain.bottom == Edge::Outflow && !domain.periodic_y() {
+            &mut self.bottom
+        } else {
+            return false;
+        };
+        *edge += 1;
+        true
+    }
+}
+
+#[inline(always)]
+fn step(c: usize, dc: isize, n: usize, periodic: bool, period: f32) -> Option<(usize, f32)> {
+    let m = c as isize + dc;
+    if m >= 0 && m < n as isize {
+        Some((m as usize, 0.0))
+    } else if periodic {
+        let offset = if m < 0 { -period } else { period };
+        Some((m.rem_euclid(n as isize) as usize, offset))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,131 @@
+//! Particle sources and sinks as scene objects.
+//!
+//! An [`Emitter`] is a nozzle: it releases particles at a steady `rate`
+//! (particles per simulated second) with a launch `velocity`, jittered inside
+//! a cone of half-angle `spread` and scattered across the nozzle `width` so a
+//! fast stream doesn't stack new particles on top of each other. A [`Drain`]
+//! deletes every particle that enters its region. Both are owned by
+//! [`Physics`](super::Physics), which emits before the substeps of a step and
+//! drains after them, so the solvers only ever see whole-step growth and
+//! shrinkage (see [`FluidSolver::retain`](super::FluidSolver::retain)).
+
+use glam::Vec2;
+
+use super::Sdf;
+use crate::constants::BALL_SIZE;
+
+/// A nozzle releasing a continuous stream of particles.
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    /// Center of the nozzle mouth.
+    pub position: Vec2,
+    /// Launch velocity of the jet axis, px/s.
+    pub velocity: Vec2,
+    /// Particles per simulated second.
+    pub rate: f32,
+    /// Half-angle (radians) of the cone launch directions are drawn from.
+    pub spread: f32,
+    /// Width of the nozzle mouth across the jet, px.
+    pub width: f32,
+    /// Paused emitters keep their place but release nothing.
+    pub active: bool,
+    /// Fluid phase of the released particles (see
+    /// [`Physics::set_phases`](super::Physics::set_phases)).
+    pub phase: u8,
+}
+
+impl Emitter {
+    /// A nozzle at `position` jetting along `velocity` (px/s) at `rate`
+    /// particles/s, with no spread and a mouth four particles wide.
+    pub fn new(position: Vec2, velocity: Vec2, rate: f32) -> Self {
+        Self {
+            position,
+            velocity,
+            rate,
+            spread: 0.0,
+            width: 8.0 * BALL_SIZE,
+            active: true,
+            phase: 0,
+        }
+    }
+}
+
+/// Emission bookkeeping kept beside the emitters: the fractional particle
+/// each one carries over to the next step, and the jitter RNG.
+pub(crate) struct Emission {
+    owed: Vec<f32>,
+    rng: u32,
+}
+
+impl Default for Emission {
+    fn default() -> Self {
+        Self {
+            owed: Vec::new(),
+            rng: 0x9e37_79b9,
+        }
+    }
+}
+
+impl Emission {
+    /// Release the particles `emitters` owe over a step of `dt` seconds, at
+    /// most `room` in total, as (position, velocity, phase) triples. Each is
+    /// placed at a random point across its nozzle's mouth and advanced along
+    /// its launch velocity by a random fraction of the step, as if it had
+    /// left mid-step.
+    pub(crate) fn emit(
+        &mut self,
+        emitters: &[Emitter],
+        dt: f32,
+        room: usize,
+        out: &mut Vec<(Vec2, Vec2, u8)>,
+    ) {
+        self.owed.resize(emitters.len(), 0.0);
+        for (emitter, owed) in emitters.iter().zip(&mut self.owed) {
+            if !emitter.active {
+                continue;
+            }
+            *owed += emitter.rate * dt;
+            let due = *owed as usize;
+            *owed -= due as f32;
+            let speed = emitter.velocity.length();
+            let axis = emitter.velocity.try_normalize().unwrap_or(Vec2::Y);
+            for _ in 0..due.min(room.saturating_sub(out.len())) {
+                let angle = emitter.spread * (2.0 * next(&mut self.rng) - 1.0);
+                let v = Vec2::from_angle(angle).rotate(axis) * speed;
+                let across = axis.perp() * emitter.width * (next(&mut self.rng) - 0.5);
+                let along = v * (dt * next(&mut self.rng));
+                out.push((emitter.position + across + along, v, emitter.phase));
+            }
+        }
+    }
+
+    /// Forget the carry-over of removed emitters.
+    pub(crate) fn clear(&mut self) {
+        self.owed.clear();
+    }
+}
+
+/// Uniform in [0, 1) (xorshift32; deterministic run to run).
+fn next(state: &mut u32) -> f32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 17;
+    *state ^= *state << 5;
+    (*state >> 8) as f32 / (1u32 << 24) as f32
+}
+
+/// A sink: particles inside `region` (where its SDF is negative) are deleted
+/// at the end of every step.
+#[derive(Clone, Debug)]
+pub struct Drain {
+    pub region: Sdf,
+}
+
+impl Drain {
+    pub fn new(region: Sdf) -> Self {
+        Self { region }
+    }
+
+    pub(crate) fn contains(&self, p: Vec2) -> bool {
+        self.region.eval(p).0 < 0.0
+    }
+}
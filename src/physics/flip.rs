@@ -0,0 +1,951 @@
+//! FLIP/PIC (Zhu & Bridson, SIGGRAPH 2005) on a staggered MAC grid as a
+//! `FluidSolver` strategy: the second grid method next to MLS-MPM, and the
+//! only one here whose incompressibility is an exact pressure projection
+//! rather than a stiff equation of state.
+//!
+//! Per substep:
+//!   1. **P2G** — splat the particle velocities onto the MAC faces (x
+//!      components on the vertical faces, y on the horizontal ones) with
+//!      bilinear weights, and keep a copy of the result.
+//!   2. classify the cells: solid where the center lies inside an obstacle or
+//!      a body or outside the container, fluid where there are particles,
+//!      air elsewhere.
+//!   3. gravity on every face, then the boundary conditions: the box walls
+//!      zero the normal component, and faces near an obstacle, a body or the
+//!      container lose their into-surface velocity (`Boundaries::face_bc`,
+//!      which books the momentum against the bodies as MPM's node BC does).
+//!   4. **pressure projection** — solve the Poisson equation on the fluid
+//!      cells (p = 0 in air, faces against a solid held fixed) by conjugate
+//!      gradients with a modified incomplete Cholesky preconditioner
+//!      (Bridson 2015, §5.4), then subtract the pressure gradient, which
+//!      leaves every fluid cell divergence-free.
+//!   5. **G2P** — each particle takes a blend of the new grid velocity (PIC)
+//!      and its own velocity plus the grid's change over the step (FLIP),
+//!      advects with it, and is clamped out of the walls and obstacles.
+//!
+//! PIC alone re-samples every velocity through the grid each step and damps
+//! the flow like syrup; FLIP transfers only the change, so the particles keep
+//! their own detail and the fluid splashes. `FlipParams::flip_ratio` blends
+//! the two, a little PIC keeping FLIP's particle noise down.
+//!
+//! The projection keeps the *grid* divergence-free, not the particle
+//! density, so a FLIP fluid slowly bunches up and loses volume. An interior
+//! cell holding more (or fewer) particles than it was seeded with is
+//! therefore given a small expansion (or contraction) as the divergence it
+//! must reach (`FlipParams::drift`), which evens them back out.
+//!
+//! Each connected body of fluid cells is solved on its own, so a drop in
+//! flight neither waits on the pool's convergence nor changes its answer.
+//! Phases are ignored: the fluid has unit density throughout.
+
+use glam::Vec2;
+
+use super::rigid::FLUID_PARTICLE_MASS;
+use super::{
+    clamp_wall, report, retain_flagged, Boundaries, Domain, Edge, FluidSolver, ShareData,
+    PHYS_TIME_STEP,
+};
+use crate::constants::BALL_SIZE;
+
+// Cell size: two particle spacings, so a rest-packed fluid seeds the usual
+// 2×2 particles per cell. Cells are stretched so whole ones tile the box
+// (`Domain::tiling`).
+const FLIP_DX: f32 = 4.0 * BALL_SIZE;
+// MIC(0) tuning constants (Bridson 2015, fig. 5.6): τ blends in the modified
+// part of the factorization, σ guards against a small pivot.
+const MIC_TAU: f32 = 0.97;
+const MIC_SIGMA: f32 = 0.25;
+// No fluid neighbor in that direction.
+const NONE: usize = usize::MAX;
+
+/// Tunable FLIP coefficients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FlipParams {
+    /// FLIP/PIC blend: 1 = pure FLIP (lively, noisy), 0 = pure PIC (smooth,
+    /// heavily damped).
+    pub flip_ratio: f32,
+    /// The pressure solve stops once the largest residual divergence is
+    /// this fraction of the largest one it started from.
+    pub tolerance: f32,
+    /// Cap on conjugate-gradient iterations per body of fluid per substep.
+    pub max_iters: usize,
+    /// Fraction of an interior cell's excess (or missing) particle density
+    /// turned into expansion (or contraction) each substep (0 = no drift
+    /// correction).
+    pub drift: f32,
+}
+
+impl Default for FlipParams {
+    fn default() -> Self {
+        Self {
+            flip_ratio: 0.95,
+            tolerance: 1e-4,
+            max_iters: 200,
+            drift: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Air,
+    Fluid,
+    Solid,
+}
+
+/// What lies across one side of a cell.
+#[derive(Clone, Copy)]
+enum Side {
+    Cell(usize),
+    /// A wall edge of the box.
+    Wall,
+    /// An outflow edge: open air.
+    Open,
+}
+
+/// One staggered sample lattice: `w × h` samples, sample (i, j) sitting at
+/// `(i, j) + shift` in cell units. A periodic axis wraps at `wrap` samples
+/// (0 = clamp at the edge instead).
+struct Lattice {
+    w: usize,
+    h: usize,
+    shift: Vec2,
+    wrap: [usize; 2],
+}
+
+impl Lattice {
+    #[inline(always)]
+    fn index(&self, i: isize, j: isize) -> usize {
+        axis(j, self.h, self.wrap[1]) * self.w + axis(i, self.w, self.wrap[0])
+    }
+
+    /// The four samples around `g` (in cell units) and their bilinear
+    /// weights.
+    #[inline(always)]
+    fn stencil(&self, g: Vec2) -> [(usize, f32); 4] {
+        let s = g - self.shift;
+        let base = s.floor();
+        let t = s - base;
+        let (i, j) = (base.x as isize, base.y as isize);
+        [
+            (self.index(i, j), (1.0 - t.x) * (1.0 - t.y)),
+            (self.index(i + 1, j), t.x * (1.0 - t.y)),
+            (self.index(i, j + 1), (1.0 - t.x) * t.y),
+            (self.index(i + 1, j + 1), t.x * t.y),
+        ]
+    }
+
+    /// Cell-unit position of sample `k`.
+    #[inline(always)]
+    fn position(&self, k: usize) -> Vec2 {
+        Vec2::new((k % self.w) as f32, (k / self.w) as f32) + self.shift
+    }
+
+    fn interpolate(&self, field: &[f32], g: Vec2) -> f32 {
+        self.stencil(g).iter().map(|&(k, w)| field[k] * w).sum()
+    }
+}
+
+#[inline(always)]
+fn axis(k: isize, n: usize, wrap: usize) -> usize {
+    if wrap > 0 {
+        k.rem_euclid(wrap as isize) as usize
+    } else {
+        k.clamp(0, n as isize - 1) as usize
+    }
+}
+
+/// FLIP solver state: the particle velocities, and the MAC grid and
+/// pressure-solve scratch, rebuilt every substep.
+pub struct Flip {
+    params: FlipParams,
+    vel: Vec<Vec2>,
+    // Grid: `nx × ny` cells of `dx`, for the domain they were sized from.
+    nx: usize,
+    ny: usize,
+    dx: Vec2,
+    domain: Domain,
+    us: Lattice,
+    vs: Lattice,
+    centers: Lattice,
+    // Face velocities (x on the `us` faces, y on the `vs` faces), their
+    // values straight after P2G, and the P2G weight each face received.
+    u: Vec<f32>,
+    v: Vec<f32>,
+    u_old: Vec<f32>,
+    v_old: Vec<f32>,
+    u_weight: Vec<f32>,
+    v_weight: Vec<f32>,
+    cells: Vec<Cell>,
+    // Particles per cell, splatted bilinearly onto the cell centers.
+    fill: Vec<f32>,
+    // Poisson matrix per fluid cell: the diagonal, the fluid neighbor in
+    // each direction (−x, +x, −y, +y) and whether any neighbor is air.
+    diag: Vec<f32>,
+    nbr: Vec<[usize; 4]>,
+    open: Vec<bool>,
+    // Connected bodies of fluid: each cell's label, and the cells grouped
+    // by label in scan order.
+    label: Vec<u32>,
+    bodies: Vec<usize>,
+    body_start: Vec<usize>,
+    // Solve state: pressure, right-hand side, residual, preconditioned
+    // residual, search direction, its image under the matrix, the MIC(0)
+    // factor and the triangular-solve scratch.
+    pressure: Vec<f32>,
+    rhs: Vec<f32>,
+    res: Vec<f32>,
+    aux: Vec<f32>,
+    search: Vec<f32>,
+    image: Vec<f32>,
+    precon: Vec<f32>,
+    sweep: Vec<f32>,
+}
+
+impl Default for Flip {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Flip {
+    pub fn new() -> Self {
+        let lattice = |shift| Lattice {
+            w: 0,
+            h: 0,
+            shift,
+            wrap: [0; 2],
+        };
+        Self {
+            params: FlipParams::default(),
+            vel: Vec::new(),
+            nx: 0,
+            ny: 0,
+            dx: Vec2::splat(FLIP_DX),
+            domain: Domain::default(),
+            us: lattice(Vec2::new(0.0, 0.5)),
+            vs: lattice(Vec2::new(0.5, 0.0)),
+            centers: lattice(Vec2::splat(0.5)),
+            u: Vec::new(),
+            v: Vec::new(),
+            u_old: Vec::new(),
+            v_old: Vec::new(),
+            u_weight: Vec::new(),
+            v_weight: Vec::new(),
+            cells: Vec::new(),
+            fill: Vec::new(),
+            diag: Vec::new(),
+            nbr: Vec::new(),
+            open: Vec::new(),
+            label: Vec::new(),
+            bodies: Vec::new(),
+            body_start: Vec::new(),
+            pressure: Vec::new(),
+            rhs: Vec::new(),
+            res: Vec::new(),
+            aux: Vec::new(),
+            search: Vec::new(),
+            image: Vec::new(),
+            precon: Vec::new(),
+            sweep: Vec::new(),
+        }
+    }
+
+    pub fn set_params(&mut self, params: FlipParams) {
+        self.params = params;
+    }
+
+    /// Size the MAC grid to tile `domain` if it isn't already.
+    fn fit_grid(&mut self, domain: &Domain) {
+        if self.domain == *domain && !self.cells.is_empty() {
+            return;
+        }
+        let (nx, ny) = domain.tiling(FLIP_DX);
+        let (px, py) = (domain.periodic_x(), domain.periodic_y());
+        let wrap = [if px { nx } else { 0 }, if py { ny } else { 0 }];
+        self.nx = nx;
+        self.ny = ny;
+        self.dx = Vec2::new(domain.width / nx as f32, domain.height / ny as f32);
+        self.us = Lattice {
+            w: if px { nx } else { nx + 1 },
+            h: ny,
+            wrap,
+            ..self.us
+        };
+        self.vs = Lattice {
+            w: nx,
+            h: if py { ny } else { ny + 1 },
+            wrap,
+            ..self.vs
+        };
+        self.centers = Lattice {
+            w: nx,
+            h: ny,
+            wrap,
+            ..self.centers
+        };
+        let (nu, nv, nc) = (self.us.w * self.us.h, self.vs.w * self.vs.h, nx * ny);
+        for field in [&mut self.u, &mut self.u_old, &mut self.u_weight] {
+            *field = vec![0.0; nu];
+        }
+        for field in [&mut self.v, &mut self.v_old, &mut self.v_weight] {
+            *field = vec![0.0; nv];
+        }
+        for field in [
+            &mut self.fill,
+            &mut self.diag,
+            &mut self.pressure,
+            &mut self.rhs,
+            &mut self.res,
+            &mut self.aux,
+            &mut self.search,
+            &mut self.image,
+            &mut self.precon,
+            &mut self.sweep,
+        ] {
+            *field = vec![0.0; nc];
+        }
+        self.cells = vec![Cell::Air; nc];
+        self.nbr = vec![[NONE; 4]; nc];
+        self.open = vec![false; nc];
+        self.label = vec![u32::MAX; nc];
+        self.domain = *domain;
+    }
+
+    fn ensure_sized(&mut self, x: &[Vec2], c_opos: &[Vec2], dt: f32) {
+        let n = x.len();
+        if self.vel.len() != n {
+            // A newcomer's Verlet displacement becomes its FLIP velocity; the
+            // next P2G splat carries it onto the faces.
+            let seen = self.vel.len().min(n);
+            self.vel.truncate(n);
+            self.vel.extend((seen..n).map(|i| (x[i] - c_opos[i]) / dt));
+        }
+    }
+
+    /// The cell containing `x`.
+    #[inline(always)]
+    fn cell_of(&self, x: Vec2) -> usize {
+        let g = x / self.dx;
+        let i = (g.x as isize).clamp(0, self.nx as isize - 1) as usize;
+        let j = (g.y as isize).clamp(0, self.ny as isize - 1) as usize;
+        j * self.nx + i
+    }
+
+    /// What lies across the (`di`, `dj`) side of cell `c`.
+    #[inline(always)]
+    fn side(&self, c: usize, di: isize, dj: isize) -> Side {
+        let (i, j) = (c % self.nx, c / self.nx);
+        let step = if di != 0 {
+            self.domain.step_x(i, di, self.nx).map(|(i, _)| (i, j))
+        } else {
+            self.domain.step_y(j, dj, self.ny).map(|(j, _)| (i, j))
+        };
+        match step {
+            Some((i, j)) => Side::Cell(j * self.nx + i),
+            None => {
+                let edge = match (di, dj) {
+                    (-1, _) => self.domain.left,
+                    (1, _) => self.domain.right,
+                    (_, -1) => self.domain.top,
+                    _ => self.domain.bottom,
+                };
+                if edge == Edge::Outflow {
+                    Side::Open
+                } else {
+                    Side::Wall
+                }
+            }
+        }
+    }
+
+    /// The sides on either side of x-face `k` (the −x one first).
+    fn u_sides(&self, k: usize) -> (Side, Side) {
+        let (i, j) = (k % self.us.w, k / self.us.w);
+        if i < self.nx {
+            let c = j * self.nx + i;
+            (self.side(c, -1, 0), Side::Cell(c))
+        } else {
+            let c = j * self.nx + self.nx - 1;
+            (Side::Cell(c), self.side(c, 1, 0))
+        }
+    }
+
+    /// The sides on either side of y-face `k` (the −y one first).
+    fn v_sides(&self, k: usize) -> (Side, Side) {
+        let (i, j) = (k % self.vs.w, k / self.vs.w);
+        if j < self.ny {
+            let c = j * self.nx + i;
+            (self.side(c, 0, -1), Side::Cell(c))
+        } else {
+            let c = (self.ny - 1) * self.nx + i;
+            (Side::Cell(c), self.side(c, 0, 1))
+        }
+    }
+
+    fn p2g(&mut self, x: &[Vec2], bounds: &Boundaries) {
+        for field in [&mut self.u, &mut self.u_weight] {
+            field.iter_mut().for_each(|f| *f = 0.0);
+        }
+        for field in [&mut self.v, &mut self.v_weight] {
+            field.iter_mut().for_each(|f| *f = 0.0);
+        }
+        self.fill.iter_mut().for_each(|f| *f = 0.0);
+        for (&xp, &vp) in x.iter().zip(&self.vel) {
+            let g = xp / self.dx;
+            for (k, w) in self.us.stencil(g) {
+                self.u[k] += w * vp.x;
+                self.u_weight[k] += w;
+            }
+            for (k, w) in self.vs.stencil(g) {
+                self.v[k] += w * vp.y;
+                self.v_weight[k] += w;
+            }
+            for (k, w) in self.centers.stencil(g) {
+                self.fill[k] += w;
+            }
+        }
+        for (u, &w) in self.u.iter_mut().zip(&self.u_weight) {
+            if w > 0.0 {
+                *u /= w;
+            }
+        }
+        for (v, &w) in self.v.iter_mut().zip(&self.v_weight) {
+            if w > 0.0 {
+                *v /= w;
+            }
+        }
+        self.u_old.copy_from_slice(&self.u);
+        self.v_old.copy_from_slice(&self.v);
+
+        // Solid cells first, so a particle pushed out just beyond a surface
+        // does not turn the solid's cell into fluid.
+        for c in 0..self.cells.len() {
+            let center = self.centers.position(c) * self.dx;
+            self.cells[c] = if !bounds.is_empty() && bounds.distance(center) < 0.0 {
+                Cell::Solid
+            } else {
+                Cell::Air
+            };
+        }
+        for &xp in x {
+            let c = self.cell_of(xp);
+            if self.cells[c] == Cell::Air {
+                self.cells[c] = Cell::Fluid;
+            }
+        }
+    }
+
+    /// Gravity, then the wall and obstacle boundary conditions, on every
+    /// face the particles reached.
+    fn forces(&mut self, gravity: Vec2, dt: f32, bounds: &mut Boundaries) {
+        // Only faces that caught particle weight carry fluid; gravity is the
+        // granular integrator's effective one (see `sph::effective_gravity`).
+        let dv = gravity * (dt / PHYS_TIME_STEP);
+        for (u, &w) in self.u.iter_mut().zip(&self.u_weight) {
+            if w > 0.0 {
+                *u += dv.x;
+            }
+        }
+        for (v, &w) in self.v.iter_mut().zip(&self.v_weight) {
+            if w > 0.0 {
+                *v += dv.y;
+            }
+        }
+        let (left, right) = self.domain.walls_x();
+        let (top, bottom) = self.domain.walls_y();
+        for j in 0..self.ny {
+            if left {
+                self.u[j * self.us.w] = 0.0;
+            }
+            if right {
+                self.u[j * self.us.w + self.nx] = 0.0;
+            }
+        }
+        for i in 0..self.nx {
+            if top {
+                self.v[i] = 0.0;
+            }
+            if bottom {
+                self.v[self.ny * self.nx + i] = 0.0;
+            }
+        }
+        if bounds.is_empty() {
+            return;
+        }
+        let band = 0.5 * self.dx.max_element();
+        for k in 0..self.u.len() {
+            if self.u_weight[k] > 0.0 {
+                let g = self.us.position(k);
+                let full = Vec2::new(self.u[k], self.vs.interpolate(&self.v, g));
+                let mass = self.u_weight[k] * FLUID_PARTICLE_MASS;
+                self.u[k] = bounds.face_bc(g * self.dx, full, 0, band, mass);
+            }
+        }
+        for k in 0..self.v.len() {
+            if self.v_weight[k] > 0.0 {
+                let g = self.vs.position(k);
+                let full = Vec2::new(self.us.interpolate(&self.u, g), self.v[k]);
+                let mass = self.v_weight[k] * FLUID_PARTICLE_MASS;
+                self.v[k] = bounds.face_bc(g * self.dx, full, 1, band, mass);
+            }
+        }
+    }
+
+    /// Assemble the Poisson system on the fluid cells and group them into
+    /// connected bodies of fluid.
+    fn assemble(&mut self, dt: f32) {
+        let inv2 = Vec2::ONE / (self.dx * self.dx);
+        let rest = self.dx.x * self.dx.y / FLUID_PARTICLE_MASS;
+        let dirs = [
+            (-1, 0, inv2.x),
+            (1, 0, inv2.x),
+            (0, -1, inv2.y),
+            (0, 1, inv2.y),
+        ];
+        for c in 0..self.cells.len() {
+            self.label[c] = u32::MAX;
+            if self.cells[c] != Cell::Fluid {
+                continue;
+            }
+            let (mut diag, mut nbr, mut open) = (0.0, [NONE; 4], false);
+            for (d, &(di, dj, coeff)) in dirs.iter().enumerate() {
+                match self.side(c, di, dj) {
+                    Side::Cell(n) => match self.cells[n] {
+                        Cell::Fluid => {
+                            diag += coeff;
+                            nbr[d] = n;
+                        }
+                        Cell::Air => {
+                            diag += coeff;
+                            open = true;
+                        }
+                        Cell::Solid => {}
+                    },
+                    Side::Open => {
+                        diag += coeff;
+                        open = true;
+                    }
+                    Side::Wall => {}
+                }
+            }
+            self.diag[c] = diag;
+            self.nbr[c] = nbr;
+            self.open[c] = open;
+            let (i, j) = (c % self.nx, c / self.nx);
+            let div = (self.u[self.us.index(i as isize + 1, j as isize)]
+                - self.u[self.us.index(i as isize, j as isize)])
+                / self.dx.x
+                + (self.v[self.vs.index(i as isize, j as isize + 1)]
+                    - self.v[self.vs.index(i as isize, j as isize)])
+                    / self.dx.y;
+            // Interior cells only: surface cells are part-filled by nature,
+            // and pushing them apart shoves the particles at their edges
+            // straight out into the air. Jammed cells relax at a capped rate.
+            let excess = if open {
+                0.0
+            } else {
+                (self.fill[c] / rest - 1.0).min(1.0)
+            };
+            self.rhs[c] = if diag > 0.0 {
+                self.params.drift * excess / dt - div
+            } else {
+                0.0
+            };
+        }
+
+        // Flood-fill the bodies of fluid, numbered in scan order, then
+        // bucket their cells by label (a counting sort keeps scan order).
+        let mut count = 0u32;
+        let mut stack = Vec::new();
+        for c in 0..self.cells.len() {
+            if self.cells[c] != Cell::Fluid || self.label[c] != u32::MAX {
+                continue;
+            }
+            self.label[c] = count;
+            stack.push(c);
+            while let Some(a) = stack.pop() {
+                for n in self.nbr[a] {
+                    if n != NONE && self.label[n] == u32::MAX {
+                        self.label[n] = count;
+                        stack.push(n);
+                    }
+                }
+            }
+            count += 1;
+        }
+        self.body_start.clear();
+        self.body_start.resize(count as usize + 1, 0);
+        for &l in &self.label {
+            if l != u32::MAX {
+                self.body_start[l as usize + 1] += 1;
+            }
+        }
+        for b in 0..count as usize {
+            self.body_start[b + 1] += self.body_start[b];
+        }
+        let mut next = self.body_start.clone();
+        self.bodies.resize(self.body_start[count as usize], 0);
+        for (c, &l) in self.label.iter().enumerate() {
+            if l != u32::MAX {
+                self.bodies[next[l as usize]] = c;
+                next[l as usize] += 1;
+            }
+        }
+    }
+
+    /// The MIC(0) coefficient coupling cell `c` to its +x (`d` = 1) or +y
+    /// (`d` = 3) neighbor, zero across a periodic seam.
+    #[inline(always)]
+    fn upper(&self, c: usize, d: usize) -> f32 {
+        let n = self.nbr[c][d];
+        let seam = if d == 1 {
+            c % self.nx + 1 == self.nx
+        } else {
+            c / self.nx + 1 == self.ny
+        };
+        if n == NONE || seam {
+            0.0
+        } else if d == 1 {
+            -1.0 / (self.dx.x * self.dx.x)
+        } else {
+            -1.0 / (self.dx.y * self.dx.y)
+        }
+    }
+
+    /// The −x (`d` = 0) or −y (`d` = 2) fluid neighbor of `c` that precedes
+    /// it in scan order, if any.
+    #[inline(always)]
+    fn lower(&self, c: usize, d: usize) -> Option<usize> {
+        let n = self.nbr[c][d];
+        let seam = if d == 0 {
+            c.is_multiple_of(self.nx)
+        } else {
+            c / self.nx == 0
+        };
+        (n != NONE && !seam).then_some(n)
+    }
+
+    fn factor(&mut self, cells: &[usize]) {
+        for &c in cells {
+            let d0 = self.diag[c];
+            let mut e = d0;
+            if let Some(l) = self.lower(c, 0) {
+                let (a, b, p) = (self.upper(l, 1), self.upper(l, 3), self.precon[l]);
+                e -= (a * p) * (a * p) + MIC_TAU * a * b * p * p;
+            }
+            if let Some(l) = self.lower(c, 2) {
+                let (a, b, p) = (self.upper(l, 3), self.upper(l, 1), self.precon[l]);
+                e -= (a * p) * (a * p) + MIC_TAU * a * b * p * p;
+            }
+            if e < MIC_SIGMA * d0 {
+                e = d0;
+            }
+            self.precon[c] = if e > 0.0 { e.sqrt().recip() } else { 0.0 };
+        }
+    }
+
+    /// `aux` = M⁻¹ `res` by the two triangular solves of the MIC(0) factor.
+    fn precondition(&mut self, cells: &[usize]) {
+        for &c in cells {
+            let mut t = self.res[c];
+            if let Some(l) = self.lower(c, 0) {
+                t -= self.upper(l, 1) * self.precon[l] * self.sweep[l];
+            }
+            if let Some(l) = self.lower(c, 2) {
+                t -= self.upper(l, 3) * self.precon[l] * self.sweep[l];
+            }
+            self.sweep[c] = t * self.precon[c];
+        }
+        for &c in cells.iter().rev() {
+            let mut t = self.sweep[c];
+            for d in [1, 3] {
+                let a = self.upper(c, d);
+                if a != 0.0 {
+                    t -= a * self.precon[c] * self.aux[self.nbr[c][d]];
+                }
+            }
+            self.aux[c] = t * self.precon[c];
+        }
+    }
+
+    /// `image` = A `search` over `cells`.
+    fn apply(&mut self, cells: &[usize]) {
+        let inv2 = Vec2::ONE / (self.dx * self.dx);
+        let coeff = [inv2.x, inv2.x, inv2.y, inv2.y];
+        for &c in cells {
+            let mut q = self.diag[c] * self.search[c];
+            for (d, &n) in self.nbr[c].iter().enumerate() {
+                if n != NONE {
+                    q -= coeff[d] * self.search[n];
+                }
+            }
+            self.image[c] = q;
+        }
+    }
+
+    /// Solve for the pressure of one body of fluid by MIC(0)-preconditioned
+    /// conjugate gradients. Returns the iterations taken.
+    fn solve(&mut self, cells: &[usize]) -> usize {
+        if !cells.iter().any(|&c| self.open[c]) {
+            // Sealed in by solids: the pressure is only defined up to a
+            // constant, and only a zero-mean divergence can be removed.
+            let mean = cells.iter().map(|&c| self.rhs[c]).sum::<f32>() / cells.len() as f32;
+            cells.iter().for_each(|&c| self.rhs[c] -= mean);
+        }
+        let dot = |a: &[f32], b: &[f32]| cells.iter().map(|&c| (a[c] * b[c]) as f64).sum::<f64>();
+        let norm = |a: &[f32]| cells.iter().map(|&c| a[c].abs()).fold(0.0f32, f32::max);
+        for &c in cells {
+            self.pressure[c] = 0.0;
+            self.res[c] = self.rhs[c];
+        }
+        // Below ~1e-6/s there is nothing to remove (and a denormal residual
+        // would turn the step length into 0/0).
+        let start = norm(&self.rhs);
+        if start < 1e-6 {
+            return 0;
+        }
+        let target = self.params.tolerance * start;
+        self.factor(cells);
+        self.precondition(cells);
+        cells.iter().for_each(|&c| self.search[c] = self.aux[c]);
+        let mut sigma = dot(&self.aux, &self.res);
+        for iter in 1..=self.params.max_iters {
+            self.apply(cells);
+            let curvature = dot(&self.search, &self.image);
+            if curvature <= 0.0 {
+                return iter;
+            }
+            let alpha = (sigma / curvature) as f32;
+            for &c in cells {
+                self.pressure[c] += alpha * self.search[c];
+                self.res[c] -= alpha * self.image[c];
+            }
+            if norm(&self.res) <= target {
+                return iter;
+            }
+            self.precondition(cells);
+            let sigma_new = dot(&self.aux, &self.res);
+            let beta = (sigma_new / sigma) as f32;
+            for &c in cells {
+                self.search[c] = self.aux[c] + beta * self.search[c];
+            }
+            sigma = sigma_new;
+        }
+        self.params.max_iters
+    }
+
+    /// Pressure of the cell across a face side, or `None` for a solid (the
+    /// face is then held fixed).
+    #[inline(always)]
+    fn side_pressure(&self, s: Side) -> Option<(f32, bool)> {
+        match s {
+            Side::Cell(c) => match self.cells[c] {
+                Cell::Fluid => Some((self.pressure[c], true)),
+                Cell::Air => Some((0.0, false)),
+                Cell::Solid => None,
+            },
+            Side::Open => Some((0.0, false)),
+            Side::Wall => None,
+        }
+    }
+
+    /// Solve every body of fluid, then subtract the pressure gradient from
+    /// the faces between a fluid cell and a fluid or air one. Returns the
+    /// most iterations any body took.
+    fn project(&mut self, dt: f32, bounds: &mut Boundaries) -> usize {
+        self.assemble(dt);
+        let bodies = std::mem::take(&mut self.bodies);
+        let mut iters = 0;
+        for b in 0..self.body_start.len() - 1 {
+            let cells = &bodies[self.body_start[b]..self.body_start[b + 1]];
+            iters = iters.max(self.solve(cells));
+        }
+        self.bodies = bodies;
+        for k in 0..self.u.len() {
+            let (a, b) = self.u_sides(k);
+            if let (Some((pa, fa)), Some((pb, fb))) = (self.side_pressure(a), self.side_pressure(b))
+            {
+                if fa || fb {
+                    self.u[k] -= (pb - pa) / self.dx.x;
+                }
+            }
+        }
+        for k in 0..self.v.len() {
+            let (a, b) = self.v_sides(k);
+            if let (Some((pa, fa)), Some((pb, fb))) = (self.side_pressure(a), self.side_pressure(b))
+            {
+                if fa || fb {
+                    self.v[k] -= (pb - pa) / self.dx.y;
+                }
+            }
+        }
+        if !bounds.is_empty() {
+            self.book_loads(bounds);
+        }
+        iters
+    }
+
+    /// The faces against a solid keep their boundary velocity, so the
+    /// pressure never acts on them: book it against the bodies instead. A
+    /// fluid cell at pressure `p` pushes on each solid face with `p` × the
+    /// face length of momentum per step (unit area density).
+    fn book_loads(&self, bounds: &mut Boundaries) {
+        let reach = self.dx.max_element();
+        for k in 0..self.u.len() {
+            let (a, b) = self.u_sides(k);
+            let x = self.us.position(k) * self.dx;
+            self.book_load(a, b, x, Vec2::X * self.dx.y, bounds, reach);
+        }
+        for k in 0..self.v.len() {
+            let (a, b) = self.v_sides(k);
+            let x = self.vs.position(k) * self.dx;
+            self.book_load(a, b, x, Vec2::Y * self.dx.x, bounds, reach);
+        }
+    }
+
+    /// One face of [`Flip::book_loads`]: `a` and `b` are its lower and upper
+    /// sides and `span` its length along the axis it faces.
+    #[inline(always)]
+    fn book_load(
+        &self,
+        a: Side,
+        b: Side,
+        x: Vec2,
+        span: Vec2,
+        bounds: &mut Boundaries,
+        reach: f32,
+    ) {
+        let kind = |s: Side| match s {
+            Side::Cell(c) => Some(self.cells[c]),
+            _ => None,
+        };
+        match (kind(a), kind(b), a, b) {
+            (Some(Cell::Fluid), Some(Cell::Solid), Side::Cell(c), _) => {
+                bounds.face_load(x, -span * self.pressure[c], reach)
+            }
+            (Some(Cell::Solid), Some(Cell::Fluid), _, Side::Cell(c)) => {
+                bounds.face_load(x, span * self.pressure[c], reach)
+            }
+            _ => {}
+        }
+    }
+
+    fn g2p(&mut self, x: &mut [Vec2], dt: f32, bounds: &Boundaries) {
+        let ratio = self.params.flip_ratio;
+        for (xp, vp) in x.iter_mut().zip(self.vel.iter_mut()) {
+            let g = *xp / self.dx;
+            let pic = Vec2::new(
+                self.us.interpolate(&self.u, g),
+                self.vs.interpolate(&self.v, g),
+            );
+            let old = Vec2::new(
+                self.us.interpolate(&self.u_old, g),
+                self.vs.interpolate(&self.v_old, g),
+            );
+            let flip = *vp + pic - old;
+            *vp = pic + (flip - pic) * ratio;
+
+            // Move with the blended velocity. The face BCs already removed
+            // the into-wall component, so the clamp and obstacle projection
+            // only catch the sub-cell overshoot of a single step.
+            let mut p = *xp + *vp * dt;
+            clamp_wall(&mut p, bounds.domain());
+            bounds.clamp(&mut p, BALL_SIZE);
+            *xp = bounds.domain().wrap(p);
+        }
+    }
+}
+
+impl FluidSolver for Flip {
+    fn name(&self) -> &'static str {
+        "FLIP"
+    }
+
+    fn set_flip_params(&mut self, params: FlipParams) {
+        self.set_params(params);
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.vel, keep);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
+        let n = share.c_pos.len();
+        if n == 0 {
+            return;
+        }
+        c_opos.resize(n, Vec2::ZERO);
+        self.ensure_sized(&share.c_pos, c_opos, dt);
+
+        self.fit_grid(bounds.domain());
+        self.p2g(&share.c_pos, bounds);
+        self.forces(gravity, dt, bounds);
+        let iters = self.project(dt, bounds);
+        self.g2p(&mut share.c_pos, dt, bounds);
+
+        // Mean particles per fluid cell over the seeding density. Surface
+        // cells are part-filled, so a settled pool reads a little under 1.
+        let rest = self.dx.x * self.dx.y / FLUID_PARTICLE_MASS;
+        let fluid = self.cells.iter().filter(|&&c| c == Cell::Fluid).count();
+        let ratio = n as f32 / (fluid.max(1) as f32 * rest);
+        report(share, c_opos, &self.vel, dt, ratio);
+        share.perf_stats.pressure_iters = iters;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::tests::dam_break_scene;
+    use crate::physics::{ShareData, Strategy, PHYS_TIME_STEP};
+
+    /// The shared dam break ([`dam_break_scene`]) under FLIP `params`,
+    /// stepped `steps` times. Returns the final shared state.
+    fn dam_break(params: FlipParams, steps: usize) -> ShareData {
+        let (mut physics, mut share) = dam_break_scene(Strategy::Flip);
+        physics.set_flip_params(params);
+        for _ in 0..steps {
+            physics.step(PHYS_TIME_STEP, &mut share);
+        }
+        share
+    }
+
+    #[test]
+    fn dam_break_settles_at_its_original_volume() {
+        let share = dam_break(FlipParams::default(), 2000);
+        // At unit area density 1200 particles fill 240 px × 180 px.
+        let n = share.c_pos.len() as f32;
+        let mean_y = share.c_pos.iter().map(|p| p.y).sum::<f32>() / n;
+        let depth = 2.0 * (600.0 - mean_y);
+        assert!(
+            (depth - 180.0).abs() < 9.0,
+            "settled depth {depth:.1} px, expected about 180"
+        );
+        assert!(share.perf_stats.mean_speed < 1.0);
+    }
+
+    #[test]
+    fn flip_keeps_more_motion_than_pic() {
+        let flip = dam_break(FlipParams::default(), 240);
+        let pic = dam_break(
+            FlipParams {
+                flip_ratio: 0.0,
+                ..Default::default()
+            },
+            240,
+        );
+        let (flip, pic) = (flip.perf_stats.mean_speed, pic.perf_stats.mean_speed);
+        assert!(
+            flip > 1.5 * pic,
+            "FLIP mean speed {flip:.2} vs PIC {pic:.2}"
+        );
+    }
+}
@@ -10,16 +10,23 @@
 //! Størmer–Verlet "previous position" that also encodes velocity — which is
 //! threaded in as a parameter so a strategy switch and the cannon can keep it
 //! consistent across models.
+//!
+//! Particles may carry their own radii (`ShareData::c_radius`, e.g. a 2:1
+//! mix for segregation studies). A pair then touches at r_i + r_j, its
+//! repulsion is the historical kernel at the distance scaled by
+//! 2·BALL_SIZE / (r_i + r_j), and each particle weighs (r/BALL_SIZE)² — unit
+//! area density, as in `rigid.rs`. While every radius is BALL_SIZE none of
+//! that runs: the kernels keep their constant contact distance.
 
 use glam::Vec2;
 use rayon::prelude::*;
 use std::time::Instant;
 
 use super::{
-    fast_rsqrt, resolve_wall_collision, wall_salt, FluidSolver, PerformanceStats, ShareData,
-    PHYS_TIME_STEP,
+    fast_rsqrt, resolve_wall_collision, retain_flagged, wall_salt, Boundaries, Domain, FluidSolver,
+    PerformanceStats, ShareData, PHYS_TIME_STEP,
 };
-use crate::constants::{BALL_SIZE, GRID_SIZE, X_LEN, Y_LEN};
+use crate::constants::{BALL_SIZE, GRID_SIZE};
 
 // The force model (stage 25): *local* repulsion with compact support, like
 // every particle water model (SPH kernels, PBF, Clavet 2005 — pressure in a
@@ -45,6 +52,33 @@ fn taper(dist_sq: f32) -> f32 {
     1.0 - u * u * (3.0 - 2.0 * u)
 }
 
+/// Capillary-bridge cohesion between wet grains: a liquid bridge pulls a pair
+/// together with its full `strength` up to contact and ruptures linearly
+/// over a `rupture` gap beyond it (the usual linear fit to the bridge force
+/// of Willett et al. 2000). The reach, contact + rupture, is capped at
+/// FORCE_CUTOFF so the pull stays exact inside the one-cell stencil, like
+/// the repulsion. `strength` is in the units of gravity (9.8 = a particle's
+/// weight at the default gravity).
+#[derive(Clone, Copy)]
+struct Bridge {
+    strength: f32,
+    inv_rupture: f32,
+}
+
+/// Longest bridge the one-cell stencil can hold, beyond contact: 1.5 px at
+/// the default sizes. `set_cohesion` clamps to it.
+const MAX_RUPTURE: f32 = FORCE_CUTOFF - 2.0 * BALL_SIZE;
+
+impl Bridge {
+    /// Pull between a pair of BALL_SIZE balls `dist` apart (> 0), as a
+    /// fraction of the separation: f = −dir·pull.
+    #[inline(always)]
+    fn pull(self, dist: f32) -> f32 {
+        let gap = (dist - 2.0 * BALL_SIZE).max(0.0);
+        self.strength * (1.0 - gap * self.inv_rupture).max(0.0) / dist
+    }
+}
+
 // Optimization constants
 const VERLET_SKIN_DISTANCE: f32 = BALL_SIZE * 0.5; // Extra distance for neighbor lists
                                                    // When neighbor lists have gone stale (something moved > skin/2), forces fall
@@ -93,11 +127,6 @@ const SOLVER_OMEGA: f32 = 1.0;
 // Runtime-tunable via `set_par_min_particles`.
 const PAR_MIN_PARTICLES: usize = 14_000;
 
-// Grid dimensions as integers (X_LEN/Y_LEN are f32 for legacy reasons)
-const GRID_W: usize = X_LEN as usize;
-const GRID_H: usize = Y_LEN as usize;
-const N_CELLS: usize = GRID_W * GRID_H;
-
 /// Verlet neighbor lists in flat CSR layout: one contiguous `neighbors` array
 /// with per-particle `start` offsets, instead of a Vec per particle. Rebuilt
 /// only when some particle has drifted more than half the skin distance from
@@ -138,8 +167,20 @@ impl VerletLists {
 /// a heap-allocated Vec per cell. Built once per step and reused across all
 /// constraint-solver iterations (particles move far less than a cell per
 /// step, so the one-cell stencil acts as a Verlet skin).
+///
+/// The domain is tiled by `w × h` cells of at least `min_cell` px (GRID_SIZE,
+/// scaled up with the largest particle radius), stretched per axis so the
+/// last column and row end exactly on the walls: the border cells are then
+/// the only ones a wall can touch, and the one-cell stencil still covers the
+/// interaction radius.
 struct CsrGrid {
-    cell_start: Vec<u32>, // N_CELLS + 1 offsets into `indices`
+    domain: Domain,
+    min_cell: f32,
+    w: usize,
+    h: usize,
+    cell_size: Vec2,
+    inv_cell: Vec2,
+    cell_start: Vec<u32>, // w·h + 1 offsets into `indices`
     cursor: Vec<u32>,     // scratch: per-cell write cursor / counts
     cell_of: Vec<u32>,    // per-particle cell id
     indices: Vec<u32>,    // particle ids grouped by cell
@@ -147,26 +188,26 @@ struct CsrGrid {
 }
 
 impl CsrGrid {
-    fn new() -> Self {
+    fn new(domain: &Domain, min_cell: f32) -> Self {
+        let (w, h) = domain.tiling(min_cell);
+        let cell_size = domain.size() / Vec2::new(w as f32, h as f32);
         Self {
-            cell_start: vec![0; N_CELLS + 1],
-            cursor: vec![0; N_CELLS],
+            domain: *domain,
+            min_cell,
+            w,
+            h,
+            cell_size,
+            inv_cell: cell_size.recip(),
+            cell_start: vec![0; w * h + 1],
+            cursor: vec![0; w * h],
             cell_of: Vec::new(),
             indices: Vec::new(),
             occupied: Vec::new(),
         }
     }
 
-    #[inline(always)]
-    fn cell_id(pos: Vec2) -> u32 {
-        const INV_GRID_SIZE: f32 = 1.0 / GRID_SIZE;
-        // `as usize` saturates negatives to 0, matching the old clamping
-        let x = ((pos.x * INV_GRID_SIZE) as usize).min(GRID_W - 1);
-        let y = ((pos.y * INV_GRID_SIZE) as usize).min(GRID_H - 1);
-        (y * GRID_W + x) as u32
-    }
-
     fn build(&mut self, positions: &[Vec2], parallel: bool) {
+        let (inv_cell, w, h) = (self.inv_cell, self.w, self.h);
         let n = positions.len();
         self.cell_of.resize(n, 0);
         self.indices.resize(n, 0);
@@ -181,7 +222,7 @@ impl CsrGrid {
                 .zip(positions.par_chunks(4096))
                 .for_each(|(cells, ps)| {
                     for (c, p) in cells.iter_mut().zip(ps) {
-                        *c = Self::cell_id(*p);
+                        *c = cell_id(*p, inv_cell, w, h);
                     }
                 });
             for &c in &self.cell_of {
@@ -189,7 +230,7 @@ impl CsrGrid {
             }
         } else {
             for (i, p) in positions.iter().enumerate() {
-                let c = Self::cell_id(*p);
+                let c = cell_id(*p, inv_cell, w, h);
                 self.cell_of[i] = c;
                 self.cursor[c as usize] += 1;
             }
@@ -197,7 +238,7 @@ impl CsrGrid {
 
         let mut sum = 0u32;
         self.occupied.clear();
-        for c in 0..N_CELLS {
+        for c in 0..w * h {
             let count = self.cursor[c];
             self.cell_start[c] = sum;
             self.cursor[c] = sum;
@@ -206,7 +247,7 @@ impl CsrGrid {
             }
             sum += count;
         }
-        self.cell_start[N_CELLS] = sum;
+        self.cell_start[w * h] = sum;
 
         for i in 0..n {
             let c = self.cell_of[i] as usize;
@@ -219,6 +260,126 @@ impl CsrGrid {
     fn cell(&self, c: usize) -> &[u32] {
         &self.indices[self.cell_start[c] as usize..self.cell_start[c + 1] as usize]
     }
+
+    /// Every pair of particles sharing a cell or in adjacent cells, once:
+    /// the forward half-stencil over the occupied cells, as `f((i, j), o)`
+    /// with `c_pos[j] + o` the image of j next to i.
+    #[inline(always)]
+    fn for_each_pair(&self, mut f: impl FnMut((usize, usize), Vec2)) {
+        for &cell in &self.occupied {
+            let cell = cell as usize;
+            let (x, y) = (cell % self.w, cell / self.w);
+            let currents = self.cell(cell);
+
+            for a in 0..currents.len() {
+                let i = currents[a] as usize;
+                for &j in &currents[a + 1..] {
+                    f((i, j as usize), Vec2::ZERO);
+                }
+            }
+
+            for (dx, dy) in [(1i32, 0i32), (-1, 1), (0, 1), (1, 1)] {
+                let Some((c, o)) = self.neighbor(x, y, dx, dy) else {
+                    continue;
+                };
+                for &i in currents {
+                    for &j in self.cell(c) {
+                        f((i as usize, j as usize), o);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Cell column/row of a world x/y, clamped to the grid.
+    #[inline(always)]
+    fn column(&self, x: f32) -> usize {
+        ((x * self.inv_cell.x) as usize).min(self.w - 1)
+    }
+
+    #[inline(always)]
+    fn row(&self, y: f32) -> usize {
+        ((y * self.inv_cell.y) as usize).min(self.h - 1)
+    }
+
+    /// Cell (x + dx, y + dy) and the offset that carries its particles to
+    /// their images next to cell (x, y) — zero unless the step crosses a
+    /// periodic seam — or `None` past a wall.
+    #[inline(always)]
+    fn neighbor(&self, x: usize, y: usize, dx: i32, dy: i32) -> Option<(usize, Vec2)> {
+        let (nx, ox) = self.domain.step_x(x, dx as isize, self.w)?;
+        let (ny, oy) = self.domain.step_y(y, dy as isize, self.h)?;
+        Some((ny * self.w + nx, Vec2::new(ox, oy)))
+    }
+
+    /// Whether a cell's 3×3 neighborhood wraps across a periodic seam.
+    #[inline(always)]
+    fn on_seam(&self, cell: usize) -> bool {
+        let (x, y) = (cell % self.w, cell / self.w);
+        (self.domain.periodic_x() && (x == 0 || x == self.w - 1))
+            || (self.domain.periodic_y() && (y == 0 || y == self.h - 1))
+    }
+
+    /// The part of a seam cell's neighborhood that `stencil_rows` leaves out:
+    /// the wrapped-around cells, grouped by the offset `o` that carries
+    /// their particles to their images (`f(o, rows)`, rows as in
+    /// `stencil_rows`). A gather for a particle at p over these rows with
+    /// the query moved to p − o sees minimum-image separations.
+    fn seam_rows(&self, cell: usize, mut f: impl FnMut(Vec2, &[(usize, usize); 3])) {
+        let (x, y) = (cell % self.w, cell / self.w);
+        let cs = &self.cell_start;
+        let x0 = x.saturating_sub(1);
+        let x1 = (x + 1).min(self.w - 1);
+        // The column across the x seam, if any.
+        let side = if !self.domain.periodic_x() {
+            None
+        } else if x == 0 {
+            Some((self.w - 1, -self.domain.width))
+        } else if x == self.w - 1 {
+            Some((0, self.domain.width))
+        } else {
+            None
+        };
+        let mut wrapped = [(0usize, 0usize); 3]; // columns x0..=x1, rows across the y seam
+        let mut beside = [(0usize, 0usize); 3]; // the side column, rows inside the grid
+        let mut corner = [(0usize, 0usize); 3]; // the side column, rows across the y seam
+        let mut oy = 0.0;
+        for dy in 0..3usize {
+            let Some((yy, o)) = self.domain.step_y(y, dy as isize - 1, self.h) else {
+                continue;
+            };
+            let base = yy * self.w;
+            if o != 0.0 {
+                oy = o;
+                wrapped[dy] = (cs[base + x0] as usize, cs[base + x1 + 1] as usize);
+            }
+            if let Some((sx, _)) = side {
+                let range = (cs[base + sx] as usize, cs[base + sx + 1] as usize);
+                if o != 0.0 {
+                    corner[dy] = range;
+                } else {
+                    beside[dy] = range;
+                }
+            }
+        }
+        if oy != 0.0 {
+            f(Vec2::new(0.0, oy), &wrapped);
+        }
+        if let Some((_, ox)) = side {
+            f(Vec2::new(ox, 0.0), &beside);
+            if oy != 0.0 {
+                f(Vec2::new(ox, oy), &corner);
+            }
+        }
+    }
+}
+
+#[inline(always)]
+fn cell_id(pos: Vec2, inv_cell: Vec2, w: usize, h: usize) -> u32 {
+    // `as usize` saturates negatives to 0, matching the old clamping
+    let x = ((pos.x * inv_cell.x) as usize).min(w - 1);
+    let y = ((pos.y * inv_cell.y) as usize).min(h - 1);
+    (y * w + x) as u32
 }
 
 /// The 3 contiguous packed ranges covering a cell's 3×3 neighborhood: cells
@@ -226,18 +387,18 @@ impl CsrGrid {
 /// concatenate (empty cells contribute empty subranges for free). This is what
 /// makes the packed kernels below stream memory sequentially and vectorize.
 #[inline(always)]
-fn stencil_rows(cell_start: &[u32], cell: usize) -> [(usize, usize); 3] {
-    let (x, y) = (cell % GRID_W, cell / GRID_W);
+fn stencil_rows(grid: &CsrGrid, cell: usize) -> [(usize, usize); 3] {
+    let (x, y) = (cell % grid.w, cell / grid.w);
     let x0 = x.saturating_sub(1);
-    let x1 = (x + 1).min(GRID_W - 1);
+    let x1 = (x + 1).min(grid.w - 1);
     let mut rows = [(0usize, 0usize); 3];
     for dy in 0..3usize {
         let yy = y as i32 + dy as i32 - 1;
-        if yy >= 0 && yy < GRID_H as i32 {
-            let base = yy as usize * GRID_W;
+        if yy >= 0 && yy < grid.h as i32 {
+            let base = yy as usize * grid.w;
             rows[dy] = (
-                cell_start[base + x0] as usize,
-                cell_start[base + x1 + 1] as usize,
+                grid.cell_start[base + x0] as usize,
+                grid.cell_start[base + x1 + 1] as usize,
             );
         }
     }
@@ -248,7 +409,11 @@ fn stencil_rows(cell_start: &[u32], cell: usize) -> [(usize, usize); 3] {
 /// vectorize the inner loop. `s8` = scale/8 (the historical accumulation
 /// factor). Self-interaction masks out via d² < BALL_SIZE² like any touching
 /// pair; the clamped denominator keeps masked lanes finite (never NaN·0).
+/// With `sized` (the packed radii and the particle's own), each pair is
+/// evaluated at its distance scaled to a pair of BALL_SIZE balls; with a
+/// `bridge`, wet pairs pull together as well.
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn gather_force(
     xa: f32,
     ya: f32,
@@ -256,14 +421,20 @@ fn gather_force(
     py: &[f32],
     rows: &[(usize, usize); 3],
     s8: f32,
+    sized: Option<(&[f32], f32)>,
+    bridge: Option<Bridge>,
 ) -> (f32, f32) {
     const BALL_SQ: f32 = BALL_SIZE * BALL_SIZE;
     let mut fx = 0.0f32;
     let mut fy = 0.0f32;
     for &(start, end) in rows {
         for k in start..end {
-            let dx = xa - px[k];
-            let dy = ya - py[k];
+            let s = match sized {
+                Some((pr, ra)) => 2.0 * BALL_SIZE / (ra + pr[k]),
+                None => 1.0,
+            };
+            let dx = (xa - px[k]) * s;
+            let dy = (ya - py[k]) * s;
             let d2 = dx * dx + dy * dy;
             let m = if d2 >= BALL_SQ && d2 < FORCE_CUTOFF_SQ {
                 1.0f32
@@ -271,7 +442,12 @@ fn gather_force(
                 0.0
             };
             let d2c = d2.max(BALL_SQ);
-            let w = (s8 * m * taper(d2)) / (d2c.sqrt() * d2c);
+            let mut w = (s8 * m * taper(d2)) / (d2c.sqrt() * d2c);
+            if let Some(b) = bridge {
+                // Self (d2 == 0) masks out; the stencil holds the reach.
+                let mb = if d2 > 1e-12 { 1.0f32 } else { 0.0 };
+                w -= mb * b.pull(d2.max(1e-12).sqrt());
+            }
             fx += dx * w;
             fy += dy * w;
         }
@@ -285,25 +461,74 @@ fn gather_force(
 /// particles from these rows with the distance mask is exact — the extra
 /// candidates mask to zero terms.
 #[inline(always)]
-fn stencil_rows_span(cell_start: &[u32], lo: usize, hi: usize) -> [(usize, usize); 3] {
-    let (x0, y) = (lo % GRID_W, lo / GRID_W);
-    let x1 = hi % GRID_W;
+fn stencil_rows_span(grid: &CsrGrid, lo: usize, hi: usize) -> [(usize, usize); 3] {
+    let (x0, y) = (lo % grid.w, lo / grid.w);
+    let x1 = hi % grid.w;
     let x0 = x0.saturating_sub(1);
-    let x1 = (x1 + 1).min(GRID_W - 1);
+    let x1 = (x1 + 1).min(grid.w - 1);
     let mut rows = [(0usize, 0usize); 3];
     for dy in 0..3usize {
         let yy = y as i32 + dy as i32 - 1;
-        if yy >= 0 && yy < GRID_H as i32 {
-            let base = yy as usize * GRID_W;
+        if yy >= 0 && yy < grid.h as i32 {
+            let base = yy as usize * grid.w;
             rows[dy] = (
-                cell_start[base + x0] as usize,
-                cell_start[base + x1 + 1] as usize,
+                grid.cell_start[base + x0] as usize,
+                grid.cell_start[base + x1 + 1] as usize,
             );
         }
     }
     rows
 }
 
+/// Contact distance of a pair of radii `ra`, `rb`, and the share of the
+/// pair's correction that `ra`'s side takes: 2·m_b/(m_a + m_b) with m ∝ r²,
+/// so equal radii split it evenly as before.
+#[inline(always)]
+fn contact_of(ra: f32, rb: f32) -> (f32, f32) {
+    let (ma, mb) = (ra * ra, rb * rb);
+    (ra + rb, 2.0 * mb / (ma + mb))
+}
+
+/// Coulomb friction for the packed contact gathers (Macklin et al. 2014,
+/// §6.1): the packed start-of-substep positions, so each pair's relative
+/// displacement this substep is at hand, and the static/kinetic coefficients.
+#[derive(Clone, Copy)]
+struct Friction<'a> {
+    ox: &'a [f32],
+    oy: &'a [f32],
+    stat: f32,
+    kin: f32,
+}
+
+/// Fraction of a contact's relative tangential slip `t` (of length `len`)
+/// that friction cancels at penetration `depth`: all of it while it is
+/// within the static cone (|t| < μ_s·d), else a kinetic μ_k·d of it.
+#[inline(always)]
+fn friction_cancel(len: f32, depth: f32, stat: f32, kin: f32) -> f32 {
+    if len < stat * depth {
+        1.0
+    } else {
+        (kin * depth / len.max(1e-12)).min(1.0)
+    }
+}
+
+/// Coulomb friction against a box wall, after `resolve_wall_collision` moved
+/// a particle from `before` to `pos`: the push-out is the penetration depth,
+/// and the slip along the wall since `opos` is cancelled within the cone.
+/// The wall does not move, so the particle takes the whole correction.
+#[inline(always)]
+fn wall_friction(pos: &mut Vec2, before: Vec2, opos: Vec2, (stat, kin): (f32, f32)) {
+    let push = *pos - before;
+    let depth = push.length();
+    if depth <= 0.0 {
+        return;
+    }
+    let n = push / depth;
+    let rel = *pos - opos;
+    let slip = rel - n * rel.dot(n);
+    *pos -= slip * friction_cancel(slip.length(), depth, stat, kin);
+}
+
 /// 4-lane variant of `gather_correction`: one pass over a shared candidate
 /// row set updates 4 cluster members at once (GROMACS-style i-cluster;
 /// docs/literature.md §4, docs/benchmarks/23-cluster-gather.md). Each
@@ -320,10 +545,20 @@ fn gather_correction4(
     py: &[f32],
     rows: &[(usize, usize); 3],
     relax: f32,
+    friction: Option<(Friction, usize)>,
 ) -> ([f32; 4], [f32; 4]) {
     const CONTACT: f32 = BALL_SIZE + BALL_SIZE;
     let mut cx = [0.0f32; 4];
     let mut cy = [0.0f32; 4];
+    // Each lane's own displacement this substep (friction only).
+    let mut ux = [0.0f32; 4];
+    let mut uy = [0.0f32; 4];
+    if let Some((f, k0)) = friction {
+        for l in 0..4 {
+            ux[l] = px[k0 + l] - f.ox[k0 + l];
+            uy[l] = py[k0 + l] - f.oy[k0 + l];
+        }
+    }
     for &(start, end) in rows {
         for k in start..end {
             let bx = px[k];
@@ -343,6 +578,17 @@ fn gather_correction4(
                 let term = m * relax * (dist - CONTACT) * inv;
                 cx[l] -= dx * term;
                 cy[l] -= dy * term;
+                if let Some((f, _)) = friction {
+                    let (nx, ny) = (dx * inv, dy * inv);
+                    let rx = ux[l] - (bx - f.ox[k]);
+                    let ry = uy[l] - (by - f.oy[k]);
+                    let dn = rx * nx + ry * ny;
+                    let (tx, ty) = (rx - dn * nx, ry - dn * ny);
+                    let len = (tx * tx + ty * ty).sqrt();
+                    let cancel = friction_cancel(len, CONTACT - dist, f.stat, f.kin);
+                    cx[l] -= m * relax * cancel * tx;
+                    cy[l] -= m * relax * cancel * ty;
+                }
             }
         }
     }
@@ -353,8 +599,12 @@ fn gather_correction4(
 /// accumulates its half of every overlapping pair's separation; the partner
 /// accumulates the other half in its own gather. Same per-iteration pair
 /// correction as the old Gauss-Seidel sweep, but order-independent, so it
-/// vectorizes and parallelizes with no cell coloring.
+/// vectorizes and parallelizes with no cell coloring. With `friction` (and
+/// the particle's packed index) it also cancels its half of every contact's
+/// tangential slip; `None` compiles to the frictionless kernel. `sized`
+/// (packed radii, own radius) sizes and mass-weights every contact.
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn gather_correction(
     xa: f32,
     ya: f32,
@@ -362,19 +612,29 @@ fn gather_correction(
     py: &[f32],
     rows: &[(usize, usize); 3],
     relax: f32,
+    friction: Option<(Friction, usize)>,
+    sized: Option<(&[f32], f32)>,
 ) -> (f32, f32) {
     const CONTACT: f32 = BALL_SIZE + BALL_SIZE;
     let mut cx = 0.0f32;
     let mut cy = 0.0f32;
+    let (ux, uy) = match friction {
+        Some((f, k)) => (px[k] - f.ox[k], py[k] - f.oy[k]),
+        None => (0.0, 0.0),
+    };
     for &(start, end) in rows {
         for k in start..end {
             let dx = xa - px[k];
             let dy = ya - py[k];
             let d2 = dx * dx + dy * dy;
+            let (contact, share) = match sized {
+                Some((pr, ra)) => contact_of(ra, pr[k]),
+                None => (CONTACT, 1.0),
+            };
             // Excludes self (d2 == 0) and coincident pairs, like the old
             // try_normalize() -> ZERO path.
-            let m = if d2 < CONTACT * CONTACT && d2 > 1e-12 {
-                1.0f32
+            let m = if d2 < contact * contact && d2 > 1e-12 {
+                share
             } else {
                 0.0
             };
@@ -383,14 +643,67 @@ fn gather_correction(
             let dist = d2c * inv;
             // == normalized(axis) * relax * (dist - CONTACT), masked;
             // relax = 0.375·ω (ω = 1 reproduces the historical factor)
-            let term = m * relax * (dist - CONTACT) * inv;
+            let term = m * relax * (dist - contact) * inv;
             cx -= dx * term;
             cy -= dy * term;
+            if let Some((f, _)) = friction {
+                let (nx, ny) = (dx * inv, dy * inv);
+                let rx = ux - (px[k] - f.ox[k]);
+                let ry = uy - (py[k] - f.oy[k]);
+                let dn = rx * nx + ry * ny;
+                let (tx, ty) = (rx - dn * nx, ry - dn * ny);
+                let len = (tx * tx + ty * ty).sqrt();
+                let cancel = friction_cancel(len, contact - dist, f.stat, f.kin);
+                cx -= m * relax * cancel * tx;
+                cy -= m * relax * cancel * ty;
+            }
         }
     }
     (cx, cy)
 }
 
+/// Rolling-resistance gather for the packed engine: the particle's side of
+/// every touching pair's relative tangential velocity (as displacement per
+/// substep, against the rolling passes' own packed `rox`/`roy`),
+/// mass-weighted like the contact correction, and the number of those
+/// contacts. `(ux, uy)` is the particle's own displacement this substep.
+#[inline(always)]
+fn gather_rolling(
+    (xa, ya): (f32, f32),
+    (ux, uy): (f32, f32),
+    px: &[f32],
+    py: &[f32],
+    (rox, roy): (&[f32], &[f32]),
+    rows: &[(usize, usize); 3],
+    sized: Option<(&[f32], f32)>,
+) -> (f32, f32, f32) {
+    const CONTACT: f32 = BALL_SIZE + BALL_SIZE;
+    let (mut sx, mut sy, mut count) = (0.0f32, 0.0f32, 0.0f32);
+    for &(start, end) in rows {
+        for k in start..end {
+            let dx = xa - px[k];
+            let dy = ya - py[k];
+            let d2 = dx * dx + dy * dy;
+            let (contact, share) = match sized {
+                Some((pr, ra)) => contact_of(ra, pr[k]),
+                None => (CONTACT, 1.0),
+            };
+            if d2 >= contact * contact || d2 <= 1e-12 {
+                continue;
+            }
+            let inv = 1.0 / d2.sqrt();
+            let (nx, ny) = (dx * inv, dy * inv);
+            let rx = ux - (px[k] - rox[k]);
+            let ry = uy - (py[k] - roy[k]);
+            let dn = rx * nx + ry * ny;
+            sx += share * (rx - dn * nx);
+            sy += share * (ry - dn * ny);
+            count += 1.0;
+        }
+    }
+    (sx, sy, count)
+}
+
 /// Granular repulsion + non-penetration contact solver, with all of its
 /// optimization scratch owned here.
 pub struct GranularSolver {
@@ -403,10 +716,24 @@ pub struct GranularSolver {
     stale_steps: usize,
     scratch_v2: Vec<Vec2>,
     scratch_f: Vec<f32>,
+    scratch_id: Vec<u32>,
+    scratch_phase: Vec<u8>,
     // Packed SoA state in CSR (cell) order, rebuilt each step: positions for
     // the force/solver kernels plus a shared accumulator pair.
     px: Vec<f32>,
     py: Vec<f32>,
+    // Packed start-of-substep positions (opos), for the contact friction.
+    pox: Vec<f32>,
+    poy: Vec<f32>,
+    // Rolling resistance's own view of opos: packed (`rox`/`roy`) or as a
+    // shift on c_opos (serial). The rolling passes update it; it reaches
+    // c_opos only after the solve, so the friction above keeps measuring
+    // from the start of the substep.
+    rox: Vec<f32>,
+    roy: Vec<f32>,
+    roll_shift: Vec<Vec2>,
+    // Packed radii, filled only while the particles are not all BALL_SIZE.
+    pr: Vec<f32>,
     acc_x: Vec<f32>,
     acc_y: Vec<f32>,
     packed_cell: Vec<u32>,
@@ -418,21 +745,44 @@ pub struct GranularSolver {
     force_interval: usize,
     solver_iterations: usize,
     solver_omega: f32,
+    // Static / kinetic Coulomb friction of the contacts (0 = frictionless).
+    friction: (f32, f32),
+    // Rolling resistance: the fraction of a touching pair's relative
+    // tangential velocity removed each solver iteration (0 = rolls freely).
+    rolling: f32,
+    // Capillary-bridge cohesion: strength and rupture gap (0 = dry).
+    cohesion: (f32, f32),
+    // Largest particle radius this substep (BALL_SIZE when monodisperse).
+    r_max: f32,
     par_min: usize,
+    // Cells close enough to the container wall that their particles need the
+    // SDF test, like the border cells for the box walls; rebuilt whenever the
+    // boundaries' generation changes.
+    container_cells: Vec<u32>,
+    boundary_gen: u64,
+    container_reach: f32,
 }
 
 impl GranularSolver {
     pub fn new(scale: f32, c_force: Vec<Vec2>) -> Self {
         Self {
             c_force,
-            grid: CsrGrid::new(),
+            grid: CsrGrid::new(&Domain::default(), GRID_SIZE),
             scale,
             neighbor_lists: VerletLists::default(),
             stale_steps: 0,
             scratch_v2: Vec::new(),
             scratch_f: Vec::new(),
+            scratch_id: Vec::new(),
+            scratch_phase: Vec::new(),
             px: Vec::new(),
             py: Vec::new(),
+            pox: Vec::new(),
+            poy: Vec::new(),
+            rox: Vec::new(),
+            roy: Vec::new(),
+            roll_shift: Vec::new(),
+            pr: Vec::new(),
             acc_x: Vec::new(),
             acc_y: Vec::new(),
             packed_cell: Vec::new(),
@@ -442,7 +792,14 @@ impl GranularSolver {
             force_interval: FORCE_INTERVAL,
             solver_iterations: SOLVER_ITERATIONS,
             solver_omega: SOLVER_OMEGA,
+            friction: (0.0, 0.0),
+            rolling: 0.0,
+            cohesion: (0.0, 0.0),
+            r_max: BALL_SIZE,
             par_min: PAR_MIN_PARTICLES,
+            container_cells: Vec::new(),
+            boundary_gen: 0,
+            container_reach: 0.0,
         }
     }
 
@@ -521,7 +878,28 @@ impl GranularSolver {
             .extend(perm.iter().map(|&p| share.c_color[p as usize]));
         std::mem::swap(&mut share.c_color, &mut self.scratch_f);
 
+        // Persistent IDs travel with their particles.
+        if share.c_id.len() == n {
+            self.scratch_id.clear();
+            self.scratch_id
+                .extend(perm.iter().map(|&p| share.c_id[p as usize]));
+            std::mem::swap(&mut share.c_id, &mut self.scratch_id);
+        }
+        if share.c_phase.len() == n {
+            self.scratch_phase.clear();
+            self.scratch_phase
+                .extend(perm.iter().map(|&p| share.c_phase[p as usize]));
+            std::mem::swap(&mut share.c_phase, &mut self.scratch_phase);
+        }
+        if share.c_radius.len() == n {
+            self.scratch_f.clear();
+            self.scratch_f
+                .extend(perm.iter().map(|&p| share.c_radius[p as usize]));
+            std::mem::swap(&mut share.c_radius, &mut self.scratch_f);
+        }
+
         // Grid order is now array order; cell assignments are unchanged.
+        let (inv_cell, w, h) = (self.grid.inv_cell, self.grid.w, self.grid.h);
         for (k, (idx, cell)) in self
             .grid
             .indices
@@ -530,7 +908,7 @@ impl GranularSolver {
             .enumerate()
         {
             *idx = k as u32;
-            *cell = CsrGrid::cell_id(share.c_pos[k]);
+            *cell = cell_id(share.c_pos[k], inv_cell, w, h);
         }
 
         // Particle indices changed; stored neighbor lists are meaningless now.
@@ -540,10 +918,15 @@ impl GranularSolver {
         self.stale_steps = VERLET_REBUILD_RETRY - 1;
     }
 
+    /// `radii` is `ShareData::c_radius`, or `None` when every particle is
+    /// BALL_SIZE.
     fn check_ball_collisions(
         &mut self,
         c_pos: &mut [Vec2],
         c_opos: &mut [Vec2],
+        radii: Option<&[f32]>,
+        bounds: &mut Boundaries,
+        dt: f32,
         stats: &mut PerformanceStats,
     ) {
         // Two solver engines (see docs/benchmarks/10-packed-simd-jacobi.md):
@@ -555,7 +938,7 @@ impl GranularSolver {
         // only 5 parallel regions instead of 24 colored ones.
         let packed = c_pos.len() >= self.par_min;
         if packed {
-            self.pack_positions(c_pos);
+            self.pack_positions(c_pos, c_opos, radii);
         }
 
         // Far-field forces with multiple time stepping (RESPA-style): the
@@ -568,7 +951,7 @@ impl GranularSolver {
             || self.c_farfield.len() != c_pos.len()
             || self.frame_count % self.force_interval == 0;
         if refresh {
-            self.compute_forces(c_pos);
+            self.compute_forces(c_pos, radii);
             if self.force_interval > 1 {
                 self.c_farfield.clear();
                 self.c_farfield.extend_from_slice(&self.c_force);
@@ -584,23 +967,62 @@ impl GranularSolver {
         if packed {
             for _ in 0..self.solver_iterations {
                 self.resolve_collisions_packed();
-                self.check_wall_collisions_packed(c_opos);
+                self.check_wall_collisions_packed(c_opos, bounds, dt);
+                self.check_obstacles_packed(c_opos, bounds, dt);
+                if self.rolling > 0.0 {
+                    self.resist_rolling_packed();
+                }
             }
             self.scatter_positions(c_pos);
+            if self.rolling > 0.0 {
+                for (k, &i) in self.grid.indices.iter().enumerate() {
+                    let shift = Vec2::new(self.rox[k] - self.pox[k], self.roy[k] - self.poy[k]);
+                    c_opos[i as usize] += shift;
+                }
+            }
         } else {
+            if self.rolling > 0.0 {
+                self.roll_shift.clear();
+                self.roll_shift.resize(c_pos.len(), Vec2::ZERO);
+            }
             for _ in 0..self.solver_iterations {
-                self.resolve_collisions_serial(c_pos);
-                self.check_wall_collisions_serial(c_pos, c_opos);
+                self.resolve_collisions_serial(c_pos, c_opos, radii);
+                self.check_wall_collisions_serial(c_pos, c_opos, radii, bounds, dt);
+                self.check_obstacles_serial(c_pos, c_opos, radii, bounds, dt);
+                if self.rolling > 0.0 {
+                    self.resist_rolling_serial(c_pos, c_opos, radii);
+                }
+            }
+            if self.rolling > 0.0 {
+                for (o, &shift) in c_opos.iter_mut().zip(&self.roll_shift) {
+                    *o += shift;
+                }
             }
         }
         stats.collision_time_us += t.elapsed().as_micros() as u64;
     }
 
-    fn pack_positions(&mut self, c_pos: &[Vec2]) {
+    fn pack_positions(&mut self, c_pos: &[Vec2], c_opos: &[Vec2], radii: Option<&[f32]>) {
         let grid = &self.grid;
         self.px.clear();
         self.py.clear();
         self.packed_cell.clear();
+        self.pr.clear();
+        if let Some(r) = radii {
+            self.pr.extend(grid.indices.iter().map(|&i| r[i as usize]));
+        }
+        if self.friction.0 > 0.0 || self.friction.1 > 0.0 || self.rolling > 0.0 {
+            self.pox.clear();
+            self.poy.clear();
+            self.pox
+                .extend(grid.indices.iter().map(|&i| c_opos[i as usize].x));
+            self.poy
+                .extend(grid.indices.iter().map(|&i| c_opos[i as usize].y));
+        }
+        if self.rolling > 0.0 {
+            self.rox.clone_from(&self.pox);
+            self.roy.clone_from(&self.poy);
+        }
         self.px
             .extend(grid.indices.iter().map(|&i| c_pos[i as usize].x));
         self.py
@@ -621,27 +1043,23 @@ impl GranularSolver {
     /// pair — any discrepancy beyond summation-order rounding is a bug).
     fn forces_grid_impl(&mut self, positions: &[Vec2]) -> Vec<Vec2> {
         self.grid.build(positions, false);
-        let (grid, scale) = (&self.grid, self.scale);
+        let (grid, scale, bridge) = (&self.grid, self.scale, self.bridge());
         positions
             .iter()
             .enumerate()
             .map(|(i, &p)| {
                 let cell = grid.cell_of[i] as usize;
-                let (x, y) = (cell % GRID_W, cell / GRID_W);
+                let (x, y) = (cell % grid.w, cell / grid.w);
                 let mut acc = Vec2::ZERO;
                 for dy in -1i32..=1 {
-                    let ny = y as i32 + dy;
-                    if ny < 0 || ny >= GRID_H as i32 {
-                        continue;
-                    }
                     for dx in -1i32..=1 {
-                        let nx = x as i32 + dx;
-                        if nx < 0 || nx >= GRID_W as i32 {
+                        let Some((c, o)) = grid.neighbor(x, y, dx, dy) else {
                             continue;
-                        }
-                        for &j in grid.cell(ny as usize * GRID_W + nx as usize) {
+                        };
+                        for &j in grid.cell(c) {
                             if j as usize != i {
-                                acc += force(p, positions[j as usize], scale);
+                                let pj = positions[j as usize] + o;
+                                acc += force_between(p, pj, scale, None, bridge);
                             }
                         }
                     }
@@ -655,24 +1073,25 @@ impl GranularSolver {
     /// and scaling — the model's ground truth, for validating that the grid
     /// gathers compute the identical interaction set.
     fn forces_direct_impl(&self, positions: &[Vec2]) -> Vec<Vec2> {
-        let scale = self.scale;
+        let (scale, domain, bridge) = (self.scale, self.grid.domain, self.bridge());
         positions
             .par_iter()
             .map(|&pi| {
                 let mut acc = Vec2::ZERO;
                 for &pj in positions {
-                    acc += force(pi, pj, scale);
+                    let pj = domain.nearest_image(pi, pj);
+                    acc += force_between(pi, pj, scale, None, bridge);
                 }
                 acc / 8.0
             })
             .collect()
     }
 
-    fn compute_forces(&mut self, c_pos: &[Vec2]) {
+    fn compute_forces(&mut self, c_pos: &[Vec2], radii: Option<&[f32]>) {
         if self.use_verlet_lists {
             if !self.neighbor_lists.needs_rebuild(c_pos) {
                 self.stale_steps = 0;
-                self.compute_forces_with_verlet_lists(c_pos);
+                self.compute_forces_with_verlet_lists(c_pos, radii);
                 return;
             }
             // Lists are stale. Rebuilding every step under fast motion costs
@@ -682,11 +1101,11 @@ impl GranularSolver {
             if self.stale_steps >= VERLET_REBUILD_RETRY {
                 self.stale_steps = 0;
                 self.rebuild_neighbor_lists(c_pos);
-                self.compute_forces_with_verlet_lists(c_pos);
+                self.compute_forces_with_verlet_lists(c_pos, radii);
                 return;
             }
         }
-        self.compute_forces_with_spatial_hash(c_pos);
+        self.compute_forces_with_spatial_hash(c_pos, radii);
     }
 
     fn rebuild_neighbor_lists(&mut self, c_pos: &[Vec2]) {
@@ -704,28 +1123,25 @@ impl GranularSolver {
         // build walks a 5×5 stencil. (Historically this range was
         // 2·BALL + skin over 3×3, which silently truncated list-mode forces
         // relative to the direct pass; with the stage-25 explicit cutoff the
-        // two modes now compute the same interaction set.)
+        // two modes now compute the same interaction set.) Larger particles
+        // reach further, as do the cells, so two cells still cover the range.
+        let cutoff = FORCE_CUTOFF * self.r_max / BALL_SIZE;
         let interaction_range_sq =
-            (FORCE_CUTOFF + VERLET_SKIN_DISTANCE) * (FORCE_CUTOFF + VERLET_SKIN_DISTANCE);
+            (cutoff + VERLET_SKIN_DISTANCE) * (cutoff + VERLET_SKIN_DISTANCE);
         for i in 0..c_pos.len() {
             lists.start[i] = lists.neighbors.len() as u32;
 
             let cell = self.grid.cell_of[i] as usize;
-            let (x, y) = (cell % GRID_W, cell / GRID_W);
+            let (x, y) = (cell % self.grid.w, cell / self.grid.w);
 
             for dy in -2i32..=2 {
-                let ny = y as i32 + dy;
-                if ny < 0 || ny >= GRID_H as i32 {
-                    continue;
-                }
                 for dx in -2i32..=2 {
-                    let nx = x as i32 + dx;
-                    if nx < 0 || nx >= GRID_W as i32 {
+                    let Some((c, o)) = self.grid.neighbor(x, y, dx, dy) else {
                         continue;
-                    }
-                    for &j in self.grid.cell(ny as usize * GRID_W + nx as usize) {
+                    };
+                    for &j in self.grid.cell(c) {
                         if i != j as usize
-                            && (c_pos[i] - c_pos[j as usize]).length_squared()
+                            && (c_pos[i] - c_pos[j as usize] - o).length_squared()
                                 < interaction_range_sq
                         {
                             lists.neighbors.push(j);
@@ -737,35 +1153,46 @@ impl GranularSolver {
         lists.start[c_pos.len()] = lists.neighbors.len() as u32;
     }
 
-    fn compute_forces_with_verlet_lists(&mut self, c_pos: &[Vec2]) {
+    fn compute_forces_with_verlet_lists(&mut self, c_pos: &[Vec2], radii: Option<&[f32]>) {
+        let bridge = self.bridge();
+        let pair = |i: usize, j: usize| radii.map(|r| (r[i], r[j]));
+        let m = |i: usize| radii.map_or(1.0, |r| mass(r[i]));
         if c_pos.len() >= self.par_min {
             // Parallel gather: each particle sums its full (symmetric) neighbor
             // list, so no cross-thread writes. Twice the arithmetic of the
             // Newton's-third-law scatter, but it parallelizes cleanly.
             let (c_force, lists, scale) = (&mut self.c_force, &self.neighbor_lists, self.scale);
+            let domain = self.grid.domain;
             c_force.par_iter_mut().enumerate().for_each(|(i, f)| {
                 let mut acc = Vec2::ZERO;
                 for &j in lists.of(i) {
-                    acc += force(c_pos[i], c_pos[j as usize], scale);
+                    let j = j as usize;
+                    let pj = domain.nearest_image(c_pos[i], c_pos[j]);
+                    acc += force_between(c_pos[i], pj, scale, pair(i, j), bridge);
                 }
-                *f += acc / 8.0;
+                *f += acc / (8.0 * m(i));
             });
         } else {
+            let domain = self.grid.domain;
             for i in 0..c_pos.len() {
                 for &j in self.neighbor_lists.of(i) {
                     let j = j as usize;
                     if i < j {
-                        let f = force(c_pos[i], c_pos[j], self.scale) / 8.0;
-                        self.c_force[i] += f;
-                        self.c_force[j] -= f; // Newton's third law
+                        let pj = domain.nearest_image(c_pos[i], c_pos[j]);
+                        let f = force_between(c_pos[i], pj, self.scale, pair(i, j), bridge) / 8.0;
+                        self.c_force[i] += f / m(i);
+                        self.c_force[j] -= f / m(j); // Newton's third law
                     }
                 }
             }
         }
     }
 
-    fn compute_forces_with_spatial_hash(&mut self, c_pos: &[Vec2]) {
+    fn compute_forces_with_spatial_hash(&mut self, c_pos: &[Vec2], radii: Option<&[f32]>) {
         let n = c_pos.len();
+        let bridge = self.bridge();
+        let pair = |i: usize, j: usize| radii.map(|r| (r[i], r[j]));
+        let m = |i: usize| radii.map_or(1.0, |r| mass(r[i]));
         if n >= self.par_min {
             // Packed parallel gather over the CSR-ordered SoA arrays. The
             // gather writes every element, so the buffers need sizing only.
@@ -777,14 +1204,38 @@ impl GranularSolver {
 
             {
                 let (grid, px, py, cells) = (&self.grid, &self.px, &self.py, &self.packed_cell);
+                let pr = &self.pr;
                 let (acc_x, acc_y) = (&mut self.acc_x, &mut self.acc_y);
                 acc_x
                     .par_iter_mut()
                     .zip(acc_y.par_iter_mut())
                     .enumerate()
                     .for_each(|(k, (fx, fy))| {
-                        let rows = stencil_rows(&grid.cell_start, cells[k] as usize);
-                        let (gx, gy) = gather_force(px[k], py[k], px, py, &rows, s8);
+                        let cell = cells[k] as usize;
+                        let rows = stencil_rows(grid, cell);
+                        let sized = (!pr.is_empty()).then(|| (pr.as_slice(), pr[k]));
+                        let (mut gx, mut gy) =
+                            gather_force(px[k], py[k], px, py, &rows, s8, sized, bridge);
+                        if grid.on_seam(cell) {
+                            grid.seam_rows(cell, |o, rows| {
+                                let (sx, sy) = gather_force(
+                                    px[k] - o.x,
+                                    py[k] - o.y,
+                                    px,
+                                    py,
+                                    rows,
+                                    s8,
+                                    sized,
+                                    bridge,
+                                );
+                                gx += sx;
+                                gy += sy;
+                            });
+                        }
+                        if let Some((_, r)) = sized {
+                            gx /= mass(r);
+                            gy /= mass(r);
+                        }
                         *fx = gx;
                         *fy = gy;
                     });
@@ -800,7 +1251,7 @@ impl GranularSolver {
         // pair is visited once and Newton's third law is applied per pair.
         for oc in 0..self.grid.occupied.len() {
             let cell = self.grid.occupied[oc] as usize;
-            let (x, y) = (cell % GRID_W, cell / GRID_W);
+            let (x, y) = (cell % self.grid.w, cell / self.grid.w);
             let currents = self.grid.cell(cell);
 
             // Pairs within the cell
@@ -808,40 +1259,53 @@ impl GranularSolver {
                 let i = currents[a] as usize;
                 for b in a + 1..currents.len() {
                     let j = currents[b] as usize;
-                    let f = force(c_pos[i], c_pos[j], self.scale) / 8.0;
-                    self.c_force[i] += f;
-                    self.c_force[j] -= f;
+                    let pj = c_pos[j];
+                    let f = force_between(c_pos[i], pj, self.scale, pair(i, j), bridge) / 8.0;
+                    self.c_force[i] += f / m(i);
+                    self.c_force[j] -= f / m(j);
                 }
             }
 
             // Forward half-stencil: E, SW, S, SE
             for (dx, dy) in [(1i32, 0i32), (-1, 1), (0, 1), (1, 1)] {
-                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
-                if nx < 0 || nx >= GRID_W as i32 || ny >= GRID_H as i32 {
+                let Some((c, o)) = self.grid.neighbor(x, y, dx, dy) else {
                     continue;
-                }
+                };
                 for &i in currents {
                     let i = i as usize;
-                    for &j in self.grid.cell(ny as usize * GRID_W + nx as usize) {
+                    for &j in self.grid.cell(c) {
                         let j = j as usize;
-                        let f = force(c_pos[i], c_pos[j], self.scale) / 8.0;
-                        self.c_force[i] += f;
-                        self.c_force[j] -= f;
+                        let pj = c_pos[j] + o;
+                        let f = force_between(c_pos[i], pj, self.scale, pair(i, j), bridge) / 8.0;
+                        self.c_force[i] += f / m(i);
+                        self.c_force[j] -= f / m(j);
                     }
                 }
             }
         }
     }
 
-    /// Project one overlapping pair apart (half correction each side). One
-    /// rsqrt replaces length() + try_normalize(); the common non-overlapping
-    /// case exits before any position store.
+    /// Project one overlapping pair apart (half correction each side), with
+    /// `c_pos[j] + o` the image of j next to i. One rsqrt replaces length() +
+    /// try_normalize(); the common non-overlapping case exits before any
+    /// position store. With friction (static, kinetic), the pair's relative
+    /// tangential slip since `c_opos` is cancelled too, within the Coulomb
+    /// cone set by the penetration depth. With `radii` the pair touches at
+    /// r_i + r_j and the lighter side takes more of the correction.
     #[inline(always)]
-    fn project_pair(c_pos: &mut [Vec2], i: usize, j: usize, relax: f32) {
-        const CONTACT: f32 = BALL_SIZE + BALL_SIZE;
-        let col_axis = c_pos[i] - c_pos[j];
+    fn project_pair(
+        c_pos: &mut [Vec2],
+        c_opos: &[Vec2],
+        (i, j): (usize, usize),
+        o: Vec2,
+        relax: f32,
+        friction: Option<(f32, f32)>,
+        radii: Option<&[f32]>,
+    ) {
+        let (contact, wi, wj) = Self::pair_contact((i, j), radii);
+        let col_axis = c_pos[i] - c_pos[j] - o;
         let dist_sq = col_axis.length_squared();
-        if dist_sq >= CONTACT * CONTACT {
+        if dist_sq >= contact * contact {
             return;
         }
         if dist_sq < 1e-12 {
@@ -852,64 +1316,170 @@ impl GranularSolver {
             // tie-break direction from the indices and project as if fully
             // overlapped along it.
             let dir = if (i ^ j) & 1 == 0 { Vec2::X } else { Vec2::Y };
-            let sep = dir * (relax * CONTACT);
-            c_pos[i] += sep;
-            c_pos[j] -= sep;
+            let sep = dir * (relax * contact);
+            c_pos[i] += sep * wi;
+            c_pos[j] -= sep * wj;
             return;
         }
         let inv_dist = fast_rsqrt(dist_sq);
         let dist = dist_sq * inv_dist;
         // == normalized(col_axis) * relax * (dist - CONTACT), where
         // relax = 0.375·ω (ω = 1 reproduces the historical 0.75/2 factor)
-        let corr = col_axis * (inv_dist * relax * (dist - CONTACT));
-        c_pos[i] -= corr;
-        c_pos[j] += corr;
+        let corr = col_axis * (inv_dist * relax * (dist - contact));
+        c_pos[i] -= corr * wi;
+        c_pos[j] += corr * wj;
+        if let Some((stat, kin)) = friction {
+            let n = col_axis * inv_dist;
+            let rel = (c_pos[i] - c_opos[i]) - (c_pos[j] - c_opos[j]);
+            let slip = rel - n * rel.dot(n);
+            let cancel = friction_cancel(slip.length(), contact - dist, stat, kin);
+            let tcorr = slip * (relax * cancel);
+            c_pos[i] -= tcorr * wi;
+            c_pos[j] += tcorr * wj;
+        }
+    }
+
+    /// Contact distance of a pair and the share of the correction each side
+    /// takes: r_i + r_j and mass-weighted with `radii`, else 2·BALL_SIZE and
+    /// an even split.
+    #[inline(always)]
+    fn pair_contact((i, j): (usize, usize), radii: Option<&[f32]>) -> (f32, f32, f32) {
+        const CONTACT: f32 = BALL_SIZE + BALL_SIZE;
+        match radii {
+            None => (CONTACT, 1.0, 1.0),
+            Some(r) => {
+                let (contact, wi) = contact_of(r[i], r[j]);
+                (contact, wi, 2.0 - wi)
+            }
+        }
+    }
+
+    /// Rolling resistance of one touching pair. The particles carry no spin,
+    /// so a grain rolling over its neighbor shows up as their relative
+    /// tangential velocity; each side gives up `damp` of it. It is taken out
+    /// of the previous positions (`c_opos` + `shift`), as a change to
+    /// `shift`, so the projected positions (and with them the contact
+    /// overlaps) stay put and the next substep has nothing new to push apart.
+    #[inline(always)]
+    fn roll_pair(
+        c_pos: &[Vec2],
+        c_opos: &[Vec2],
+        shift: &mut [Vec2],
+        (i, j): (usize, usize),
+        o: Vec2,
+        damp: f32,
+        radii: Option<&[f32]>,
+    ) {
+        let (contact, wi, wj) = Self::pair_contact((i, j), radii);
+        let axis = c_pos[i] - c_pos[j] - o;
+        let dist_sq = axis.length_squared();
+        if dist_sq >= contact * contact || dist_sq < 1e-12 {
+            return;
+        }
+        let n = axis * fast_rsqrt(dist_sq);
+        let rel = (c_pos[i] - c_opos[i] - shift[i]) - (c_pos[j] - c_opos[j] - shift[j]);
+        let roll = (rel - n * rel.dot(n)) * damp;
+        shift[i] += roll * wi;
+        shift[j] -= roll * wj;
     }
 
     /// Gauss-Seidel sweep over occupied cells (forward half-stencil, each
     /// unordered cell pair once). Fastest engine at serial particle counts.
-    fn resolve_collisions_serial(&mut self, c_pos: &mut [Vec2]) {
+    fn resolve_collisions_serial(
+        &mut self,
+        c_pos: &mut [Vec2],
+        c_opos: &[Vec2],
+        radii: Option<&[f32]>,
+    ) {
         let relax = 0.375 * self.solver_omega;
-        for oc in 0..self.grid.occupied.len() {
-            let cell = self.grid.occupied[oc] as usize;
-            let (x, y) = (cell % GRID_W, cell / GRID_W);
-            let currents = self.grid.cell(cell);
+        let friction = self.contact_friction();
+        self.grid.for_each_pair(|pair, o| {
+            Self::project_pair(c_pos, c_opos, pair, o, relax, friction, radii);
+        });
+    }
 
-            for a in 0..currents.len() {
-                let i = currents[a] as usize;
-                for b in a + 1..currents.len() {
-                    Self::project_pair(c_pos, i, currents[b] as usize, relax);
+    /// Rolling resistance for one serial solver iteration: a Gauss-Seidel
+    /// sweep of `roll_pair`, half of `rolling` to each side, so a lone pair
+    /// at `rolling` = 1 stops rolling outright. It runs after every contact
+    /// sweep rather than once per substep: the normal corrections of the
+    /// later sweeps would otherwise start the grains rolling again.
+    fn resist_rolling_serial(&mut self, c_pos: &[Vec2], c_opos: &[Vec2], radii: Option<&[f32]>) {
+        let damp = 0.5 * self.rolling;
+        let shift = &mut self.roll_shift;
+        self.grid.for_each_pair(|pair, o| {
+            Self::roll_pair(c_pos, c_opos, shift, pair, o, damp, radii);
+        });
+    }
+
+    fn check_wall_collisions_serial(
+        &mut self,
+        c_pos: &mut [Vec2],
+        c_opos: &mut [Vec2],
+        radii: Option<&[f32]>,
+        bounds: &Boundaries,
+        dt: f32,
+    ) {
+        let domain = self.grid.domain;
+        let friction = self.contact_friction();
+        let radius = |i: usize| radii.map_or(BALL_SIZE, |r| r[i]);
+        let mut wall = |cells: &[u32], c_opos: &mut [Vec2]| {
+            for &i in cells {
+                let i = i as usize;
+                let before = c_pos[i];
+                let (r, salt) = (radius(i), wall_salt(i));
+                resolve_wall_collision(&mut c_pos[i], &mut c_opos[i], r, salt, &domain);
+                if let Some(f) = friction {
+                    wall_friction(&mut c_pos[i], before, c_opos[i], f);
                 }
             }
+        };
+        // Border cells of the walled axes (a periodic one has no walls).
+        if !domain.periodic_x() {
+            for y in 0..self.grid.h {
+                wall(self.grid.cell(y * self.grid.w), c_opos);
+                wall(self.grid.cell(y * self.grid.w + (self.grid.w - 1)), c_opos);
+            }
+        }
+        if !domain.periodic_y() {
+            for x in 0..self.grid.w {
+                wall(self.grid.cell(x), c_opos);
+                wall(self.grid.cell((self.grid.h - 1) * self.grid.w + x), c_opos);
+            }
+        }
 
-            for (dx, dy) in [(1i32, 0i32), (-1, 1), (0, 1), (1, 1)] {
-                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
-                if nx < 0 || nx >= GRID_W as i32 || ny >= GRID_H as i32 {
-                    continue;
-                }
-                for &i in currents {
-                    for &j in self.grid.cell(ny as usize * GRID_W + nx as usize) {
-                        Self::project_pair(c_pos, i as usize, j as usize, relax);
-                    }
+        // Container wall: same Verlet treatment as the obstacles.
+        let inv_dt = 1.0 / dt;
+        for &cell in &self.container_cells {
+            for &i in self.grid.cell(cell as usize) {
+                let i = i as usize;
+                let mut v = (c_pos[i] - c_opos[i]) * inv_dt;
+                if bounds.collide_container(&mut c_pos[i], &mut v, radius(i)) {
+                    c_opos[i] = c_pos[i] - v * dt;
                 }
             }
         }
     }
 
-    fn check_wall_collisions_serial(&mut self, c_pos: &mut [Vec2], c_opos: &mut [Vec2]) {
-        let mut wall = |cells: &[u32], c_opos: &mut [Vec2]| {
-            for &i in cells {
-                let i = i as usize;
-                resolve_wall_collision(&mut c_pos[i], &mut c_opos[i], wall_salt(i));
-            }
-        };
-        for y in 0..GRID_H {
-            wall(self.grid.cell(y * GRID_W), c_opos);
-            wall(self.grid.cell(y * GRID_W + (GRID_W - 1)), c_opos);
+    /// Refresh `container_cells` if the boundaries (or the largest radius)
+    /// changed since the last substep. A cell qualifies when its center is
+    /// within the largest radius, the half-diagonal and one cell of drift of
+    /// the container wall (or outside it).
+    fn update_container_cells(&mut self, bounds: &Boundaries) {
+        let cell = self.grid.cell_size;
+        let reach = self.r_max + cell.max_element() * (std::f32::consts::FRAC_1_SQRT_2 + 1.0);
+        if self.boundary_gen == bounds.generation() && self.container_reach == reach {
+            return;
         }
-        for x in 0..GRID_W {
-            wall(self.grid.cell(x), c_opos);
-            wall(self.grid.cell((GRID_H - 1) * GRID_W + x), c_opos);
+        self.boundary_gen = bounds.generation();
+        self.container_reach = reach;
+        self.container_cells.clear();
+        for y in 0..self.grid.h {
+            for x in 0..self.grid.w {
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * cell;
+                if bounds.near_container(center, reach) {
+                    self.container_cells.push((y * self.grid.w + x) as u32);
+                }
+            }
         }
     }
 
@@ -936,7 +1506,14 @@ impl GranularSolver {
 
         {
             let relax = 0.375 * self.solver_omega;
+            let friction = self.contact_friction().map(|(stat, kin)| Friction {
+                ox: &self.pox,
+                oy: &self.poy,
+                stat,
+                kin,
+            });
             let (grid, px, py, cells) = (&self.grid, &self.px, &self.py, &self.packed_cell);
+            let pr = &self.pr;
             let (acc_x, acc_y) = (&mut self.acc_x, &mut self.acc_y);
             acc_x
                 .par_chunks_mut(4)
@@ -950,24 +1527,54 @@ impl GranularSolver {
                     // AND sit close enough that the union stays narrow (in
                     // sparse regions consecutive occupied cells can be far
                     // apart — scalar path is fine there, the rows are short).
+                    // Clusters touching a periodic seam take the scalar path
+                    // too, which adds the wrapped-around cells, and so does
+                    // every cluster once the radii differ.
                     let c_lo = cells[k0] as usize;
                     let c_hi = cells[k0 + m - 1] as usize;
-                    if m == 4 && c_hi - c_lo < 4 && c_lo / GRID_W == c_hi / GRID_W {
-                        let rows = stencil_rows_span(&grid.cell_start, c_lo, c_hi);
+                    if m == 4
+                        && pr.is_empty()
+                        && c_hi - c_lo < 4
+                        && c_lo / grid.w == c_hi / grid.w
+                        && !grid.on_seam(c_lo)
+                        && !grid.on_seam(c_hi)
+                    {
+                        let rows = stencil_rows_span(grid, c_lo, c_hi);
                         let xs = [px[k0], px[k0 + 1], px[k0 + 2], px[k0 + 3]];
                         let ys = [py[k0], py[k0 + 1], py[k0 + 2], py[k0 + 3]];
-                        let (gx, gy) = gather_correction4(&xs, &ys, px, py, &rows, relax);
+                        let fr = friction.map(|f| (f, k0));
+                        let (gx, gy) = gather_correction4(&xs, &ys, px, py, &rows, relax, fr);
                         for l in 0..4 {
                             ax[l] = xs[l] + gx[l];
                             ay[l] = ys[l] + gy[l];
                         }
                     } else {
                         for l in 0..m {
-                            let rows = stencil_rows(&grid.cell_start, cells[k0 + l] as usize);
-                            let (gx, gy) =
-                                gather_correction(px[k0 + l], py[k0 + l], px, py, &rows, relax);
-                            ax[l] = px[k0 + l] + gx;
-                            ay[l] = py[k0 + l] + gy;
+                            let (xa, ya) = (px[k0 + l], py[k0 + l]);
+                            let cell = cells[k0 + l] as usize;
+                            let rows = stencil_rows(grid, cell);
+                            let fr = friction.map(|f| (f, k0 + l));
+                            let sz = (!pr.is_empty()).then(|| (pr.as_slice(), pr[k0 + l]));
+                            let (mut gx, mut gy) =
+                                gather_correction(xa, ya, px, py, &rows, relax, fr, sz);
+                            if grid.on_seam(cell) {
+                                grid.seam_rows(cell, |o, rows| {
+                                    let (sx, sy) = gather_correction(
+                                        xa - o.x,
+                                        ya - o.y,
+                                        px,
+                                        py,
+                                        rows,
+                                        relax,
+                                        fr,
+                                        sz,
+                                    );
+                                    gx += sx;
+                                    gy += sy;
+                                });
+                            }
+                            ax[l] = xa + gx;
+                            ay[l] = ya + gy;
                         }
                     }
                 });
@@ -978,17 +1585,108 @@ impl GranularSolver {
         std::mem::swap(&mut self.py, &mut self.acc_y);
     }
 
-    fn check_wall_collisions_packed(&mut self, c_opos: &mut [Vec2]) {
-        // Left and right border columns
-        for y in 0..GRID_H {
-            self.wall_cell(y * GRID_W, c_opos);
-            self.wall_cell(y * GRID_W + (GRID_W - 1), c_opos);
+    /// Rolling resistance for one packed solver iteration, the Jacobi
+    /// counterpart of `resist_rolling_serial`: every particle gathers its
+    /// side of its touching pairs' relative tangential velocity against
+    /// `rox`/`roy` and gives up `rolling`/2 of their mean. Averaging
+    /// over the contacts (Macklin et al. 2014, §4.2) keeps the
+    /// order-independent sum from overshooting when several contacts pull
+    /// the same way.
+    fn resist_rolling_packed(&mut self) {
+        let n = self.px.len();
+        self.acc_x.resize(n, 0.0);
+        self.acc_y.resize(n, 0.0);
+
+        let damp = 0.5 * self.rolling;
+        let (grid, px, py, cells, pr) =
+            (&self.grid, &self.px, &self.py, &self.packed_cell, &self.pr);
+        let po = (self.rox.as_slice(), self.roy.as_slice());
+        self.acc_x
+            .par_iter_mut()
+            .zip(self.acc_y.par_iter_mut())
+            .enumerate()
+            .for_each(|(k, (ax, ay))| {
+                let (xa, ya) = (px[k], py[k]);
+                let u = (xa - po.0[k], ya - po.1[k]);
+                let cell = cells[k] as usize;
+                let sz = (!pr.is_empty()).then(|| (pr.as_slice(), pr[k]));
+                let rows = stencil_rows(grid, cell);
+                let (mut sx, mut sy, mut count) =
+                    gather_rolling((xa, ya), u, px, py, po, &rows, sz);
+                if grid.on_seam(cell) {
+                    grid.seam_rows(cell, |o, rows| {
+                        let q = (xa - o.x, ya - o.y);
+                        let (x, y, c) = gather_rolling(q, u, px, py, po, rows, sz);
+                        sx += x;
+                        sy += y;
+                        count += c;
+                    });
+                }
+                let scale = damp / count.max(1.0);
+                *ax = sx * scale;
+                *ay = sy * scale;
+            });
+        for k in 0..n {
+            self.rox[k] += self.acc_x[k];
+            self.roy[k] += self.acc_y[k];
+        }
+    }
+
+    /// The wet-grain bridge, or `None` when dry (so the force kernels compile
+    /// without the pull).
+    fn bridge(&self) -> Option<Bridge> {
+        let (strength, rupture) = self.cohesion;
+        (strength > 0.0).then(|| Bridge {
+            strength,
+            inv_rupture: 1.0 / rupture.max(1e-6),
+        })
+    }
+
+    /// Radius of the particle at packed index `k`.
+    #[inline(always)]
+    fn packed_radius(&self, k: usize) -> f32 {
+        self.pr.get(k).copied().unwrap_or(BALL_SIZE)
+    }
+
+    /// The contact friction coefficients, or `None` when frictionless (so
+    /// the contact kernels compile without the tangential term).
+    fn contact_friction(&self) -> Option<(f32, f32)> {
+        let (stat, kin) = self.friction;
+        (stat > 0.0 || kin > 0.0).then_some((stat, kin))
+    }
+
+    fn check_wall_collisions_packed(&mut self, c_opos: &mut [Vec2], bounds: &Boundaries, dt: f32) {
+        // Left and right border columns (unless periodic)
+        if !self.grid.domain.periodic_x() {
+            for y in 0..self.grid.h {
+                self.wall_cell(y * self.grid.w, c_opos);
+                self.wall_cell(y * self.grid.w + (self.grid.w - 1), c_opos);
+            }
+        }
+
+        // Bottom and top border rows (unless periodic)
+        if !self.grid.domain.periodic_y() {
+            for x in 0..self.grid.w {
+                self.wall_cell(x, c_opos);
+                self.wall_cell((self.grid.h - 1) * self.grid.w + x, c_opos);
+            }
         }
 
-        // Bottom and top border rows
-        for x in 0..GRID_W {
-            self.wall_cell(x, c_opos);
-            self.wall_cell((GRID_H - 1) * GRID_W + x, c_opos);
+        // Container wall cells
+        let inv_dt = 1.0 / dt;
+        for &cell in &self.container_cells {
+            let start = self.grid.cell_start[cell as usize] as usize;
+            let end = self.grid.cell_start[cell as usize + 1] as usize;
+            for k in start..end {
+                let mut pos = Vec2::new(self.px[k], self.py[k]);
+                let i = self.grid.indices[k] as usize;
+                let mut v = (pos - c_opos[i]) * inv_dt;
+                if bounds.collide_container(&mut pos, &mut v, self.packed_radius(k)) {
+                    c_opos[i] = pos - v * dt;
+                    self.px[k] = pos.x;
+                    self.py[k] = pos.y;
+                }
+            }
         }
     }
 
@@ -997,14 +1695,83 @@ impl GranularSolver {
     fn wall_cell(&mut self, cell: usize, c_opos: &mut [Vec2]) {
         let start = self.grid.cell_start[cell] as usize;
         let end = self.grid.cell_start[cell + 1] as usize;
+        let friction = self.contact_friction();
         for k in start..end {
-            let mut pos = Vec2::new(self.px[k], self.py[k]);
+            let before = Vec2::new(self.px[k], self.py[k]);
+            let mut pos = before;
             let i = self.grid.indices[k] as usize;
-            resolve_wall_collision(&mut pos, &mut c_opos[i], wall_salt(i));
+            let (r, salt) = (self.packed_radius(k), wall_salt(i));
+            resolve_wall_collision(&mut pos, &mut c_opos[i], r, salt, &self.grid.domain);
+            if let Some(f) = friction {
+                wall_friction(&mut pos, before, c_opos[i], f);
+            }
             self.px[k] = pos.x;
             self.py[k] = pos.y;
         }
     }
+
+    /// Grid cells (inclusive x/y ranges) that can hold a particle touching
+    /// collider `k` (obstacle or body). The grid was binned at the start of
+    /// the substep, so the bounds are padded by a cell on top of the largest
+    /// radius to cover the drift since.
+    fn obstacle_cells(&self, bounds: &Boundaries, k: usize) -> (usize, usize, usize, usize) {
+        let pad = self.r_max + self.grid.cell_size.max_element();
+        let (lo, hi) = bounds.bounds_of(k, pad);
+        let g = &self.grid;
+        (g.column(lo.x), g.column(hi.x), g.row(lo.y), g.row(hi.y))
+    }
+
+    /// Obstacle contacts for the serial engine. The Verlet velocity is
+    /// `(pos − opos)/Δt`, so the contact response is applied to it and folded
+    /// back into `opos`, exactly as the box walls do; untouched particles are
+    /// not rewritten.
+    fn check_obstacles_serial(
+        &self,
+        c_pos: &mut [Vec2],
+        c_opos: &mut [Vec2],
+        radii: Option<&[f32]>,
+        bounds: &mut Boundaries,
+        dt: f32,
+    ) {
+        let inv_dt = 1.0 / dt;
+        for k in 0..bounds.colliders() {
+            let (x0, x1, y0, y1) = self.obstacle_cells(bounds, k);
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    for &i in self.grid.cell(y * self.grid.w + x) {
+                        let i = i as usize;
+                        let mut v = (c_pos[i] - c_opos[i]) * inv_dt;
+                        let r = radii.map_or(BALL_SIZE, |r| r[i]);
+                        if bounds.collide_with(k, &mut c_pos[i], &mut v, r) {
+                            c_opos[i] = c_pos[i] - v * dt;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Obstacle contacts for the packed engine (see `check_obstacles_serial`).
+    fn check_obstacles_packed(&mut self, c_opos: &mut [Vec2], bounds: &mut Boundaries, dt: f32) {
+        let inv_dt = 1.0 / dt;
+        for k in 0..bounds.colliders() {
+            let (x0, x1, y0, y1) = self.obstacle_cells(bounds, k);
+            for y in y0..=y1 {
+                let start = self.grid.cell_start[y * self.grid.w + x0] as usize;
+                let end = self.grid.cell_start[y * self.grid.w + x1 + 1] as usize;
+                for p in start..end {
+                    let mut pos = Vec2::new(self.px[p], self.py[p]);
+                    let i = self.grid.indices[p] as usize;
+                    let mut v = (pos - c_opos[i]) * inv_dt;
+                    if bounds.collide_with(k, &mut pos, &mut v, self.packed_radius(p)) {
+                        c_opos[i] = pos - v * dt;
+                        self.px[p] = pos.x;
+                        self.py[p] = pos.y;
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl FluidSolver for GranularSolver {
@@ -1012,6 +1779,15 @@ impl FluidSolver for GranularSolver {
         "Granular"
     }
 
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.c_force, keep);
+        retain_flagged(&mut self.c_farfield, keep);
+        // Survivors were renumbered; invalidate the neighbor lists exactly as
+        // a reorder does (the far-field cache stays valid for them).
+        self.neighbor_lists.start.clear();
+        self.stale_steps = VERLET_REBUILD_RETRY - 1;
+    }
+
     fn verlet_lists_enabled(&self) -> bool {
         self.use_verlet_lists
     }
@@ -1037,6 +1813,18 @@ impl FluidSolver for GranularSolver {
         self.par_min = par_min;
     }
 
+    fn set_friction(&mut self, stat: f32, kinetic: f32, rolling: f32) {
+        self.friction = (stat.max(0.0), kinetic.max(0.0));
+        self.rolling = rolling.clamp(0.0, 1.0);
+    }
+
+    fn set_cohesion(&mut self, strength: f32, rupture: f32) {
+        // Documented range: a longer gap would reach past the stencil.
+        self.cohesion = (strength.max(0.0), rupture.clamp(0.0, MAX_RUPTURE));
+        // The cached far field was gathered with the old bridge.
+        self.c_farfield.clear();
+    }
+
     fn toggle_verlet_lists(&mut self) {
         self.use_verlet_lists = !self.use_verlet_lists;
         println!(
@@ -1053,14 +1841,45 @@ impl FluidSolver for GranularSolver {
         self.forces_direct_impl(positions)
     }
 
-    fn substep(&mut self, dt: f32, gravity: Vec2, share: &mut ShareData, c_opos: &mut Vec<Vec2>) {
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
         let n = share.c_pos.len();
-        // The cannon pushes to c_pos/c_opos/c_color only; grow c_force to match
-        // (new particles start with zero accumulated force, as before).
+        // The cannon and emitters push to c_pos/c_opos/c_color only; grow
+        // c_force to match (new particles start with zero accumulated force).
         self.c_force.resize(n, Vec2::ZERO);
+        // Sized contacts only once some radius differs from BALL_SIZE; the
+        // cells grow with the largest radius so one cell still spans the
+        // force cutoff.
+        let (r_min, r_max) = if share.c_radius.len() == n {
+            share
+                .c_radius
+                .iter()
+                .fold((BALL_SIZE, BALL_SIZE), |(lo, hi), &r| {
+                    (lo.min(r), hi.max(r))
+                })
+        } else {
+            (BALL_SIZE, BALL_SIZE)
+        };
+        let sized = r_min != BALL_SIZE || r_max != BALL_SIZE;
+        self.r_max = r_max;
+        let min_cell = GRID_SIZE * (r_max / BALL_SIZE).max(1.0);
+        if self.grid.domain != *bounds.domain() || self.grid.min_cell != min_cell {
+            // Resized box or particles: re-tile (the container cells follow
+            // below, on the generation bump or the new reach).
+            self.grid = CsrGrid::new(bounds.domain(), min_cell);
+        }
 
         self.integrate(dt, gravity, share, c_opos);
 
+        // Particles that crossed a periodic seam re-enter on the far side.
+        wrap_periodic(&self.grid.domain, &mut share.c_pos, c_opos);
+
         // Build the CSR grid once per substep; forces and all solver
         // iterations reuse it (positions move a small fraction of a cell).
         let t = Instant::now();
@@ -1072,15 +1891,68 @@ impl FluidSolver for GranularSolver {
             self.reorder_particles(share, c_opos);
         }
 
+        self.update_container_cells(bounds);
         let ShareData {
-            c_pos, perf_stats, ..
+            c_pos,
+            c_radius,
+            perf_stats,
+            ..
         } = share;
-        self.check_ball_collisions(c_pos, c_opos, perf_stats);
+        let radii = sized.then_some(c_radius.as_slice());
+        self.check_ball_collisions(c_pos, c_opos, radii, bounds, dt, perf_stats);
+        // The contact projection can nudge a particle back across a seam.
+        wrap_periodic(&self.grid.domain, c_pos, c_opos);
 
         self.frame_count += 1;
     }
 }
 
+/// Move particles that left the box across a periodic seam to their image
+/// inside it; opos moves with them, so the Verlet velocity is unchanged.
+fn wrap_periodic(domain: &Domain, c_pos: &mut [Vec2], c_opos: &mut [Vec2]) {
+    if !(domain.periodic_x() || domain.periodic_y()) {
+        return;
+    }
+    for (p, op) in c_pos.iter_mut().zip(c_opos.iter_mut()) {
+        let wrapped = domain.wrap(*p);
+        *op += wrapped - *p;
+        *p = wrapped;
+    }
+}
+
+/// Mass of a particle of radius `r`, relative to a BALL_SIZE one (unit area
+/// density).
+#[inline(always)]
+fn mass(r: f32) -> f32 {
+    let m = r / BALL_SIZE;
+    m * m
+}
+
+/// `force` between two particles, or with their `radii` the kernel at the
+/// distance scaled to a pair of BALL_SIZE balls (it then reaches out to
+/// 1.25·(r_a + r_b), still within a cell sized for the largest radius).
+/// A `bridge` adds the wet pull at that same distance.
+#[inline(always)]
+fn force_between(
+    pos_a: Vec2,
+    pos_b: Vec2,
+    scale: f32,
+    radii: Option<(f32, f32)>,
+    bridge: Option<Bridge>,
+) -> Vec2 {
+    let dir = match radii {
+        None => pos_a - pos_b,
+        Some((ra, rb)) => (pos_a - pos_b) * (2.0 * BALL_SIZE / (ra + rb)),
+    };
+    let f = force(dir, Vec2::ZERO, scale);
+    match bridge {
+        // Pair forces are accumulated ÷8 (the historical factor), so the
+        // pull, in gravity units, goes in ×8.
+        Some(b) if dir != Vec2::ZERO => f - dir * (8.0 * b.pull(dir.length())),
+        _ => f,
+    }
+}
+
 #[inline(always)]
 fn force(pos_a: Vec2, pos_b: Vec2, scale: f32) -> Vec2 {
     let dir = pos_a - pos_b;
@@ -0,0 +1,446 @@
+//! Implicit Incompressible SPH (Ihmsen, Cornelis, Solenthaler, Horvath &
+//! Teschner, IEEE TVCG 2014) as a `FluidSolver` strategy.
+//!
+//! IISPH and DFSPH are the same family — both derive a pressure force from an
+//! implicit solve — and are kept side by side for A/B comparisons of the
+//! pressure solver on identical scenes. Where DFSPH splits the job into a
+//! constant-density and a divergence-free solve, IISPH solves one pressure
+//! Poisson equation per step, for the pressures p_i whose accelerations bring
+//! the predicted density back to rest:
+//!   Δt² Σ_j (aᵖ_i − aᵖ_j)·∇W_ij = ρ0 − ρ*_i,
+//!   aᵖ_i = −w_i Σ_j (p_i/ρ_i² + p_j/ρ_j²) ∇W_ij
+//! with ρ*_i = ρ_i + Δt Σ_j (v*_i − v*_j)·∇W_ij the density after the
+//! non-pressure velocities v*. It is solved matrix-free by relaxed Jacobi
+//! (ω = 0.5 in the paper), p_i ← p_i + ω (ρ0 − ρ*_i − (Ap)_i) / a_ii, with
+//! the pressures clamped at zero so the free surface is never pulled together.
+//! The paper warm-starts from half the previous step's pressures; at the
+//! 480 Hz substep the hydrostatic part barely changes between steps, and
+//! halving it left Jacobi rebuilding it every step (the solve hit any cap),
+//! so the previous pressures are reused whole.
+//!
+//! Per step:
+//!   1. build the neighbor grid; compute densities ρ_i and the diagonal
+//!      a_ii = −Δt² (w_i |Σ_j ∇W_ij|² + Σ_j w_j |∇W_ij|²) / ρ_i²
+//!   2. apply gravity and XSPH viscosity, giving v*; predict ρ*_i
+//!   3. iterate the pressure solve until the mean predicted compression is
+//!      below [`IisphParams::max_density_error`] (at least two sweeps)
+//!   4. v += Δt aᵖ, advect, and project onto the box and out of the obstacles
+//!
+//! The box walls have no boundary particles, so they enter the solve only
+//! as contacts: a particle touching a wall loses the into-wall part of aᵖ_i,
+//! as the advection step would take it away. Without that the floor row
+//! asks for a push the wall cancels, and the solve stalls at a few percent
+//! compression there.
+//!
+//! Masses, phases and the unit system follow [`Dfsph`](super::Dfsph): mⱼ = 1
+//! so ρ_i = Σ_j W_ij, and a heavier phase enters only through w_i = 1/m_i.
+//! The a_ii bracket is DFSPH's α_i denominator, so both solvers guard their
+//! poorly-supported surface particles the same way.
+
+use glam::Vec2;
+use rayon::prelude::*;
+
+use super::phase::inv_masses;
+use super::sph::{
+    advect, effective_gravity, fit_particles, sampled_density_ratio, SphGrid, SphKernel, WallBox,
+};
+use super::{report, retain_flagged, Boundaries, Domain, FluidSolver, Phase, ShareData};
+use crate::constants::BALL_SIZE;
+
+// The smoothing radius and rest spacing of DFSPH, so the two pack the same
+// particle count to the same volume and compare like for like.
+const IISPH_H: f32 = 15.0;
+const IISPH_REST_SPACING: f32 = 2.0 * BALL_SIZE;
+
+// A particle this close to a wall is in contact with it: the pressure solve
+// drops the into-wall part of its acceleration, as the advection step would.
+const WALL_CONTACT: f32 = 0.5;
+
+/// Tunable IISPH coefficients.
+#[derive(Clone, Copy, Debug)]
+pub struct IisphParams {
+    /// Iteration cap for the pressure solve.
+    pub max_iters: usize,
+    /// Mean predicted compression (ρ − ρ0)/ρ0 at which the pressure solve
+    /// stops early.
+    pub max_density_error: f32,
+    /// Jacobi relaxation ω (the paper's 0.5).
+    pub omega: f32,
+    /// XSPH viscosity coefficient, as [`DfsphParams::xsph_c`](super::DfsphParams::xsph_c).
+    pub xsph_c: f32,
+    /// Per-substep velocity clamp as a fraction of h/Δt, as DFSPH's.
+    pub vmax_frac: f32,
+}
+
+impl Default for IisphParams {
+    fn default() -> Self {
+        Self {
+            max_iters: 50,
+            // 0.1%, the paper's target. A settling blob needs ~13 sweeps a
+            // substep (DFSPH's fixed 3 + 2 are cheaper but less exact).
+            max_density_error: 1e-3,
+            omega: 0.5,
+            xsph_c: 0.05,
+            vmax_frac: 0.5,
+        }
+    }
+}
+
+/// Implicit Incompressible SPH solver state. Velocities are explicit; the
+/// pressures persist between steps as the solve's warm start.
+pub struct Iisph {
+    grid: SphGrid,
+    kernel: SphKernel,
+    rest_density: f32,
+    params: IisphParams,
+    vel: Vec<Vec2>,
+    pressure: Vec<f32>,
+    density: Vec<f32>,
+    rho_adv: Vec<f32>,   // ρ*_i, the density after the non-pressure velocities
+    diag: Vec<f32>,      // a_ii
+    accel: Vec<Vec2>,    // aᵖ_i from the current pressures
+    vscratch: Vec<Vec2>, // XSPH double-buffer
+    phases: Vec<Phase>,
+    inv_mass: Vec<f32>, // w_i = 1/m_i from the particle's phase
+    walls: WallBox,     // the box's inner corners (±∞ on open/periodic edges)
+}
+
+impl Default for Iisph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iisph {
+    pub fn new() -> Self {
+        let kernel = SphKernel::new(IISPH_H);
+        Self {
+            grid: SphGrid::new(IISPH_H, &Domain::default()),
+            rest_density: kernel.rest_density(IISPH_REST_SPACING),
+            kernel,
+            params: IisphParams::default(),
+            vel: Vec::new(),
+            pressure: Vec::new(),
+            density: Vec::new(),
+            rho_adv: Vec::new(),
+            diag: Vec::new(),
+            accel: Vec::new(),
+            vscratch: Vec::new(),
+            phases: Vec::new(),
+            inv_mass: Vec::new(),
+            walls: WallBox::default(),
+        }
+    }
+
+    pub fn set_params(&mut self, params: IisphParams) {
+        self.params = params;
+    }
+
+    /// ρ_i (incl. self term) and the Jacobi diagonal a_ii, read-only over the
+    /// grid ⇒ trivially parallel.
+    fn compute_density_diag(&mut self, x: &[Vec2], dt: f32) {
+        let grid = &self.grid;
+        let kernel = &self.kernel;
+        let inv_mass = &self.inv_mass;
+        self.density
+            .par_iter_mut()
+            .zip(self.diag.par_iter_mut())
+            .enumerate()
+            .for_each(|(i, (rho_i, diag_i))| {
+                let xi = x[i];
+                let mut rho = 0.0f32;
+                let mut grad_sum = Vec2::ZERO; // Σ_j ∇W_ij
+                let mut grad2_sum = 0.0f32; // Σ_j w_j |∇W_ij|²
+                grid.for_neighbors(xi, |j, o| {
+                    let d = xi - x[j] - o;
+                    rho += kernel.w(d.length_squared());
+                    if j != i {
+                        let g = kernel.grad(d);
+                        grad_sum += g;
+                        grad2_sum += inv_mass[j] * g.length_squared();
+                    }
+                });
+                *rho_i = rho;
+                let denom = inv_mass[i] * grad_sum.length_squared() + grad2_sum;
+                // Same surface guard as DFSPH's α_i: a particle with a
+                // handful of neighbors would take a runaway pressure from
+                // its near-zero diagonal, so it gets none.
+                *diag_i = if denom > 2e-6 {
+                    -dt * dt * denom / (rho * rho)
+                } else {
+                    0.0
+                };
+            });
+    }
+
+    /// XSPH viscosity, as `Dfsph::apply_xsph`.
+    fn apply_xsph(&mut self, x: &[Vec2]) {
+        let c = self.params.xsph_c;
+        if c == 0.0 {
+            return;
+        }
+        {
+            let grid = &self.grid;
+            let kernel = &self.kernel;
+            let vel = &self.vel;
+            let inv_rho0 = 1.0 / self.rest_density;
+            self.vscratch.par_iter_mut().enumerate().for_each(|(i, o)| {
+                let xi = x[i];
+                let vi = vel[i];
+                let mut acc = Vec2::ZERO;
+                grid.for_neighbors(xi, |j, o| {
+                    if j == i {
+                        return;
+                    }
+                    acc += (vel[j] - vi) * kernel.w((xi - x[j] - o).length_squared());
+                });
+                *o = vi + acc * (c * inv_rho0);
+            });
+        }
+        std::mem::swap(&mut self.vel, &mut self.vscratch);
+    }
+
+    /// ρ*_i = ρ_i + Δt Σ_j (v*_i − v*_j)·∇W_ij.
+    fn predict_density(&mut self, x: &[Vec2], dt: f32) {
+        let grid = &self.grid;
+        let kernel = &self.kernel;
+        let vel = &self.vel;
+        let density = &self.density;
+        self.rho_adv.par_iter_mut().enumerate().for_each(|(i, r)| {
+            let xi = x[i];
+            let vi = vel[i];
+            let mut drho = 0.0f32;
+            grid.for_neighbors(xi, |j, o| {
+                if j == i {
+                    return;
+                }
+                drho += (vi - vel[j]).dot(kernel.grad(xi - x[j] - o));
+            });
+            *r = density[i] + dt * drho;
+        });
+    }
+
+    /// aᵖ_i = −w_i Σ_j (p_i/ρ_i² + p_j/ρ_j²) ∇W_ij from the current pressures.
+    fn compute_pressure_accel(&mut self, x: &[Vec2]) {
+        let grid = &self.grid;
+        let kernel = &self.kernel;
+        let pressure = &self.pressure;
+        let density = &self.density;
+        let inv_mass = &self.inv_mass;
+        let WallBox { lo, hi } = self.walls;
+        self.accel.par_iter_mut().enumerate().for_each(|(i, a)| {
+            let xi = x[i];
+            let pi = pressure[i] / (density[i] * density[i]);
+            let mut acc = Vec2::ZERO;
+            grid.for_neighbors(xi, |j, o| {
+                if j == i {
+                    return;
+                }
+                let pj = pressure[j] / (density[j] * density[j]);
+                acc += kernel.grad(xi - x[j] - o) * (pi + pj);
+            });
+            let mut ai = acc * -inv_mass[i];
+            if xi.x <= lo.x + WALL_CONTACT {
+                ai.x = ai.x.max(0.0);
+            } else if xi.x >= hi.x - WALL_CONTACT {
+                ai.x = ai.x.min(0.0);
+            }
+            if xi.y <= lo.y + WALL_CONTACT {
+                ai.y = ai.y.max(0.0);
+            } else if xi.y >= hi.y - WALL_CONTACT {
+                ai.y = ai.y.min(0.0);
+            }
+            *a = ai;
+        });
+    }
+
+    /// The relaxed-Jacobi pressure solve. Returns the iterations used.
+    fn pressure_solve(&mut self, x: &[Vec2], dt: f32) -> usize {
+        let rho0 = self.rest_density;
+        let omega = self.params.omega;
+        let dt2 = dt * dt;
+        let n = x.len();
+        let mut iters = 0;
+        while iters < self.params.max_iters {
+            iters += 1;
+            self.compute_pressure_accel(x);
+            let grid = &self.grid;
+            let kernel = &self.kernel;
+            let accel = &self.accel;
+            let rho_adv = &self.rho_adv;
+            let diag = &self.diag;
+            // Each particle's update reads only the accelerations, so the
+            // pressures can be overwritten in place. The error is the
+            // compression predicted by the pressures going *into* the sweep.
+            let error: f32 = self
+                .pressure
+                .par_iter_mut()
+                .enumerate()
+                .map(|(i, p)| {
+                    let xi = x[i];
+                    let ai = accel[i];
+                    let mut ap = 0.0f32; // (Ap)_i
+                    grid.for_neighbors(xi, |j, o| {
+                        if j == i {
+                            return;
+                        }
+                        ap += (ai - accel[j]).dot(kernel.grad(xi - x[j] - o));
+                    });
+                    ap *= dt2;
+                    let residual = rho0 - rho_adv[i] - ap;
+                    if diag[i] != 0.0 {
+                        *p = (*p + omega * residual / diag[i]).max(0.0);
+                    } else {
+                        *p = 0.0;
+                    }
+                    (-residual).max(0.0)
+                })
+                .sum();
+            if iters >= 2 && error / (n as f32 * rho0) <= self.params.max_density_error {
+                break;
+            }
+        }
+        self.compute_pressure_accel(x);
+        iters
+    }
+}
+
+impl FluidSolver for Iisph {
+    fn name(&self) -> &'static str {
+        "IISPH"
+    }
+
+    fn set_iisph_params(&mut self, params: IisphParams) {
+        self.set_params(params);
+    }
+
+    fn set_phases(&mut self, phases: &[Phase]) {
+        self.phases = phases.to_vec();
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.vel, keep);
+        retain_flagged(&mut self.pressure, keep);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
+        let n = share.c_pos.len();
+        if n == 0 {
+            return;
+        }
+        c_opos.resize(n, Vec2::ZERO);
+        fit_particles(
+            &mut self.vel,
+            &share.c_pos,
+            c_opos,
+            dt,
+            &mut [
+                &mut self.pressure,
+                &mut self.density,
+                &mut self.rho_adv,
+                &mut self.diag,
+                &mut self.accel,
+                &mut self.vscratch,
+            ],
+        );
+        inv_masses(&self.phases, &share.c_phase, n, &mut self.inv_mass);
+
+        let accel = effective_gravity(gravity);
+
+        let x = &share.c_pos;
+
+        // 1. Neighbor grid + per-step precompute (ρ_i, a_ii).
+        self.grid.fit(bounds.domain());
+        self.grid.build(x);
+        self.compute_density_diag(x, dt);
+
+        // 2. Non-pressure velocities v* and the density they predict.
+        for v in &mut self.vel {
+            *v += accel * dt;
+        }
+        self.apply_xsph(x);
+        self.predict_density(x, dt);
+
+        // The box the solve and the advection both respect; open and
+        // periodic edges have no wall (periodic axes wrap).
+        self.walls = WallBox::new(bounds.domain());
+
+        // 3. Pressure solve, then the pressure acceleration it leaves.
+        let pressure_iters = self.pressure_solve(x, dt);
+        for (v, a) in self.vel.iter_mut().zip(&self.accel) {
+            *v += *a * dt;
+        }
+
+        // 4. Advect and project onto the box, killing the into-wall velocity,
+        // then out of the obstacles (as DFSPH).
+        let vmax = self.params.vmax_frac * IISPH_H / dt;
+        let walls = self.walls;
+        advect(&mut share.c_pos, &mut self.vel, dt, vmax, &walls, bounds);
+
+        // 5. Shared/rendered bookkeeping and diagnostics, as DFSPH.
+        let (density, rho0) = (&self.density, self.rest_density);
+        let ratio = sampled_density_ratio(n, |i| density[i] / rho0);
+        report(share, c_opos, &self.vel, dt, ratio);
+        share.perf_stats.pressure_iters = pressure_iters;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{HEIGHT, WIDTH};
+    use crate::physics::tests::block_scene;
+    use crate::physics::{Strategy, PHYS_TIME_STEP};
+
+    /// An IISPH blob dropped in the box must stay finite, stay inside the
+    /// walls, and settle toward rest with its pressure solve converging well
+    /// under the iteration cap.
+    #[test]
+    fn iisph_blob_is_stable() {
+        let (mut physics, mut share) = block_scene(
+            Strategy::Iisph,
+            Domain::default(),
+            Vec2::new(500.0, 300.0),
+            (30, 30),
+            Vec2::ZERO,
+        );
+        let mut iters = 0;
+        for _ in 0..1500 {
+            physics.step(PHYS_TIME_STEP, &mut share);
+            iters += share.perf_stats.pressure_iters;
+        }
+
+        let nan = share
+            .c_pos
+            .iter()
+            .filter(|p| !p.x.is_finite() || !p.y.is_finite())
+            .count();
+        let escaped = share
+            .c_pos
+            .iter()
+            .filter(|p| p.x < -1.0 || p.x > WIDTH + 1.0 || p.y < -1.0 || p.y > HEIGHT + 1.0)
+            .count();
+        assert_eq!(nan, 0, "IISPH produced NaNs");
+        assert_eq!(escaped, 0, "IISPH particles escaped the box");
+        // The same bar as DFSPH's blob: a blown-up solve pins the clamp
+        // (mean_speed ≈ 117), a settled one decays toward ~8.
+        assert!(
+            share.perf_stats.mean_speed < 15.0,
+            "IISPH did not settle: mean_speed = {}",
+            share.perf_stats.mean_speed
+        );
+        // ~13 sweeps on average; a solve that stalls sits at the cap.
+        let mean_iters = iters as f32 / 1500.0;
+        assert!(
+            mean_iters < 0.5 * IisphParams::default().max_iters as f32,
+            "IISPH pressure solve averaged {mean_iters} iterations"
+        );
+    }
+}
@@ -0,0 +1,1029 @@
+//! Free-surface lattice Boltzmann (D2Q9) as a `FluidSolver` strategy: the
+//! one Eulerian method here. The fluid is a field of particle distributions
+//! on a fixed lattice; the particles in `ShareData::c_pos` are passive
+//! tracers carried by it, there to be drawn like every other model's.
+//!
+//! Per substep:
+//!   1. classify the cells against the obstacles, bodies and container:
+//!      a cell whose center turns solid hands its mass to its fluid
+//!      neighbors, one the solid leaves becomes an interface cell again.
+//!   2. newcomers (the initial set, emitters, the cannon) deposit their
+//!      mass into the cells around them, with their velocity.
+//!   3. a fixed number of lattice steps (`LbmParams::lattice_steps`), each:
+//!      - **stream** the populations to the neighboring cells; links into a
+//!        wall reflect off it (free slip), links into a solid bounce back
+//!        (moving with the solid, the momentum they exchange over the gas
+//!        pressure booked against the bodies), links from the gas or past
+//!        an outflow edge are rebuilt at atmospheric pressure (Körner et al.
+//!        2005), what streams out over the edge being lost;
+//!      - track the mass of the interface cells from the populations they
+//!        swap with their neighbors, weighted by how full both are;
+//!      - **collide** toward equilibrium — BGK, or the cumulant operator
+//!        (Geier et al. 2015) that stays stable at much lower viscosity —
+//!        with gravity as a velocity shift;
+//!      - fill the interface cells that overflowed and empty those that ran
+//!        dry, converting their neighbors to keep the interface closed and
+//!        passing the excess mass on (Thürey 2007).
+//!   4. **tracers** take the velocity of the fluid cells around them and
+//!      move with it; where there is none (a droplet the lattice cannot
+//!      resolve, a splash), they fly ballistically until they land.
+//!
+//! The lattice has one cell per particle at rest, so each tracer stands for
+//! one cell's worth of mass: a removed tracer (drain, outflow edge,
+//! `Physics::remove_particles`) takes its share of the mass in the cells
+//! around it with it. Nothing ties the tracers to the mass otherwise, so
+//! they thin out where the fluid stretches and crowd where it pools.
+//!
+//! Everything is local to a cell and its neighbors, so a blob far away is
+//! unaffected by anything done to another within a few hundred lattice
+//! steps. Phases are ignored: the fluid has unit density throughout.
+
+use glam::Vec2;
+
+use super::rigid::FLUID_PARTICLE_MASS;
+use super::{
+    clamp_wall, report, retain_flagged, Boundaries, Domain, Edge, FluidSolver, ShareData,
+    PHYS_TIME_STEP,
+};
+use crate::constants::BALL_SIZE;
+
+// Cell size: the rest spacing, one particle per cell. Cells are stretched
+// so whole ones tile the box (`Domain::tiling`).
+const LBM_DX: f32 = 2.0 * BALL_SIZE;
+// D2Q9 velocities, weights and the opposite of each direction.
+const E: [(isize, isize); 9] = [
+    (0, 0),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (0, -1),
+    (1, 1),
+    (-1, 1),
+    (-1, -1),
+    (1, -1),
+];
+const W: [f32; 9] = [
+    4.0 / 9.0,
+    1.0 / 9.0,
+    1.0 / 9.0,
+    1.0 / 9.0,
+    1.0 / 9.0,
+    1.0 / 36.0,
+    1.0 / 36.0,
+    1.0 / 36.0,
+    1.0 / 36.0,
+];
+const OPP: [usize; 9] = [0, 3, 4, 1, 2, 7, 8, 5, 6];
+// Neighbor-table sentinels: past a wall, or past an outflow edge (gas).
+const WALL: u32 = u32::MAX;
+const OPEN: u32 = u32::MAX - 1;
+// Fill/empty hysteresis of the interface cells, as a fraction of ρ.
+const KAPPA: f32 = 1e-3;
+// Lattice speed cap (the sound speed is 1/√3): a cell pushed past it is
+// reset to equilibrium at the capped velocity rather than blowing up.
+const U_MAX: f32 = 0.25;
+// The relaxation time never gets closer to ½ than this.
+const TAU_MIN: f32 = 0.505;
+
+/// The collision operator of the lattice Boltzmann strategy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LbmCollision {
+    /// Single relaxation time (Bhatnagar–Gross–Krook): every population
+    /// relaxes toward the equilibrium at the rate the viscosity sets.
+    #[default]
+    Bgk,
+    /// Cumulant (Geier et al. 2015): the shear moments relax at the
+    /// viscosity's rate, the bulk and higher cumulants straight to
+    /// equilibrium. Stable at viscosities where BGK is not.
+    Cumulant,
+}
+
+/// Tunable lattice Boltzmann coefficients.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LbmParams {
+    pub collision: LbmCollision,
+    /// Kinematic viscosity ν in px²/s. Sets the relaxation time
+    /// τ = ½ + 3ν·Δt/Δx², floored just above ½.
+    pub viscosity: f32,
+    /// Lattice steps per engine substep. The lattice speed of a fall grows
+    /// with the lattice time step, and must stay well below the sound
+    /// speed; fewer steps are cheaper but hit the speed cap sooner.
+    pub lattice_steps: usize,
+}
+
+impl Default for LbmParams {
+    fn default() -> Self {
+        Self {
+            collision: LbmCollision::default(),
+            viscosity: 1500.0,
+            lattice_steps: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Kind {
+    Gas,
+    Interface,
+    Fluid,
+    Solid,
+}
+
+impl Kind {
+    /// Whether the cell holds fluid (fluid or interface).
+    #[inline(always)]
+    fn wet(self) -> bool {
+        matches!(self, Kind::Interface | Kind::Fluid)
+    }
+}
+
+/// Equilibrium population in direction `i` at density `rho`, velocity `u`.
+#[inline(always)]
+fn feq(i: usize, rho: f32, u: Vec2) -> f32 {
+    let eu = E[i].0 as f32 * u.x + E[i].1 as f32 * u.y;
+    W[i] * rho * (1.0 + 3.0 * eu + 4.5 * eu * eu - 1.5 * u.length_squared())
+}
+
+/// Density and velocity of a cell's populations.
+#[inline(always)]
+fn moments(f: &[f32; 9]) -> (f32, Vec2) {
+    let mut rho = 0.0;
+    let mut m = Vec2::ZERO;
+    for (i, &fi) in f.iter().enumerate() {
+        rho += fi;
+        m += Vec2::new(E[i].0 as f32, E[i].1 as f32) * fi;
+    }
+    let rho = rho.max(1e-6);
+    (rho, m / rho)
+}
+
+/// Central moments of three populations (velocities −1, 0, +1) about `u`:
+/// one line of the chimera transform.
+#[inline(always)]
+fn to_central(m: f32, z: f32, p: f32, u: f32) -> (f32, f32, f32) {
+    let k0 = m + z + p;
+    let k1 = (p - m) - u * k0;
+    let k2 = (p + m) - 2.0 * u * (p - m) + u * u * k0;
+    (k0, k1, k2)
+}
+
+/// Inverse of [`to_central`].
+#[inline(always)]
+fn from_central(k0: f32, k1: f32, k2: f32, u: f32) -> (f32, f32, f32) {
+    let z = k0 * (1.0 - u * u) - 2.0 * u * k1 - k2;
+    let m = 0.5 * (k0 * (u * u - u) + k1 * (2.0 * u - 1.0) + k2);
+    let p = 0.5 * (k0 * (u * u + u) + k1 * (2.0 * u + 1.0) + k2);
+    (m, z, p)
+}
+
+// The populations of each row (ey = −1, 0, +1) in the order ex = −1, 0, +1.
+const ROWS: [[usize; 3]; 3] = [[7, 4, 8], [3, 0, 1], [6, 2, 5]];
+
+/// Cumulant collision of one cell (Geier et al. 2015, in 2D): shear rate
+/// `omega`, bulk and higher orders relaxed to equilibrium, and the force
+/// `g` (lattice units, already half applied to `u`) applied by reversing
+/// the first central moments.
+fn collide_cumulant(f: &mut [f32; 9], rho: f32, u: Vec2, g: Vec2, omega: f32) {
+    // x-lines within each row, then the y-line of each x-moment.
+    let mut k = [[0.0f32; 3]; 3]; // k[a][row] after the x pass
+    for (row, idx) in ROWS.iter().enumerate() {
+        let (k0, k1, k2) = to_central(f[idx[0]], f[idx[1]], f[idx[2]], u.x);
+        k[0][row] = k0;
+        k[1][row] = k1;
+        k[2][row] = k2;
+    }
+    let mut c = [[0.0f32; 3]; 3]; // c[a][b]: central moment κ_ab
+    for a in 0..3 {
+        let (k0, k1, k2) = to_central(k[a][0], k[a][1], k[a][2], u.y);
+        c[a] = [k0, k1, k2];
+    }
+
+    // Second order: the deviatoric part relaxes at the shear rate, the
+    // trace straight to its equilibrium 2ρ/3.
+    let trace = 2.0 * rho / 3.0;
+    let dev = (1.0 - omega) * (c[2][0] - c[0][2]);
+    let k11 = (1.0 - omega) * c[1][1];
+    let (k20, k02) = (0.5 * (trace + dev), 0.5 * (trace - dev));
+    // Third and fourth cumulants to equilibrium (zero).
+    let k22 = (k20 * k02 + 2.0 * k11 * k11) / rho;
+    let post = [
+        [rho, rho * g.y * 0.5, k02],
+        [rho * g.x * 0.5, k11, 0.0],
+        [k20, 0.0, k22],
+    ];
+
+    for a in 0..3 {
+        let (m, z, p) = from_central(post[a][0], post[a][1], post[a][2], u.y);
+        k[a] = [m, z, p];
+    }
+    for (row, idx) in ROWS.iter().enumerate() {
+        let (m, z, p) = from_central(k[0][row], k[1][row], k[2][row], u.x);
+        f[idx[0]] = m;
+        f[idx[1]] = z;
+        f[idx[2]] = p;
+    }
+}
+
+/// Lattice Boltzmann state: the lattice (populations, mass, cell kinds and
+/// the neighbor table) and the tracers' positions and velocities.
+pub struct Lbm {
+    params: LbmParams,
+    // Tracers: where they were left at the end of the last substep, their
+    // velocity in px/s, and the mass of a stranded droplet each carries.
+    x: Vec<Vec2>,
+    vel: Vec<Vec2>,
+    carry: Vec<f32>,
+    // Lattice: `nx × ny` cells of `dx`, for the domain they were sized from.
+    nx: usize,
+    ny: usize,
+    dx: Vec2,
+    domain: Domain,
+    // Neighbor of each cell in each direction, or `WALL` / `OPEN`.
+    nbr: Vec<[u32; 9]>,
+    // For the `WALL` links: the cell and direction the population coming
+    // back off the wall left from.
+    slip: Vec<[(u32, u8); 9]>,
+    // The lattice direction closest to gravity's (0 without gravity).
+    down: usize,
+    kind: Vec<Kind>,
+    // Populations, and the buffer they stream into.
+    f: Vec<[f32; 9]>,
+    next: Vec<[f32; 9]>,
+    // Mass, density, fill fraction (mass / density) and lattice velocity.
+    mass: Vec<f32>,
+    rho: Vec<f32>,
+    fill: Vec<f32>,
+    u: Vec<Vec2>,
+    // Surface velocity of the solid cells, lattice units.
+    wall: Vec<Vec2>,
+    // Mass of the droplets stranded this substep, for the tracers in them
+    // to carry.
+    stranded: Vec<f32>,
+    // Interface cells to fill / empty this lattice step, and the cells
+    // already converted to interface by a neighbor's filling.
+    filled: Vec<usize>,
+    emptied: Vec<usize>,
+    fresh: Vec<bool>,
+}
+
+impl Default for Lbm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lbm {
+    pub fn new() -> Self {
+        Self {
+            params: LbmParams::default(),
+            x: Vec::new(),
+            vel: Vec::new(),
+            carry: Vec::new(),
+            nx: 0,
+            ny: 0,
+            dx: Vec2::splat(LBM_DX),
+            domain: Domain::default(),
+            nbr: Vec::new(),
+            slip: Vec::new(),
+            down: 0,
+            kind: Vec::new(),
+            f: Vec::new(),
+            next: Vec::new(),
+            mass: Vec::new(),
+            rho: Vec::new(),
+            fill: Vec::new(),
+            u: Vec::new(),
+            wall: Vec::new(),
+            stranded: Vec::new(),
+            filled: Vec::new(),
+            emptied: Vec::new(),
+            fresh: Vec::new(),
+        }
+    }
+
+    pub fn set_params(&mut self, params: LbmParams) {
+        self.params = params;
+    }
+
+    /// Size the lattice to tile `domain` if it isn't already. A resize
+    /// starts from an empty lattice; the tracers then deposit their mass
+    /// into it again.
+    fn fit_grid(&mut self, domain: &Domain) {
+        if self.domain == *domain && !self.kind.is_empty() {
+            return;
+        }
+        let (nx, ny) = domain.tiling(LBM_DX);
+        self.nx = nx;
+        self.ny = ny;
+        self.dx = Vec2::new(domain.width / nx as f32, domain.height / ny as f32);
+        self.domain = *domain;
+        let n = nx * ny;
+        (self.nbr, self.slip) = (0..n).map(|c| self.neighbors(c)).unzip();
+        self.kind = vec![Kind::Gas; n];
+        self.f = vec![[0.0; 9]; n];
+        self.next = vec![[0.0; 9]; n];
+        self.mass = vec![0.0; n];
+        self.rho = vec![0.0; n];
+        self.fill = vec![0.0; n];
+        self.u = vec![Vec2::ZERO; n];
+        self.wall = vec![Vec2::ZERO; n];
+        self.stranded = vec![0.0; n];
+        self.fresh = vec![false; n];
+        // Every tracer deposits its mass again.
+        self.x.clear();
+        self.vel.clear();
+        self.carry.clear();
+    }
+
+    /// The neighbor table row of cell `c`: periodic axes wrap, a step past
+    /// any wall edge is `WALL`, past an outflow edge only `OPEN`. Along with
+    /// it, the slip row: the walls reflect specularly (free slip, like the
+    /// particle models' walls), so what comes back over a `WALL` link left
+    /// the next cell along the wall with its normal component mirrored —
+    /// in a corner, the cell itself in the opposite direction.
+    fn neighbors(&self, c: usize) -> ([u32; 9], [(u32, u8); 9]) {
+        let d = &self.domain;
+        let (i, j) = ((c % self.nx) as isize, (c / self.nx) as isize);
+        let (nx, ny) = (self.nx as isize, self.ny as isize);
+        let mut row = [0u32; 9];
+        let mut slip = [(c as u32, 0u8); 9];
+        for (k, &(ex, ey)) in E.iter().enumerate() {
+            let (mut a, mut b) = (i + ex, j + ey);
+            let mut edges = Vec::new();
+            let (mut cross_x, mut cross_y) = (false, false);
+            if a < 0 || a >= nx {
+                if d.periodic_x() {
+                    a = a.rem_euclid(nx);
+                } else {
+                    edges.push(if a < 0 { d.left } else { d.right });
+                    cross_x = true;
+                }
+            }
+            if b < 0 || b >= ny {
+                if d.periodic_y() {
+                    b = b.rem_euclid(ny);
+                } else {
+                    edges.push(if b < 0 { d.top } else { d.bottom });
+                    cross_y = true;
+                }
+            }
+            row[k] = if edges.contains(&Edge::Wall) {
+                let (sa, sb) = (if cross_x { i } else { a }, if cross_y { j } else { b });
+                let e = (
+                    if cross_x { ex } else { -ex },
+                    if cross_y { ey } else { -ey },
+                );
+                let dir = E.iter().position(|&d| d == e).unwrap_or(0);
+                slip[k] = ((sb * nx + sa) as u32, dir as u8);
+                WALL
+            } else if edges.is_empty() {
+                (b * nx + a) as u32
+            } else {
+                OPEN
+            };
+        }
+        (row, slip)
+    }
+
+    /// Center of cell `c`, px.
+    #[inline(always)]
+    fn center(&self, c: usize) -> Vec2 {
+        Vec2::new((c % self.nx) as f32 + 0.5, (c / self.nx) as f32 + 0.5) * self.dx
+    }
+
+    /// The four cells around `x` (their centers bracket it) with bilinear
+    /// weights, clamped at the walls and wrapped across periodic edges.
+    fn stencil(&self, x: Vec2) -> [(usize, f32); 4] {
+        let g = x / self.dx - Vec2::splat(0.5);
+        let base = g.floor();
+        let t = g - base;
+        let axis = |k: isize, n: usize, periodic: bool| {
+            if periodic {
+                k.rem_euclid(n as isize) as usize
+            } else {
+                k.clamp(0, n as isize - 1) as usize
+            }
+        };
+        let (px, py) = (self.domain.periodic_x(), self.domain.periodic_y());
+        let (i, j) = (base.x as isize, base.y as isize);
+        let (i0, i1) = (axis(i, self.nx, px), axis(i + 1, self.nx, px));
+        let (j0, j1) = (axis(j, self.ny, py), axis(j + 1, self.ny, py));
+        [
+            (j0 * self.nx + i0, (1.0 - t.x) * (1.0 - t.y)),
+            (j0 * self.nx + i1, t.x * (1.0 - t.y)),
+            (j1 * self.nx + i0, (1.0 - t.x) * t.y),
+            (j1 * self.nx + i1, t.x * t.y),
+        ]
+    }
+
+    /// The cell containing `x`.
+    #[inline(always)]
+    fn cell_of(&self, x: Vec2) -> usize {
+        let g = x / self.dx;
+        let i = (g.x as isize).clamp(0, self.nx as isize - 1) as usize;
+        let j = (g.y as isize).clamp(0, self.ny as isize - 1) as usize;
+        j * self.nx + i
+    }
+
+    /// Mass one tracer stands for, in lattice density units.
+    #[inline(always)]
+    fn tracer_mass(&self) -> f32 {
+        FLUID_PARTICLE_MASS / (self.dx.x * self.dx.y)
+    }
+
+    /// Set cell `c` to an interface cell at equilibrium (density `rho`,
+    /// lattice velocity `u`) holding `mass`.
+    fn make_interface(&mut self, c: usize, rho: f32, u: Vec2, mass: f32) {
+        self.kind[c] = Kind::Interface;
+        for i in 0..9 {
+            self.f[c][i] = feq(i, rho, u);
+        }
+        self.rho[c] = rho;
+        self.u[c] = u;
+        self.mass[c] = mass;
+        self.fill[c] = mass / rho;
+    }
+
+    /// Mean density and velocity of the wet neighbors of `c` (rest, if it
+    /// has none).
+    fn neighbor_state(&self, c: usize) -> (f32, Vec2) {
+        let (mut rho, mut u, mut count) = (0.0, Vec2::ZERO, 0.0);
+        for &n in &self.nbr[c][1..] {
+            if n < OPEN && self.kind[n as usize].wet() && !self.fresh[n as usize] {
+                rho += self.rho[n as usize];
+                u += self.u[n as usize];
+                count += 1.0;
+            }
+        }
+        if count > 0.0 {
+            (rho / count, u / count)
+        } else {
+            (1.0, Vec2::ZERO)
+        }
+    }
+
+    /// Add `dm` of mass moving at lattice velocity `u` to cell `c`: a gas
+    /// cell becomes an interface cell, an interface cell fills up further
+    /// (gaining the momentum but keeping its density; [`Self::convert`]
+    /// hands on any overflow) and a fluid cell gains the populations of that
+    /// mass and momentum.
+    fn deposit(&mut self, c: usize, dm: f32, u: Vec2) {
+        match self.kind[c] {
+            Kind::Solid => {}
+            Kind::Gas => self.make_interface(c, 1.0, u, dm),
+            Kind::Interface => {
+                for (i, fi) in self.f[c].iter_mut().enumerate() {
+                    *fi += feq(i, dm, u) - W[i] * dm;
+                }
+                self.mass[c] += dm;
+                self.fill[c] = self.mass[c] / self.rho[c];
+            }
+            Kind::Fluid => {
+                for i in 0..9 {
+                    self.f[c][i] += feq(i, dm, u);
+                }
+                self.mass[c] += dm;
+                self.rho[c] += dm;
+            }
+        }
+    }
+
+    /// Hand the mass of cell `c` to its wet neighbors, moving at `u`; the
+    /// cell itself is left empty.
+    fn spill(&mut self, c: usize, u: Vec2) {
+        let wet: Vec<usize> = self.nbr[c][1..]
+            .iter()
+            .filter(|&&n| n < OPEN && n as usize != c && self.kind[n as usize].wet())
+            .map(|&n| n as usize)
+            .collect();
+        let share = self.mass[c] / wet.len().max(1) as f32;
+        for n in wet {
+            self.deposit(n, share, u);
+        }
+        self.mass[c] = 0.0;
+        self.fill[c] = 0.0;
+    }
+
+    /// Turn the fluid neighbors of `c` into interface cells, which keeps
+    /// the interface closed around a cell about to become gas.
+    fn open_neighbors(&mut self, c: usize) {
+        for k in 1..9 {
+            let n = self.nbr[c][k];
+            if n < OPEN && self.kind[n as usize] == Kind::Fluid {
+                // Its mass is already ρ, or more for a cell that was
+                // just filled, whose excess is still to be passed on.
+                let n = n as usize;
+                self.kind[n] = Kind::Interface;
+                self.fill[n] = self.mass[n] / self.rho[n];
+            }
+        }
+    }
+
+    /// Step 1: re-mark the solid cells for where the obstacles and bodies
+    /// are now.
+    fn classify(&mut self, bounds: &Boundaries, scale: Vec2) {
+        let reach = self.dx.max_element();
+        for c in 0..self.kind.len() {
+            let center = self.center(c);
+            let solid = !bounds.is_empty() && bounds.distance(center) < 0.0;
+            match (self.kind[c] == Kind::Solid, solid) {
+                (false, true) => {
+                    let u = bounds.surface_velocity(center, reach) / scale;
+                    if self.kind[c].wet() {
+                        self.spill(c, u);
+                    }
+                    self.kind[c] = Kind::Solid;
+                    self.wall[c] = u;
+                }
+                (true, true) => self.wall[c] = bounds.surface_velocity(center, reach) / scale,
+                (true, false) => {
+                    self.kind[c] = Kind::Gas;
+                    let wet = self.nbr[c][1..]
+                        .iter()
+                        .any(|&n| n < OPEN && self.kind[n as usize].wet());
+                    if wet {
+                        let (rho, u) = self.neighbor_state(c);
+                        self.make_interface(c, rho, u, 0.0);
+                    }
+                }
+                (false, false) => {}
+            }
+        }
+    }
+
+    /// Step 2: the tracers added since the last substep deposit their mass
+    /// with the velocity encoded in `c_opos`.
+    fn ensure_sized(&mut self, x: &[Vec2], c_opos: &[Vec2], dt: f32, scale: Vec2) {
+        let n = x.len();
+        let seen = self.vel.len().min(n);
+        self.vel.truncate(n);
+        self.x.truncate(n);
+        self.carry.truncate(n);
+        let m = self.tracer_mass();
+        for i in seen..n {
+            let v = (x[i] - c_opos[i]) / dt;
+            for (c, w) in self.stencil(x[i]) {
+                if w > 0.0 {
+                    self.deposit(c, w * m, v / scale);
+                }
+            }
+            self.vel.push(v);
+            self.x.push(x[i]);
+            self.carry.push(0.0);
+        }
+    }
+
+    /// Step 3a: stream the populations and track the interface mass.
+    fn stream(&mut self, bounds: &mut Boundaries, momentum: Vec2) {
+        let coupled = !bounds.is_empty();
+        let reach = self.dx.max_element();
+        for c in 0..self.kind.len() {
+            if !self.kind[c].wet() {
+                continue;
+            }
+            let interface = self.kind[c] == Kind::Interface;
+            let mut dm = 0.0;
+            let mut out = [0.0f32; 9];
+            out[0] = self.f[c][0];
+            for i in 1..9 {
+                let o = OPP[i];
+                // Population arriving in direction i comes from c − e_i.
+                let src = self.nbr[c][o];
+                out[i] = if src == WALL {
+                    let (s, j) = self.slip[c][o];
+                    let (s, j) = (s as usize, j as usize);
+                    if s != c && self.kind[s].wet() {
+                        if interface {
+                            let weight = if self.kind[s] == Kind::Fluid {
+                                1.0
+                            } else {
+                                0.5 * (self.fill[c] + self.fill[s])
+                            };
+                            dm += weight * (self.f[s][j] - self.f[c][o]);
+                        }
+                        self.f[s][j]
+                    } else {
+                        self.f[c][o]
+                    }
+                } else if src == OPEN {
+                    // An outflow edge: what streams out is gone.
+                    if interface {
+                        dm -= self.f[c][o];
+                    }
+                    let u = self.u[c];
+                    feq(i, 1.0, u) + feq(o, 1.0, u) - self.f[c][o]
+                } else {
+                    let s = src as usize;
+                    match self.kind[s] {
+                        Kind::Fluid | Kind::Interface => {
+                            if interface {
+                                let weight = if self.kind[s] == Kind::Fluid {
+                                    1.0
+                                } else {
+                                    0.5 * (self.fill[c] + self.fill[s])
+                                };
+                                dm += weight * (self.f[s][i] - self.f[c][o]);
+                            }
+                            self.f[s][i]
+                        }
+                        Kind::Solid => {
+                            let e = Vec2::new(E[i].0 as f32, E[i].1 as f32);
+                            let back =
+                                self.f[c][o] + 6.0 * W[i] * self.rho[c] * e.dot(self.wall[s]);
+                            if coupled {
+                                // Fluid momentum change across the link,
+                                // booked against the body at its midpoint —
+                                // less the part of it the gas pressure on the
+                                // dry side (at rest, unit density) balances.
+                                let x = self.center(c) - 0.5 * e * self.dx;
+                                let dp = back + self.f[c][o] - 2.0 * W[i];
+                                bounds.face_load(x, e * dp * momentum, reach);
+                            }
+                            back
+                        }
+                        Kind::Gas => {
+                            let u = self.u[c];
+                            feq(i, 1.0, u) + feq(o, 1.0, u) - self.f[c][o]
+                        }
+                    }
+                };
+            }
+            self.next[c] = out;
+            if interface {
+                self.mass[c] += dm;
+            }
+        }
+        std::mem::swap(&mut self.f, &mut self.next);
+    }
+
+    /// Step 3b: collide every wet cell, with gravity `g` in lattice units.
+    fn collide(&mut self, g: Vec2, tau: f32) {
+        let omega = 1.0 / tau;
+        let collision = self.params.collision;
+        for c in 0..self.kind.len() {
+            if !self.kind[c].wet() {
+                continue;
+            }
+            let f = &mut self.f[c];
+            let (rho, u0) = moments(f);
+            let mut u = u0 + 0.5 * g;
+            if u.length() > U_MAX {
+                u = u.clamp_length_max(U_MAX);
+                for (i, fi) in f.iter_mut().enumerate() {
+                    *fi = feq(i, rho, u);
+                }
+            } else {
+                match collision {
+                    LbmCollision::Bgk => {
+                        // Exact difference method: relax about the
+                        // pre-force velocity, then add the change the
+                        // force makes to the equilibrium.
+                        for (i, fi) in f.iter_mut().enumerate() {
+                            let eq = feq(i, rho, u0);
+                            *fi += omega * (eq - *fi) + feq(i, rho, u0 + g) - eq;
+                        }
+                    }
+                    LbmCollision::Cumulant => collide_cumulant(f, rho, u, g, omega),
+                }
+            }
+            self.rho[c] = rho;
+            self.u[c] = u;
+            if self.kind[c] == Kind::Fluid {
+                self.mass[c] = rho;
+            }
+            self.fill[c] = self.mass[c] / rho;
+        }
+    }
+
+    /// Step 3c: fill the interface cells that overflowed, empty those that
+    /// ran dry, and pass the excess mass on to the surrounding interface.
+    fn convert(&mut self) {
+        self.filled.clear();
+        self.emptied.clear();
+        for c in 0..self.kind.len() {
+            if self.kind[c] != Kind::Interface {
+                continue;
+            }
+            let (m, rho) = (self.mass[c], self.rho[c]);
+            let (mut fluid, mut interface, mut gas, mut edge) = (false, false, false, false);
+            for &n in &self.nbr[c][1..] {
+                match n {
+                    WALL => {}
+                    OPEN => (gas, edge) = (true, true),
+                    n => match self.kind[n as usize] {
+                        Kind::Fluid => fluid = true,
+                        Kind::Interface => interface = true,
+                        Kind::Gas => gas = true,
+                        Kind::Solid => {}
+                    },
+                }
+            }
+            let below = self.nbr[c][self.down];
+            let hanging = below == OPEN || (below < OPEN && self.kind[below as usize] == Kind::Gas);
+            if gas && !fluid && (!interface || hanging) {
+                // A droplet without bulk only swaps mass with its own cells,
+                // never fills and so never moves: it would hang in the air
+                // for good. Hand it to its tracers, which fall with it.
+                self.stranded[c] += m.max(0.0);
+                self.kind[c] = Kind::Gas;
+                self.mass[c] = 0.0;
+                self.fill[c] = 0.0;
+            } else if (m > (1.0 + KAPPA) * rho && !edge) || !gas {
+                // An overflowing cell fills, unless it sits on an outflow
+                // edge (it stays interface, to drain); one with no gas
+                // neighbor left is inside the fluid and is promoted anyway.
+                self.filled.push(c);
+            } else if m < -KAPPA * rho || (!fluid && m < 0.1 * rho) {
+                self.emptied.push(c);
+            }
+        }
+
+        let filled = std::mem::take(&mut self.filled);
+        let emptied = std::mem::take(&mut self.emptied);
+        // Filled cells take in their gas neighbors as fresh interface, at
+        // the mean state of the wet cells around them.
+        for &c in &filled {
+            for k in 1..9 {
+                let n = self.nbr[c][k];
+                if n < OPEN && self.kind[n as usize] == Kind::Gas {
+                    let n = n as usize;
+                    let (rho, u) = self.neighbor_state(n);
+                    self.make_interface(n, rho, u, 0.0);
+                    self.fresh[n] = true;
+                }
+            }
+            self.kind[c] = Kind::Fluid;
+        }
+        // Emptied cells release their fluid neighbors to the interface,
+        // cells filled a moment ago included.
+        for &c in &emptied {
+            if self.kind[c] == Kind::Interface {
+                self.open_neighbors(c);
+                self.kind[c] = Kind::Gas;
+            }
+        }
+
+        // The excess mass goes to the interface neighbors, evenly.
+        for &c in filled.iter().chain(&emptied) {
+            let excess = match self.kind[c] {
+                Kind::Fluid => {
+                    let excess = self.mass[c] - self.rho[c];
+                    self.mass[c] = self.rho[c];
+                    self.fill[c] = 1.0;
+                    excess
+                }
+                Kind::Gas => {
+                    let excess = self.mass[c];
+                    self.mass[c] = 0.0;
+                    self.fill[c] = 0.0;
+                    excess
+                }
+                _ => continue,
+            };
+            let takers = self.nbr[c][1..]
+                .iter()
+                .filter(|&&n| n < OPEN && self.kind[n as usize] == Kind::Interface)
+                .count();
+            if takers == 0 {
+                continue;
+            }
+            let share = excess / takers as f32;
+            for k in 1..9 {
+                let n = self.nbr[c][k];
+                if n < OPEN && self.kind[n as usize] == Kind::Interface {
+                    let n = n as usize;
+                    self.mass[n] += share;
+                    self.fill[n] = self.mass[n] / self.rho[n];
+                }
+            }
+        }
+        for &c in filled.iter() {
+            for &n in &self.nbr[c][1..] {
+                if n < OPEN {
+                    self.fresh[n as usize] = false;
+                }
+            }
+        }
+        self.filled = filled;
+        self.emptied = emptied;
+    }
+
+    /// Step 4: move the tracers with the fluid around them, ballistically
+    /// where there is none, and keep them out of the walls and obstacles.
+    fn advect(&mut self, x: &mut [Vec2], dt: f32, accel: Vec2, scale: Vec2, bounds: &Boundaries) {
+        self.pick_up(x);
+        for (i, xp) in x.iter_mut().enumerate() {
+            let (mut u, mut weight) = (Vec2::ZERO, 0.0);
+            for (c, w) in self.stencil(*xp) {
+                if self.kind[c].wet() {
+                    u += w * self.u[c];
+                    weight += w;
+                }
+            }
+            // A tracer in a gas cell is in flight: it only lands (and takes
+            // the fluid's velocity) once it reaches a wet cell.
+            let landed = self.kind[self.cell_of(*xp)].wet() && weight > 0.0;
+            let vp = &mut self.vel[i];
+            *vp = if landed {
+                u * scale / weight
+            } else {
+                *vp + accel * dt
+            };
+            let free = *xp + *vp * dt;
+            let mut p = free;
+            clamp_wall(&mut p, bounds.domain());
+            bounds.clamp(&mut p, BALL_SIZE);
+            // What a wall or an obstacle stopped does not keep falling.
+            *vp = (p - *xp) / dt;
+            *xp = bounds.domain().wrap(p);
+            // A droplet's mass goes back into the lattice where it lands.
+            if self.carry[i] > 0.0 && (landed || p != free) {
+                let carry = std::mem::take(&mut self.carry[i]);
+                self.deposit(self.cell_of(*xp), carry, self.vel[i] / scale);
+            }
+        }
+    }
+
+    /// Share the mass of the droplets stranded this substep among the
+    /// tracers inside them (lost if there are none).
+    fn pick_up(&mut self, x: &[Vec2]) {
+        if !self.stranded.iter().any(|&m| m > 0.0) {
+            return;
+        }
+        let mut count = vec![0u32; self.kind.len()];
+        for &xp in x {
+            count[self.cell_of(xp)] += 1;
+        }
+        for (i, &xp) in x.iter().enumerate() {
+            let c = self.cell_of(xp);
+            if self.stranded[c] > 0.0 {
+                self.carry[i] += self.stranded[c] / count[c] as f32;
+            }
+        }
+        self.stranded.iter_mut().for_each(|m| *m = 0.0);
+    }
+}
+
+impl FluidSolver for Lbm {
+    fn name(&self) -> &'static str {
+        "LBM"
+    }
+
+    fn set_lbm_params(&mut self, params: LbmParams) {
+        self.set_params(params);
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        let seen = self.vel.len().min(keep.len());
+        if !self.kind.is_empty() && keep[..seen].contains(&false) {
+            // Each removed tracer takes the share of each cell's mass that
+            // its weight is of all the tracers' weight there.
+            let mut total = vec![0.0f32; self.kind.len()];
+            let mut taken = vec![0.0f32; self.kind.len()];
+            for (i, &xp) in self.x[..seen].iter().enumerate() {
+                for (c, w) in self.stencil(xp) {
+                    total[c] += w;
+                    if !keep[i] {
+                        taken[c] += w;
+                    }
+                }
+            }
+            for c in 0..self.kind.len() {
+                if taken[c] == 0.0 || !self.kind[c].wet() {
+                    continue;
+                }
+                let left = 1.0 - taken[c] / total[c];
+                self.mass[c] *= left;
+                if self.mass[c] <= KAPPA * self.rho[c] {
+                    self.open_neighbors(c);
+                    self.kind[c] = Kind::Gas;
+                    self.mass[c] = 0.0;
+                    self.fill[c] = 0.0;
+                } else {
+                    self.kind[c] = Kind::Interface;
+                    self.fill[c] = self.mass[c] / self.rho[c];
+                }
+            }
+        }
+        let prefix = &keep[..seen];
+        retain_flagged(&mut self.vel, prefix);
+        retain_flagged(&mut self.x, prefix);
+        retain_flagged(&mut self.carry, prefix);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
+        let n = share.c_pos.len();
+        c_opos.resize(n, Vec2::ZERO);
+        self.fit_grid(bounds.domain());
+
+        let steps = self.params.lattice_steps.max(1);
+        let lattice_dt = dt / steps as f32;
+        // Lattice → px/s, per axis (the cells may be stretched a little).
+        let scale = self.dx / lattice_dt;
+        // The granular integrator's effective gravity (see
+        // `sph::effective_gravity`) as a per-lattice-step velocity increment.
+        let accel = gravity / PHYS_TIME_STEP;
+        let g = accel * lattice_dt / scale;
+        let nu = self.params.viscosity * lattice_dt / (self.dx.x * self.dx.y);
+        let tau = (0.5 + 3.0 * nu).max(TAU_MIN);
+        // Lattice momentum → px·mass/s (unit area density).
+        let momentum = scale * self.dx.x * self.dx.y;
+
+        self.down = if g == Vec2::ZERO {
+            0
+        } else {
+            (1..9)
+                .max_by(|&a, &b| {
+                    let along =
+                        |i: usize| Vec2::new(E[i].0 as f32, E[i].1 as f32).normalize().dot(g);
+                    along(a).total_cmp(&along(b))
+                })
+                .unwrap_or(0)
+        };
+        self.classify(bounds, scale);
+        self.ensure_sized(&share.c_pos, c_opos, dt, scale);
+        for _ in 0..steps {
+            self.stream(bounds, momentum);
+            self.collide(g, tau);
+            self.convert();
+        }
+        self.advect(&mut share.c_pos, dt, accel, scale, bounds);
+        self.x.copy_from_slice(&share.c_pos);
+
+        // Mean lattice density of the fluid cells in the density-ratio slot.
+        let (mut rho_sum, mut fluid) = (0.0f32, 0usize);
+        for c in 0..self.kind.len() {
+            if self.kind[c] == Kind::Fluid {
+                rho_sum += self.rho[c];
+                fluid += 1;
+            }
+        }
+        let ratio = if fluid > 0 {
+            rho_sum / fluid as f32
+        } else {
+            1.0
+        };
+        report(share, c_opos, &self.vel, dt, ratio);
+        share.perf_stats.sph_substeps = steps;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physics::tests::dam_break_scene;
+    use crate::physics::Strategy;
+
+    /// The shared dam break ([`dam_break_scene`]) on the lattice with
+    /// `params`, its particles as tracers, run for `steps` steps.
+    fn dam_break(params: LbmParams, steps: usize) -> ShareData {
+        let (mut physics, mut share) = dam_break_scene(Strategy::Lbm);
+        physics.set_lbm_params(params);
+        for _ in 0..steps {
+            physics.step(PHYS_TIME_STEP, &mut share);
+        }
+        share
+    }
+
+    /// At unit area density 1200 particles fill 240 px × 180 px: checks the
+    /// tracers settled about that deep, and at rest. They are not tied to
+    /// the mass, and crowd a little toward the floor, hence the slack.
+    fn assert_settled(share: &ShareData) {
+        let n = share.c_pos.len() as f32;
+        let mean_y = share.c_pos.iter().map(|p| p.y).sum::<f32>() / n;
+        let depth = 2.0 * (600.0 - mean_y);
+        assert!(
+            (depth - 180.0).abs() < 12.0,
+            "settled depth {depth:.1} px, expected about 180"
+        );
+        assert!(share.perf_stats.mean_speed < 1.0);
+    }
+
+    #[test]
+    fn dam_break_settles_at_its_original_volume() {
+        let share = dam_break(LbmParams::default(), 2000);
+        assert_settled(&share);
+        assert!((share.perf_stats.pbf_density_ratio - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn cumulant_settles_at_the_lowest_viscosity() {
+        let share = dam_break(
+            LbmParams {
+                collision: LbmCollision::Cumulant,
+                viscosity: 0.0,
+                ..Default::default()
+            },
+            2000,
+        );
+        assert!(share.c_pos.iter().all(|p| p.is_finite()));
+        assert_settled(&share);
+    }
+}
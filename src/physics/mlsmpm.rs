@@ -9,39 +9,49 @@
 //!
 //! What it buys us that nothing else here can: swapping the *constitutive
 //! model* turns the identical solver from a weakly-compressible fluid into an
-//! elastic **jelly** (and, with a plasticity return map, sand or snow). The
-//! default material is `Liquid` so it slots into the water comparison; `Jelly`
-//! shows the elastic-solid differentiator.
+//! elastic **jelly** and, with a plasticity return map on the singular values
+//! of F, **snow** and **sand**. The default material is `Liquid` so it slots
+//! into the water comparison; `Jelly` shows the elastic-solid differentiator.
+//! The viscoplastic materials — `Bingham`, `HerschelBulkley` and
+//! `ShearThinning` — add a deviatoric stress on top of the liquid's pressure,
+//! for mud, slurry and paint.
 //!
 //! Per substep (Hu's "MLS-MPM" / the taichi 88-line formulation, in 2D):
 //!   1. **P2G** — scatter mass and APIC momentum to the 3×3 grid nodes around
 //!      each particle, folding in the internal stress via the MLS affine form
 //!      `affine = m·C − Δt·V·(4/Δx²)·σ`.
-//!   2. **grid update** — momentum→velocity, add gravity, apply wall BCs.
+//!   2. **grid update** — momentum→velocity, add gravity, apply wall BCs and
+//!      the obstacle BCs (into-surface velocity removed along the normal).
 //!   3. **G2P** — gather velocity (and reconstruct C) back to particles,
-//!      advect, and update J (liquid) or F (jelly).
+//!      advect, and update J (liquid), F (jelly), the elastic part of F
+//!      (snow, sand — with their return maps), or J plus the elastic shear
+//!      strain (viscoplastic, with a return map onto the yield surface).
 
 use glam::{Mat2, Vec2};
 
-use super::{clamp_wall, FluidSolver, ShareData, PHYS_TIME_STEP};
-use crate::constants::{BALL_SIZE, HEIGHT, WIDTH};
+use super::{
+    clamp_wall, report, retain_flagged, Boundaries, Domain, FluidSolver, Phase, ShareData,
+    PHYS_TIME_STEP,
+};
+use crate::constants::BALL_SIZE;
 
-// Grid spacing: two particle spacings, so a rest-packed fluid seeds ~4
-// particles per cell (the MPM sweet spot). Support is the 3×3 block of nodes.
-const MPM_DX: f32 = 4.0 * BALL_SIZE; // = 2 × rest spacing (2·BALL_SIZE)
-const MPM_INV_DX: f32 = 1.0 / MPM_DX;
+// Grid spacing: two particle spacings (2 × 2·BALL_SIZE), so a rest-packed
+// fluid seeds ~4 particles per cell (the MPM sweet spot). Support is the 3×3
+// block of nodes. A periodic axis stretches it slightly so whole cells tile
+// the period.
+const MPM_DX: f32 = 4.0 * BALL_SIZE;
 // One-cell halo (`HALO`) on every side: a particle at the wall has base cell
-// −1, so node indices are shifted by +HALO and the grid is padded by 2·HALO+1
-// cells, keeping the whole 3×3 stencil in bounds without desyncing weights.
+// −1, so node indices are shifted by +HALO and the grid (sized from the
+// domain at runtime) is padded by 2·HALO+1 cells, keeping the whole 3×3
+// stencil in bounds without desyncing weights.
 const HALO: isize = 1;
-const GW: usize = (WIDTH / MPM_DX) as usize + 2 * HALO as usize + 1;
-const GH: usize = (HEIGHT / MPM_DX) as usize + 2 * HALO as usize + 1;
 // Volume/mass one particle represents (rest spacing², unit density).
 const P_VOL: f32 = (2.0 * BALL_SIZE) * (2.0 * BALL_SIZE);
-const P_MASS: f32 = P_VOL; // unit rest density
-                           // Grid nodes within this many cells of a wall get their into-wall velocity
-                           // component zeroed (sticky floor / slip walls).
-const MPM_BND: usize = 3;
+// Unit rest density (a phase's density scales it).
+const P_MASS: f32 = P_VOL;
+// Grid nodes within this distance of a wall (or beyond it) get their
+// into-wall velocity component zeroed (sticky floor / slip walls).
+const MPM_BND: f32 = MPM_DX;
 
 /// Which constitutive model the MPM particles obey.
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
@@ -51,10 +61,28 @@ pub enum MpmMaterial {
     Liquid,
     /// Fixed-corotated elastic solid — wobbles and holds its shape (jelly).
     Jelly,
+    /// Bingham plastic: rigid below `yield_stress`, flows with constant
+    /// plastic `viscosity` above it (toothpaste, mud).
+    Bingham,
+    /// Herschel–Bulkley: a yield stress plus a power-law flow curve
+    /// τ = τ_y + K·γ̇ⁿ (slurry, many paints).
+    HerschelBulkley,
+    /// Power-law fluid without a yield stress, τ = K·γ̇ⁿ; thins under shear
+    /// for `flow_index` < 1 (paint, ketchup once it moves).
+    ShearThinning,
+    /// Stomakhin et al. 2013 snow: fixed-corotated elasticity whose singular
+    /// values are clamped to a small stretch/compression window, the excess
+    /// becoming plastic; compaction hardens it. Packs, breaks and crumbles.
+    Snow,
+    /// Drucker–Prager sand (Klár et al. 2016): Hencky-strain elasticity with
+    /// a friction-cone return map. No cohesion — it pours and heaps at its
+    /// angle of repose.
+    Sand,
 }
 
-/// Tunable MLS-MPM coefficients.
-#[derive(Clone, Copy, Debug)]
+/// Tunable MLS-MPM coefficients. One set is the solver-wide default; a
+/// [`Phase`] can carry its own, so one scene mixes materials by region.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct MpmParams {
     pub material: MpmMaterial,
     /// Bulk modulus λ for the volumetric (pressure) response. Higher = stiffer
@@ -62,6 +90,28 @@ pub struct MpmParams {
     pub bulk: f32,
     /// Shear modulus μ (jelly only; 0 for a liquid, which sustains no shear).
     pub shear: f32,
+    /// Yield stress τ_y (Bingham, Herschel–Bulkley): below it the material
+    /// holds its shape, above it it flows.
+    pub yield_stress: f32,
+    /// Plastic viscosity (Bingham) or consistency K (Herschel–Bulkley,
+    /// shear-thinning). Higher = slower flow once yielded.
+    pub viscosity: f32,
+    /// Flow index n of the power law (Herschel–Bulkley, shear-thinning):
+    /// < 1 thins under shear, 1 is Bingham/Newtonian, > 1 thickens.
+    pub flow_index: f32,
+    /// Internal friction angle φ of sand, in degrees. Sets the Drucker–Prager
+    /// cone and with it the angle of repose; sand and snow also grip the
+    /// walls with μ = tan φ.
+    pub friction_angle: f32,
+    /// Snow hardening ξ: the moduli scale by e^{ξ(1−J_p)}, so compacted snow
+    /// (plastic volume J_p < 1) stiffens and stretched snow softens.
+    pub hardening: f32,
+    /// Snow critical compression θ_c: singular values of F below 1 − θ_c
+    /// yield plastically.
+    pub critical_compression: f32,
+    /// Snow critical stretch θ_s: singular values above 1 + θ_s yield
+    /// (fracture).
+    pub critical_stretch: f32,
 }
 
 impl Default for MpmParams {
@@ -73,24 +123,105 @@ impl Default for MpmParams {
         // impact to ~15–25% compression while staying well under the CFL ceiling
         // (sound speed √E ≈ 3160, √E·Δt/Δx ≈ 0.55). Shear (jelly only) is set an
         // order below so a jelly wobbles and holds shape without locking up.
+        // The flow law defaults to a stiff mud: τ_y holds a heap a couple of
+        // hundred px tall against that gravity, and the plastic return is
+        // implicit, so K and n can be anything without touching the CFL.
         Self {
             material: MpmMaterial::Liquid,
             bulk: 1.0e7,
             shear: 4.0e4,
+            yield_stress: 3.0e5,
+            viscosity: 1.0e5,
+            flow_index: 0.5,
+            // Stomakhin's published snow window and hardening; a sand-like
+            // friction angle.
+            friction_angle: 30.0,
+            hardening: 10.0,
+            critical_compression: 2.5e-2,
+            critical_stretch: 7.5e-3,
         }
     }
 }
 
-/// MLS-MPM solver state. Per-particle Lagrangian state (v, C, and J or F) lives
-/// here; the Eulerian grid is scratch rebuilt every substep.
+impl MpmParams {
+    /// (τ_y, K, n) of the material's flow law, or `None` if it isn't
+    /// viscoplastic. Bingham is Herschel–Bulkley with n = 1; shear-thinning
+    /// is Herschel–Bulkley with no yield stress.
+    fn flow_law(&self) -> Option<(f32, f32, f32)> {
+        match self.material {
+            MpmMaterial::Liquid | MpmMaterial::Jelly | MpmMaterial::Snow | MpmMaterial::Sand => {
+                None
+            }
+            MpmMaterial::Bingham => Some((self.yield_stress, self.viscosity, 1.0)),
+            MpmMaterial::HerschelBulkley => {
+                Some((self.yield_stress, self.viscosity, self.flow_index))
+            }
+            MpmMaterial::ShearThinning => Some((0.0, self.viscosity, self.flow_index)),
+        }
+    }
+
+    /// Elastic shear modulus of the viscoplastic materials below yield, and
+    /// of snow and sand (which take λ = μ). Tied to the bulk modulus (μ = E/10)
+    /// rather than the jelly's `shear`: these must be stiff next to their own
+    /// weight (ρgH ≈ 4e5 for a 180 px heap) or they sag elastically before
+    /// they ever yield, and it's the plasticity, not μ, they're tuned by.
+    fn stiff_shear(&self) -> f32 {
+        0.1 * self.bulk
+    }
+
+    /// Coulomb friction coefficient at the walls. Sand and snow grip them
+    /// (μ = tan φ): on a frictionless floor a cohesionless pile has nothing
+    /// holding its base and runs out flat, and snow skates. Everything else
+    /// slips.
+    fn wall_friction(&self) -> f32 {
+        match self.material {
+            MpmMaterial::Sand | MpmMaterial::Snow => self.friction_angle.to_radians().tan(),
+            _ => 0.0,
+        }
+    }
+
+    /// Whether the material tracks its elastic deformation gradient F (the
+    /// solids) rather than just the volume ratio J.
+    fn is_solid(&self) -> bool {
+        matches!(
+            self.material,
+            MpmMaterial::Jelly | MpmMaterial::Snow | MpmMaterial::Sand
+        )
+    }
+}
+
+/// MLS-MPM solver state. Per-particle Lagrangian state (v, C, and J or F, plus
+/// each particle's material and mass) lives here; the Eulerian grid is scratch
+/// rebuilt every substep.
 pub struct Mlsmpm {
     params: MpmParams,
+    /// Phase table: a phase with its own `mpm` params overrides `params` for
+    /// its particles, and its density scales their mass.
+    phases: Vec<Phase>,
+    // Per-particle material and mass, resolved from the particle's phase when
+    // it arrives (and for everyone again once `stale`, after the params or
+    // phases change).
+    mat: Vec<MpmParams>,
+    mass: Vec<f32>,
+    stale: bool,
     vel: Vec<Vec2>,
     cmat: Vec<Mat2>, // APIC affine velocity C
     jdet: Vec<f32>,  // volume ratio J = det(F) (liquid)
-    fmat: Vec<Mat2>, // deformation gradient F (jelly)
+    fmat: Vec<Mat2>, // deformation gradient F (jelly), its elastic part (snow, sand)
+    jp: Vec<f32>,    // plastic volume ratio J_p = det F_P (snow hardening)
+    bmat: Vec<Mat2>, // isochoric elastic left Cauchy–Green b̄ (viscoplastic)
     grid_v: Vec<Vec2>,
     grid_m: Vec<f32>,
+    grid_grip: Vec<f32>, // mass-weighted wall friction of the node's particles
+    // Grid dimensions in nodes and node spacing per axis, for the domain
+    // they were sized from; `period` is the node count a periodic axis
+    // wraps at.
+    gw: usize,
+    gh: usize,
+    dx: Vec2,
+    inv_dx: Vec2,
+    period: [usize; 2],
+    domain: Domain,
 }
 
 impl Default for Mlsmpm {
@@ -103,26 +234,94 @@ impl Mlsmpm {
     pub fn new() -> Self {
         Self {
             params: MpmParams::default(),
+            phases: Vec::new(),
+            mat: Vec::new(),
+            mass: Vec::new(),
+            stale: false,
             vel: Vec::new(),
             cmat: Vec::new(),
             jdet: Vec::new(),
             fmat: Vec::new(),
-            grid_v: vec![Vec2::ZERO; GW * GH],
-            grid_m: vec![0.0; GW * GH],
+            jp: Vec::new(),
+            bmat: Vec::new(),
+            grid_v: Vec::new(),
+            grid_m: Vec::new(),
+            grid_grip: Vec::new(),
+            gw: 0,
+            gh: 0,
+            dx: Vec2::splat(MPM_DX),
+            inv_dx: Vec2::splat(1.0 / MPM_DX),
+            period: [0; 2],
+            domain: Domain::default(),
         }
     }
 
+    /// Size the background grid to cover `domain` (plus the halo) if it
+    /// isn't already.
+    fn fit_grid(&mut self, domain: &Domain) {
+        if self.domain == *domain && !self.grid_v.is_empty() {
+            return;
+        }
+        let pad = 2 * HALO as usize + 1;
+        let axis = |len: f32, periodic: bool| {
+            if periodic {
+                let n = ((len / MPM_DX).round() as usize).max(3);
+                (n, len / n as f32)
+            } else {
+                ((len / MPM_DX).ceil() as usize, MPM_DX)
+            }
+        };
+        let (nx, dx) = axis(domain.width, domain.periodic_x());
+        let (ny, dy) = axis(domain.height, domain.periodic_y());
+        self.gw = nx + pad;
+        self.gh = ny + pad;
+        self.dx = Vec2::new(dx, dy);
+        self.inv_dx = self.dx.recip();
+        self.period = [nx, ny];
+        self.grid_v = vec![Vec2::ZERO; self.gw * self.gh];
+        self.grid_m = vec![0.0; self.gw * self.gh];
+        self.grid_grip = vec![0.0; self.gw * self.gh];
+        self.domain = *domain;
+    }
+
     pub fn set_params(&mut self, params: MpmParams) {
         self.params = params;
+        self.stale = true;
     }
 
-    fn ensure_sized(&mut self, n: usize) {
+    /// Material and mass of a particle in phase `phase`: the phase's own MPM
+    /// params if it has them, else the solver's, and the phase density (1 if
+    /// the phase is undefined) times the unit-density mass.
+    fn resolve(&self, phase: u8) -> (MpmParams, f32) {
+        match self.phases.get(phase as usize) {
+            Some(ph) => (ph.mpm.unwrap_or(self.params), P_MASS * ph.density),
+            None => (self.params, P_MASS),
+        }
+    }
+
+    fn ensure_sized(&mut self, x: &[Vec2], c_opos: &[Vec2], phase: &[u8], dt: f32) {
+        let n = x.len();
+        // Newcomers (or everyone, when stale) take their phase's material.
+        let from = if self.stale { 0 } else { self.mat.len().min(n) };
+        self.mat.truncate(from);
+        self.mass.truncate(from);
+        for i in from..n {
+            let (mat, mass) = self.resolve(phase.get(i).copied().unwrap_or(0));
+            self.mat.push(mat);
+            self.mass.push(mass);
+        }
+        self.stale = false;
         if self.vel.len() != n {
-            // Grow (cannon) / init: newcomers start at rest, undeformed.
-            self.vel.resize(n, Vec2::ZERO);
+            // Grow (cannon, emitters) / init: newcomers keep the velocity
+            // encoded in `c_opos`, with no affine part and undeformed.
+            let seen = self.vel.len().min(n);
+            self.vel.truncate(n);
+            self.vel.extend((seen..n).map(|i| (x[i] - c_opos[i]) / dt));
             self.cmat.resize(n, Mat2::ZERO);
             self.jdet.resize(n, 1.0);
             self.fmat.resize(n, Mat2::IDENTITY);
+            self.jp.resize(n, 1.0);
+            self.bmat.resize(n, Mat2::IDENTITY);
         }
     }
 
@@ -130,8 +329,8 @@ impl Mlsmpm {
     /// integer base node (unclamped; may be −1 at a wall — the HALO absorbs it)
     /// and the fractional offset `fx ∈ [0.5, 1.5)`.
     #[inline(always)]
-    fn weights(xp: Vec2) -> ([isize; 2], Vec2, [Vec2; 3]) {
-        let cell = xp * MPM_INV_DX;
+    fn weights(&self, xp: Vec2) -> ([isize; 2], Vec2, [Vec2; 3]) {
+        let cell = xp * self.inv_dx;
         let base_f = (cell - Vec2::splat(0.5)).floor();
         let fx = cell - base_f;
         let w = [
@@ -145,26 +344,45 @@ impl Mlsmpm {
     /// Flat node index for stencil offset (i, j) around `base`, shifted by the
     /// halo and clamped to the padded grid (the clamp only ever bites if a
     /// particle reaches the very corner, which the wall projection prevents).
+    /// A periodic axis wraps around its period instead.
     #[inline(always)]
-    fn node(base: [isize; 2], i: usize, j: usize) -> usize {
-        let nx = (base[0] + i as isize + HALO).clamp(0, GW as isize - 1) as usize;
-        let ny = (base[1] + j as isize + HALO).clamp(0, GH as isize - 1) as usize;
-        ny * GW + nx
+    fn node(&self, base: [isize; 2], i: usize, j: usize) -> usize {
+        let nx = self.axis_node(0, base[0] + i as isize, self.gw, self.domain.periodic_x());
+        let ny = self.axis_node(1, base[1] + j as isize, self.gh, self.domain.periodic_y());
+        ny * self.gw + nx
+    }
+
+    #[inline(always)]
+    fn axis_node(&self, axis: usize, k: isize, len: usize, periodic: bool) -> usize {
+        if periodic {
+            (k.rem_euclid(self.period[axis] as isize) + HALO) as usize
+        } else {
+            (k + HALO).clamp(0, len as isize - 1) as usize
+        }
     }
 
     /// Cauchy stress × the MLS-MPM prefactor `−Δt·V·(4/Δx²)`, plus the APIC
     /// `m·C` term — i.e. the "affine" matrix scattered in P2G.
     #[inline(always)]
     fn affine(&self, p: usize, dt: f32) -> Mat2 {
-        let pre = -dt * P_VOL * 4.0 * MPM_INV_DX * MPM_INV_DX;
-        let stress = match self.params.material {
+        // −Δt·V·D⁻¹ with D⁻¹ = diag(4/Δx², 4/Δy²), applied per column.
+        let pre = -dt * P_VOL * 4.0 * self.inv_dx * self.inv_dx;
+        let m = &self.mat[p];
+        let stress = match m.material {
+            MpmMaterial::Bingham | MpmMaterial::HerschelBulkley | MpmMaterial::ShearThinning => {
+                // Liquid pressure plus the elastic deviatoric (Kirchhoff)
+                // stress μ·dev(b̄); the return map in G2P keeps it on or
+                // inside the yield surface.
+                let j = self.jdet[p];
+                Mat2::IDENTITY * (m.bulk * (j - 1.0)) + deviator(self.bmat[p]) * m.stiff_shear()
+            }
             MpmMaterial::Liquid => {
                 // Linear equation of state (mpm88): pressure = E·(J−1),
                 // isotropic. Unlike λ·J·(J−1) this keeps a *finite* restoring
                 // force as J→0 (E·(J−1) → −E), so a hard impact can't collapse
                 // the volume to a pressureless pancake — J self-corrects.
                 let j = self.jdet[p];
-                Mat2::IDENTITY * (self.params.bulk * (j - 1.0))
+                Mat2::IDENTITY * (m.bulk * (j - 1.0))
             }
             MpmMaterial::Jelly => {
                 // Fixed-corotated: σ = 2μ(F−R)Fᵀ + λ·J·(J−1)·I, R from the 2D
@@ -172,90 +390,200 @@ impl Mlsmpm {
                 // the shear μ (λ ≈ 5μ, a solid-like Lamé ratio) rather than the
                 // liquid's `bulk`: a λ ≫ μ would swamp the shear and the jelly
                 // would flow like a fluid instead of holding its shape.
-                let f = self.fmat[p];
-                let j = f.determinant();
-                let r = polar_rotation(f);
-                let mu = self.params.shear;
-                let la = 5.0 * mu;
-                (f - r) * f.transpose() * (2.0 * mu) + Mat2::IDENTITY * (la * j * (j - 1.0))
+                let mu = m.shear;
+                corotated(self.fmat[p], mu, 5.0 * mu)
+            }
+            MpmMaterial::Snow => {
+                // Fixed-corotated on the elastic F, with both Lamé moduli
+                // scaled by the hardening e^{ξ(1−J_p)} (capped so packed snow
+                // stays inside the CFL limit).
+                let h = (m.hardening * (1.0 - self.jp[p])).exp().clamp(0.1, 5.0);
+                let mu = m.stiff_shear() * h;
+                corotated(self.fmat[p], mu, mu)
+            }
+            MpmMaterial::Sand => {
+                // St. Venant–Kirchhoff in Hencky strain ε = ln Σ: the
+                // Kirchhoff stress is U·diag(2μ·ε + λ·tr ε)·Uᵀ.
+                let (u, sig, _) = svd2(self.fmat[p]);
+                let mu = m.stiff_shear();
+                let eps = hencky(sig);
+                let diag = eps * (2.0 * mu) + Vec2::splat(mu * (eps.x + eps.y));
+                u * Mat2::from_diagonal(diag) * u.transpose()
             }
         };
-        stress * pre + self.cmat[p] * P_MASS
+        Mat2::from_cols(stress.col(0) * pre.x, stress.col(1) * pre.y) + self.cmat[p] * self.mass[p]
+    }
+
+    /// Herschel–Bulkley return map on the elastic strain (Yue et al. 2015).
+    /// The trial deviatoric stress s = μ·dev(b̄) has equivalent shear stress
+    /// τ = |s|/√2; above τ_y it relaxes along its own direction at the plastic
+    /// rate γ̇ = ((τ − τ_y)/K)^(1/n). Integrated backward in time, the new τ
+    /// solves τ + μ̄·Δt·γ̇(τ) = τ_trial (μ̄ = μ·tr b̄/2), which is monotone in
+    /// τ and so bisects safely — unconditionally stable for any K and n.
+    fn plastic_return(&self, p: usize, b: Mat2, dt: f32) -> Mat2 {
+        let Some((yield_stress, k, n)) = self.mat[p].flow_law() else {
+            return b;
+        };
+        let mu = self.mat[p].stiff_shear();
+        let dev = deviator(b);
+        let norm = (dev.col(0).length_squared() + dev.col(1).length_squared()).sqrt();
+        let tau = mu * norm / std::f32::consts::SQRT_2;
+        if tau <= yield_stress {
+            return b;
+        }
+        let mu_bar = mu * 0.5 * (b.col(0).x + b.col(1).y);
+        let k = k.max(1e-6);
+        let inv_n = 1.0 / n.max(0.05);
+        let excess = tau - yield_stress;
+        let (mut lo, mut hi) = (0.0f32, excess);
+        for _ in 0..24 {
+            let mid = 0.5 * (lo + hi);
+            if mid + mu_bar * dt * (mid / k).powf(inv_n) > excess {
+                hi = mid;
+            } else {
+                lo = mid;
+            }
+        }
+        let scale = (yield_stress + 0.5 * (lo + hi)) / tau;
+        dev * scale + (b - dev)
+    }
+
+    /// Stomakhin snow plasticity: clamp the singular values of the trial
+    /// elastic F into [1 − θ_c, 1 + θ_s], moving the excess into F_P (tracked
+    /// only through its determinant J_p, which drives the hardening).
+    fn snow_return(&mut self, p: usize, f: Mat2) -> Mat2 {
+        let (u, sig, v) = svd2(f);
+        let clamped = sig.clamp(
+            Vec2::splat(1.0 - self.mat[p].critical_compression),
+            Vec2::splat(1.0 + self.mat[p].critical_stretch),
+        );
+        self.jp[p] = (self.jp[p] * (sig.x * sig.y) / (clamped.x * clamped.y)).clamp(0.2, 10.0);
+        u * Mat2::from_diagonal(clamped) * v.transpose()
+    }
+
+    /// Drucker–Prager return map in Hencky strain (Klár 2016, §7.3). Expansion
+    /// (tr ε > 0) has no cohesion to resist it: the elastic strain resets to
+    /// zero. Otherwise the deviatoric strain is projected back onto the
+    /// friction cone |ε̂| ≤ −α·(dλ + 2μ)/(2μ)·tr ε, α = √(2/3)·2 sin φ/(3 − sin φ).
+    fn sand_return(&self, p: usize, f: Mat2) -> Mat2 {
+        let (u, sig, v) = svd2(f);
+        let eps = hencky(sig);
+        let tr = eps.x + eps.y;
+        let dev = eps - Vec2::splat(0.5 * tr);
+        let dev_norm = dev.length();
+        let new_eps = if tr >= 0.0 {
+            Vec2::ZERO
+        } else {
+            // λ = μ, d = 2: (dλ + 2μ)/(2μ) = 2.
+            let sin_phi = self.mat[p].friction_angle.to_radians().sin();
+            let alpha = (2.0f32 / 3.0).sqrt() * 2.0 * sin_phi / (3.0 - sin_phi);
+            let dgamma = dev_norm + 2.0 * tr * alpha;
+            if dgamma <= 0.0 || dev_norm < 1e-12 {
+                eps
+            } else {
+                eps - dev * (dgamma / dev_norm)
+            }
+        };
+        u * Mat2::from_diagonal(Vec2::new(new_eps.x.exp(), new_eps.y.exp())) * v.transpose()
     }
 
     fn p2g(&mut self, x: &[Vec2], dt: f32) {
         self.grid_v.iter_mut().for_each(|v| *v = Vec2::ZERO);
         self.grid_m.iter_mut().for_each(|m| *m = 0.0);
+        self.grid_grip.iter_mut().for_each(|g| *g = 0.0);
 
         for p in 0..x.len() {
-            let (base, fx, w) = Self::weights(x[p]);
+            let (base, fx, w) = self.weights(x[p]);
             let affine = self.affine(p, dt);
-            let mv = self.vel[p] * P_MASS;
+            let mass = self.mass[p];
+            let mv = self.vel[p] * mass;
+            let grip = self.mat[p].wall_friction() * mass;
             for j in 0..3 {
                 for i in 0..3 {
                     let weight = w[i].x * w[j].y;
-                    let dpos = (Vec2::new(i as f32, j as f32) - fx) * MPM_DX;
-                    let node = Self::node(base, i, j);
+                    let dpos = (Vec2::new(i as f32, j as f32) - fx) * self.dx;
+                    let node = self.node(base, i, j);
                     self.grid_v[node] += (mv + affine * dpos) * weight;
-                    self.grid_m[node] += weight * P_MASS;
+                    self.grid_m[node] += weight * mass;
+                    self.grid_grip[node] += weight * grip;
                 }
             }
         }
     }
 
-    fn grid_update(&mut self, gravity: Vec2, dt: f32) {
+    fn grid_update(&mut self, gravity: Vec2, dt: f32, bounds: &mut Boundaries) {
         // Match the other models' effective acceleration so all fall the same.
         let dv = gravity * (dt / PHYS_TIME_STEP);
-        for node in 0..GW * GH {
+        let (hi_x, hi_y) = (self.domain.width - MPM_BND, self.domain.height - MPM_BND);
+        let (left, right) = self.domain.walls_x();
+        let (top, bottom) = self.domain.walls_y();
+        for node in 0..self.gw * self.gh {
             let m = self.grid_m[node];
             if m > 0.0 {
+                // Coulomb wall friction, mass-averaged over the materials
+                // that reach this node.
+                let grip = self.grid_grip[node] / m;
                 let mut v = self.grid_v[node] / m + dv;
-                let (gx, gy) = (node % GW, node / GW);
-                if gx < MPM_BND && v.x < 0.0 {
+                let (gx, gy) = (node % self.gw, node / self.gw);
+                let xg =
+                    Vec2::new((gx as isize - HALO) as f32, (gy as isize - HALO) as f32) * self.dx;
+                if left && xg.x <= MPM_BND && v.x < 0.0 {
+                    v.y = coulomb(v.y, -grip * v.x);
                     v.x = 0.0;
                 }
-                if gx >= GW - MPM_BND && v.x > 0.0 {
+                if right && xg.x >= hi_x && v.x > 0.0 {
+                    v.y = coulomb(v.y, grip * v.x);
                     v.x = 0.0;
                 }
-                if gy < MPM_BND && v.y < 0.0 {
+                if top && xg.y <= MPM_BND && v.y < 0.0 {
+                    v.x = coulomb(v.x, -grip * v.y);
                     v.y = 0.0;
                 }
-                if gy >= GH - MPM_BND && v.y > 0.0 {
+                if bottom && xg.y >= hi_y && v.y > 0.0 {
+                    v.x = coulomb(v.x, grip * v.y);
                     v.y = 0.0;
                 }
+                // Obstacles, bodies and the container: nodes within one cell
+                // of a surface lose their into-surface velocity, so the whole
+                // 3×3 stencil of a particle touching it sees the wall; what
+                // a body takes out of a node it gets back as an impulse.
+                bounds.grid_bc(xg, &mut v, self.dx.max_element(), m);
                 self.grid_v[node] = v;
             }
         }
     }
 
-    fn g2p(&mut self, x: &mut [Vec2], dt: f32) {
+    fn g2p(&mut self, x: &mut [Vec2], dt: f32, bounds: &Boundaries) {
         for p in 0..x.len() {
-            let (base, fx, w) = Self::weights(x[p]);
+            let (base, fx, w) = self.weights(x[p]);
             let mut new_v = Vec2::ZERO;
             let mut new_c = Mat2::ZERO;
             for j in 0..3 {
                 for i in 0..3 {
                     let weight = w[i].x * w[j].y;
                     let dpos = Vec2::new(i as f32, j as f32) - fx;
-                    let gv = self.grid_v[Self::node(base, i, j)];
+                    let gv = self.grid_v[self.node(base, i, j)];
                     new_v += gv * weight;
-                    // C = (4/Δx²) Σ w·gv⊗dpos ; dpos is in cell units so one
-                    // 1/Δx is folded and the other stays explicit below.
+                    // C = Σ w·gv⊗dpos·D⁻¹ ; dpos is in cell units so one
+                    // 1/Δx per column is folded and the other stays explicit
+                    // below.
                     new_c += Mat2::from_cols(gv * (dpos.x * weight), gv * (dpos.y * weight));
                 }
             }
-            new_c *= 4.0 * MPM_INV_DX;
+            let scale = 4.0 * self.inv_dx;
+            new_c = Mat2::from_cols(new_c.col(0) * scale.x, new_c.col(1) * scale.y);
             self.vel[p] = new_v;
             self.cmat[p] = new_c;
 
-            // Advect, then wall-clamp the position (belt-and-suspenders with the
-            // grid BC).
+            // Advect, then wall-clamp and obstacle-project the position
+            // (belt-and-suspenders with the grid BCs).
             let mut xp = x[p] + new_v * dt;
-            clamp_wall(&mut xp);
-            x[p] = xp;
+            clamp_wall(&mut xp, bounds.domain());
+            bounds.clamp(&mut xp, BALL_SIZE);
+            x[p] = bounds.domain().wrap(xp);
 
             // Evolve the material state.
-            match self.params.material {
+            match self.mat[p].material {
                 MpmMaterial::Liquid => {
                     // Volume ratio evolves with the velocity divergence (the
                     // trace of C): J ← J·(1 + Δt·tr C) (mpm88). The linear EOS
@@ -269,6 +597,32 @@ impl Mlsmpm {
                     // F ← (I + Δt·C)·F.
                     self.fmat[p] = (Mat2::IDENTITY + new_c * dt) * self.fmat[p];
                 }
+                MpmMaterial::Snow => {
+                    let f = (Mat2::IDENTITY + new_c * dt) * self.fmat[p];
+                    self.fmat[p] = self.snow_return(p, f);
+                }
+                MpmMaterial::Sand => {
+                    let f = (Mat2::IDENTITY + new_c * dt) * self.fmat[p];
+                    self.fmat[p] = self.sand_return(p, f);
+                }
+                MpmMaterial::Bingham
+                | MpmMaterial::HerschelBulkley
+                | MpmMaterial::ShearThinning => {
+                    // Volume as for the liquid; shear as an elastic predictor
+                    // b̄ ← (I + Δt·C)·b̄·(I + Δt·C)ᵀ, rescaled to det 1, then
+                    // the viscoplastic return.
+                    let tr = new_c.col(0).x + new_c.col(1).y;
+                    self.jdet[p] = (self.jdet[p] * (1.0 + dt * tr)).clamp(0.1, 3.0);
+                    let step = Mat2::IDENTITY + new_c * dt;
+                    let b = step * self.bmat[p] * step.transpose();
+                    let det = b.determinant();
+                    let b = if det > 1e-6 {
+                        b * det.sqrt().recip()
+                    } else {
+                        Mat2::IDENTITY
+                    };
+                    self.bmat[p] = self.plastic_return(p, b, dt);
+                }
             }
         }
     }
@@ -283,46 +637,56 @@ impl FluidSolver for Mlsmpm {
         self.set_params(params);
     }
 
-    fn substep(&mut self, dt: f32, gravity: Vec2, share: &mut ShareData, c_opos: &mut Vec<Vec2>) {
+    fn set_phases(&mut self, phases: &[Phase]) {
+        self.phases = phases.to_vec();
+        self.stale = true;
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.mat, keep);
+        retain_flagged(&mut self.mass, keep);
+        retain_flagged(&mut self.vel, keep);
+        retain_flagged(&mut self.cmat, keep);
+        retain_flagged(&mut self.jdet, keep);
+        retain_flagged(&mut self.fmat, keep);
+        retain_flagged(&mut self.jp, keep);
+        retain_flagged(&mut self.bmat, keep);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
         let n = share.c_pos.len();
         if n == 0 {
             return;
         }
-        self.ensure_sized(n);
         c_opos.resize(n, Vec2::ZERO);
+        self.ensure_sized(&share.c_pos, c_opos, &share.c_phase, dt);
 
+        self.fit_grid(bounds.domain());
         self.p2g(&share.c_pos, dt);
-        self.grid_update(gravity, dt);
-        self.g2p(&mut share.c_pos, dt);
-
-        // Shared/rendered bookkeeping, matching the granular/PBF convention.
-        let x = &share.c_pos;
-        let mut speed_sum = 0.0f32;
-        let mut max_speed = 0.0f32;
-        let mut jsum = 0.0f64;
-        for i in 0..n {
-            c_opos[i] = x[i] - self.vel[i] * dt;
-            let speed = (self.vel[i] * dt * 20.0).length();
-            share.c_color[i] = (speed + 198.0) % 360.0;
-            speed_sum += speed;
-            max_speed = max_speed.max(speed);
-            jsum += self.jdet[i] as f64;
-        }
+        self.grid_update(gravity, dt, bounds);
+        self.g2p(&mut share.c_pos, dt, bounds);
 
-        let ps = &mut share.perf_stats;
-        ps.mean_speed = speed_sum / n as f32;
-        ps.max_speed = max_speed;
         // Report mean J (volume ratio) in the density-ratio slot: 1.0 = at rest
-        // volume, <1 compressed, >1 expanded. (For jelly J tracks det F.)
-        ps.pbf_density_ratio = if self.params.material == MpmMaterial::Liquid {
-            (jsum / n as f64) as f32
-        } else {
-            let mut js = 0.0f64;
-            for f in &self.fmat[..n] {
-                js += f.determinant() as f64;
-            }
-            (js / n as f64) as f32
-        };
+        // volume, <1 compressed, >1 expanded. (For the solids J tracks det of
+        // the elastic F.)
+        let jsum: f64 = (0..n)
+            .map(|i| {
+                let j = if self.mat[i].is_solid() {
+                    self.fmat[i].determinant()
+                } else {
+                    self.jdet[i]
+                };
+                j as f64
+            })
+            .sum();
+        report(share, c_opos, &self.vel, dt, (jsum / n as f64) as f32);
     }
 }
 
@@ -344,9 +708,58 @@ fn polar_rotation(f: Mat2) -> Mat2 {
     Mat2::from_cols(Vec2::new(cos, sin), Vec2::new(-sin, cos))
 }
 
+/// Fixed-corotated Kirchhoff stress 2μ(F−R)Fᵀ + λ·J·(J−1)·I, R from the polar
+/// decomposition of F.
+#[inline(always)]
+fn corotated(f: Mat2, mu: f32, la: f32) -> Mat2 {
+    let j = f.determinant();
+    let r = polar_rotation(f);
+    (f - r) * f.transpose() * (2.0 * mu) + Mat2::IDENTITY * (la * j * (j - 1.0))
+}
+
+/// 2×2 singular value decomposition F = U·diag(σ)·Vᵀ with U, V rotations.
+/// Closed form: F = R·S by the polar decomposition, then S = V·diag(σ)·Vᵀ
+/// (symmetric, so a Jacobi rotation diagonalizes it) and U = R·V. An
+/// inverted F (det < 0) shows up as a negative σ₁ rather than a reflection.
+#[inline(always)]
+fn svd2(f: Mat2) -> (Mat2, Vec2, Mat2) {
+    let r = polar_rotation(f);
+    let s = r.transpose() * f;
+    let (a, b, d) = (s.col(0).x, 0.5 * (s.col(0).y + s.col(1).x), s.col(1).y);
+    let theta = 0.5 * (2.0 * b).atan2(a - d);
+    let (sin, cos) = theta.sin_cos();
+    let v = Mat2::from_cols(Vec2::new(cos, sin), Vec2::new(-sin, cos));
+    let sig = Vec2::new(
+        cos * cos * a + 2.0 * cos * sin * b + sin * sin * d,
+        sin * sin * a - 2.0 * cos * sin * b + cos * cos * d,
+    );
+    (r * v, sig, v)
+}
+
+/// Tangential velocity after Coulomb friction removes up to `budget` (μ times
+/// the normal velocity the wall just took out) — it stops, never reverses.
+#[inline(always)]
+fn coulomb(vt: f32, budget: f32) -> f32 {
+    vt.signum() * (vt.abs() - budget).max(0.0)
+}
+
+/// Hencky (logarithmic) strain of singular values σ, guarded against a
+/// crushed or inverted F.
+#[inline(always)]
+fn hencky(sig: Vec2) -> Vec2 {
+    Vec2::new(sig.x.max(1e-4).ln(), sig.y.max(1e-4).ln())
+}
+
+/// Deviatoric (trace-free) part of a 2×2 tensor.
+#[inline(always)]
+fn deviator(m: Mat2) -> Mat2 {
+    m - Mat2::IDENTITY * (0.5 * (m.col(0).x + m.col(1).y))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::{HEIGHT, WIDTH};
     use crate::physics::{Physics, ShareData, Strategy, PHYS_TIME_STEP};
     use std::sync::mpsc::channel;
 
@@ -442,4 +855,253 @@ mod tests {
             "MPM jelly spread like a liquid (lost elasticity): spread_x = {spread_x}"
         );
     }
+
+    /// A viscoplastic heap on the floor: Bingham (at the default yield stress)
+    /// and Herschel–Bulkley must sag a little and then *stop* — below yield
+    /// they're solid — while a shear-thinning fluid of the same consistency
+    /// keeps flowing out, and a liquid runs across the whole floor.
+    #[test]
+    fn mpm_yield_stress_holds_a_heap() {
+        let heap = |material: MpmMaterial| {
+            let s = 2.0 * BALL_SIZE;
+            let mut positions = Vec::new();
+            for gy in 0..30 {
+                for gx in 0..20 {
+                    positions.push(Vec2::new(
+                        700.0 + gx as f32 * s,
+                        HEIGHT - 16.0 - gy as f32 * s,
+                    ));
+                }
+            }
+            let n = positions.len();
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_strategy(Strategy::Mlsmpm);
+            physics.set_mpm_params(MpmParams {
+                material,
+                ..MpmParams::default()
+            });
+            let mut share = ShareData {
+                c_pos: positions,
+                c_color: vec![0.0; n],
+                ..Default::default()
+            };
+            for _ in 0..1440 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            let (nan, escaped) = finite_and_inside(&share);
+            assert_eq!((nan, escaped), (0, 0), "{material:?} heap went bad");
+            let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+            for p in &share.c_pos {
+                lo = lo.min(p.x);
+                hi = hi.max(p.x);
+            }
+            hi - lo
+        };
+
+        // The heap starts 114 px wide and 174 tall.
+        for material in [MpmMaterial::Bingham, MpmMaterial::HerschelBulkley] {
+            let width = heap(material);
+            assert!(
+                width < 350.0,
+                "{material:?} heap flowed below yield: width = {width}"
+            );
+        }
+        let thinning = heap(MpmMaterial::ShearThinning);
+        let liquid = heap(MpmMaterial::Liquid);
+        assert!(
+            thinning > 450.0,
+            "shear-thinning heap held like a yield-stress material: width = {thinning}"
+        );
+        assert!(
+            liquid > 1000.0,
+            "liquid heap did not spread: width = {liquid}"
+        );
+    }
+
+    /// The closed-form 2×2 SVD must reconstruct F with proper rotations U, V,
+    /// including for a sheared, a rotated and an already-diagonal F.
+    #[test]
+    fn svd2_reconstructs() {
+        let rot = |a: f32| Mat2::from_angle(a);
+        for f in [
+            Mat2::IDENTITY,
+            Mat2::from_cols(Vec2::new(0.9, 0.0), Vec2::new(0.0, 1.1)),
+            Mat2::from_cols(Vec2::new(1.2, 0.3), Vec2::new(-0.4, 0.7)),
+            rot(0.7) * Mat2::from_diagonal(Vec2::new(1.3, 0.6)) * rot(-1.9),
+        ] {
+            let (u, sig, v) = svd2(f);
+            let back = u * Mat2::from_diagonal(sig) * v.transpose();
+            assert!(back.abs_diff_eq(f, 1e-5), "U·Σ·Vᵀ = {back:?} ≠ F = {f:?}");
+            for r in [u, v] {
+                assert!((r.transpose() * r).abs_diff_eq(Mat2::IDENTITY, 1e-5));
+                assert!((r.determinant() - 1.0).abs() < 1e-5, "not a rotation");
+            }
+        }
+    }
+
+    /// A sand column collapses into a heap and stops — steeper for a larger
+    /// friction angle, and nowhere near the floor-wide sheet a liquid makes —
+    /// while snow stays finite and in the box.
+    #[test]
+    fn mpm_sand_heaps_and_snow_is_stable() {
+        let column = |params: MpmParams| {
+            let s = 2.0 * BALL_SIZE;
+            let mut positions = Vec::new();
+            for gy in 0..50 {
+                for gx in 0..24 {
+                    positions.push(Vec2::new(
+                        680.0 + gx as f32 * s,
+                        HEIGHT - 16.0 - gy as f32 * s,
+                    ));
+                }
+            }
+            let n = positions.len();
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_strategy(Strategy::Mlsmpm);
+            physics.set_mpm_params(params);
+            let mut share = ShareData {
+                c_pos: positions,
+                c_color: vec![0.0; n],
+                ..Default::default()
+            };
+            for _ in 0..1920 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            let (nan, escaped) = finite_and_inside(&share);
+            assert_eq!(
+                (nan, escaped),
+                (0, 0),
+                "{:?} column went bad",
+                params.material
+            );
+            let (mut lo, mut hi, mut top) = (f32::MAX, f32::MIN, f32::MAX);
+            for p in &share.c_pos {
+                lo = lo.min(p.x);
+                hi = hi.max(p.x);
+                top = top.min(p.y);
+            }
+            (hi - lo, HEIGHT - top, share.perf_stats.mean_speed)
+        };
+        let sand = |friction_angle| MpmParams {
+            material: MpmMaterial::Sand,
+            friction_angle,
+            ..MpmParams::default()
+        };
+
+        // The column starts 138 px wide and 294 tall.
+        let (loose_w, loose_h, loose_v) = column(sand(30.0));
+        let (steep_w, steep_h, steep_v) = column(sand(45.0));
+        assert!(
+            loose_v < 1.0 && steep_v < 1.0,
+            "sand did not come to rest: mean_speed = {loose_v}, {steep_v}"
+        );
+        assert!(
+            loose_w < 1200.0 && loose_h > 50.0,
+            "sand ran out like a liquid: width = {loose_w}, height = {loose_h}"
+        );
+        assert!(
+            steep_w < loose_w && steep_h > loose_h,
+            "friction angle did not steepen the heap: \
+             30° → {loose_w}×{loose_h}, 45° → {steep_w}×{steep_h}"
+        );
+
+        column(MpmParams {
+            material: MpmMaterial::Snow,
+            ..MpmParams::default()
+        });
+    }
+
+    /// One scene, three materials by phase: a dense jelly cube dropped into a
+    /// liquid pool over a sand bed must land intact and sink below the
+    /// liquid, where the same particles left on the solver's liquid default
+    /// splash apart.
+    #[test]
+    fn mpm_phases_mix_materials() {
+        let run = |mixed: bool| {
+            let s = 2.0 * BALL_SIZE;
+            let (mut positions, mut phase) = (Vec::new(), Vec::new());
+            for gy in 0..20 {
+                for gx in 0..((WIDTH - 20.0) / s) as usize {
+                    positions.push(Vec2::new(
+                        10.0 + gx as f32 * s,
+                        HEIGHT - 10.0 - gy as f32 * s,
+                    ));
+                    phase.push(if gy < 4 { 2 } else { 0 });
+                }
+            }
+            for gy in 0..12 {
+                for gx in 0..12 {
+                    positions.push(Vec2::new(720.0 + gx as f32 * s, 500.0 + gy as f32 * s));
+                    phase.push(1);
+                }
+            }
+            let n = positions.len();
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_strategy(Strategy::Mlsmpm);
+            let jelly = MpmParams {
+                material: MpmMaterial::Jelly,
+                shear: 4e5,
+                ..MpmParams::default()
+            };
+            let sand = MpmParams {
+                material: MpmMaterial::Sand,
+                ..MpmParams::default()
+            };
+            physics.set_phases(vec![
+                Phase::default(),
+                Phase {
+                    density: 2.0,
+                    mpm: mixed.then_some(jelly),
+                },
+                Phase {
+                    density: 2.0,
+                    mpm: mixed.then_some(sand),
+                },
+            ]);
+            let mut share = ShareData {
+                c_pos: positions,
+                c_color: vec![0.0; n],
+                c_phase: phase,
+                ..Default::default()
+            };
+            for _ in 0..1440 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            let (nan, escaped) = finite_and_inside(&share);
+            assert_eq!((nan, escaped), (0, 0), "mixed scene went bad");
+            let (mut lo, mut hi) = (f32::MAX, f32::MIN);
+            let (mut cube_y, mut liquid_y) = ((0.0, 0.0), (0.0, 0.0));
+            for (p, &k) in share.c_pos.iter().zip(&share.c_phase) {
+                match k {
+                    1 => {
+                        lo = lo.min(p.x);
+                        hi = hi.max(p.x);
+                        cube_y = (cube_y.0 + p.y, cube_y.1 + 1.0);
+                    }
+                    0 => liquid_y = (liquid_y.0 + p.y, liquid_y.1 + 1.0),
+                    _ => {}
+                }
+            }
+            (hi - lo, cube_y.0 / cube_y.1, liquid_y.0 / liquid_y.1)
+        };
+
+        // The cube starts 66 px wide.
+        let (width, cube_y, liquid_y) = run(true);
+        assert!(width < 150.0, "jelly cube came apart: width = {width}");
+        assert!(
+            cube_y > liquid_y,
+            "dense jelly cube floated: cube y = {cube_y}, liquid y = {liquid_y}"
+        );
+        let (width, _, _) = run(false);
+        assert!(
+            width > 300.0,
+            "phase materials leaked into the default liquid: width = {width}"
+        );
+    }
 }
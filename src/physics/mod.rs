@@ -11,30 +11,69 @@
 //!
 //! Each concrete strategy lives in its own module and keeps *its own* scratch
 //! (grids, neighbor lists, velocities, deformation gradients, …) rather than
-//! bundling every method's data into one struct:
+//! bundling every method's data into one struct. By [`Strategy`] variant:
 //!
 //! - [`granular`] — short-range 1/r² repulsion + hard non-penetration contact
 //!   (piles/stacks like sand); the historical, heavily-optimized engine.
 //! - [`pbf`] — Position Based Fluids density constraint (pours/splashes as an
 //!   incompressible liquid).
+//! - [`dfsph`], [`iisph`], [`wcsph`] — SPH with a pressure force: divergence-
+//!   free, implicit-incompressible, and the explicit weakly-compressible
+//!   baseline.
+//! - [`viscoelastic`] — Clavet's double-density relaxation plus plastic
+//!   springs (slime, goo).
+//! - [`mlsmpm`] — hybrid grid+particle MPM with swappable materials (water,
+//!   jelly, snow, sand, viscoplastic mud).
+//! - [`flip`] — FLIP/PIC on a MAC grid with an exact pressure projection.
+//! - [`lbm`] — free-surface lattice Boltzmann, the particles tracing its flow.
 //!
-//! See `docs/solvers.md` for the survey of these and the methods being added.
+//! Solid obstacles inside the box ([`boundary`]) and the container shape
+//! ([`sdf`]) are likewise owned by the coordinator and handed to the active
+//! strategy every substep, so all of them collide against the same set.
+//! Emitters and drains ([`emitter`]) add and delete particles between steps;
+//! every strategy compacts its per-particle state to match
+//! ([`FluidSolver::retain`]). So do outflow edges of the [`Domain`].
+//!
+//! See `docs/solvers.md` for the survey of these methods and how they compare.
 
+mod boundary;
 mod dfsph;
+mod domain;
+mod emitter;
+mod flip;
 mod granular;
+mod iisph;
+mod lbm;
 mod mlsmpm;
 mod pbf;
+mod phase;
+mod rigid;
+mod sdf;
 mod sph;
+mod viscoelastic;
+mod wcsph;
 
 use glam::Vec2;
 use std::sync::mpsc::Receiver;
 
-use crate::constants::{BALL_SIZE, HEIGHT, INITIAL_BALL_SPEED_MODIFIER, WIDTH};
+use crate::constants::{BALL_SIZE, INITIAL_BALL_SPEED_MODIFIER};
 
+pub use boundary::{Boundaries, Container, Motion, Obstacle, Pose, Shape};
 pub use dfsph::{Dfsph, DfsphParams};
+pub use domain::{Domain, Edge, Outflow};
+use emitter::Emission;
+pub use emitter::{Drain, Emitter};
+pub use flip::{Flip, FlipParams};
 pub use granular::GranularSolver;
+pub use iisph::{Iisph, IisphParams};
+pub use lbm::{Lbm, LbmCollision, LbmParams};
 pub use mlsmpm::{Mlsmpm, MpmMaterial, MpmParams};
 pub use pbf::{Pbf, PbfParams};
+pub use phase::Phase;
+pub use rigid::RigidBody;
+pub use sdf::{Sdf, SdfGrid};
+pub use viscoelastic::{Viscoelastic, ViscoelasticParams};
+pub use wcsph::{Wcsph, WcsphParams};
 
 const GRAVITY: Vec2 = Vec2::new(0.0, 9.8);
 pub const PHYS_TIME_STEP: f32 = 1.0 / 480.0;
@@ -43,11 +82,6 @@ const ADAPTIVE_DT_MIN: f32 = PHYS_TIME_STEP * 0.1;
 const ADAPTIVE_DT_MAX: f32 = PHYS_TIME_STEP * 2.0;
 const MAX_SAFE_VELOCITY: f32 = 100.0; // Reduce dt when velocities exceed this
 
-pub(crate) const LEFT_WALL: f32 = 0.;
-pub(crate) const RIGHT_WALL: f32 = WIDTH;
-pub(crate) const BOTTOM_WALL: f32 = 0.;
-pub(crate) const TOP_WALL: f32 = HEIGHT;
-
 /// Which fluid model the engine runs. Selected once (via `--sim` on the
 /// command line, or [`Physics::set_strategy`]) and kept for the whole run.
 ///
@@ -66,9 +100,27 @@ pub enum Strategy {
     /// Divergence-Free SPH (Bender & Koschier): crisp, low-dissipation
     /// incompressible water. See [`dfsph`].
     Dfsph,
+    /// Implicit Incompressible SPH (Ihmsen et al.): DFSPH's family with a
+    /// single pressure-Poisson solve, for A/B comparisons. See [`iisph`].
+    Iisph,
+    /// Weakly-compressible SPH (Becker & Teschner): the explicit Tait
+    /// equation-of-state baseline, CFL-substepped. See [`wcsph`].
+    Wcsph,
+    /// Clavet et al.'s viscoelastic fluid: double-density relaxation plus a
+    /// plastic spring network, for slime and goo. See [`viscoelastic`].
+    Viscoelastic,
     /// MLS-MPM (Hu et al.): hybrid grid+particle method; fluid by default,
-    /// elastic jelly with a swapped constitutive model. See [`mlsmpm`].
+    /// elastic jelly, snow, sand or viscoplastic mud/paint with a swapped
+    /// constitutive model. See [`mlsmpm`].
     Mlsmpm,
+    /// FLIP/PIC (Zhu & Bridson): particles advected through a staggered MAC
+    /// grid with an exact pressure projection — lively, incompressible
+    /// water. See [`flip`].
+    Flip,
+    /// Free-surface D2Q9 lattice Boltzmann (BGK or cumulant collision):
+    /// the one Eulerian method, the particles being passive tracers of its
+    /// flow. See [`lbm`].
+    Lbm,
 }
 
 impl Strategy {
@@ -77,7 +129,12 @@ impl Strategy {
             "granular" | "balls" | "repulsion" => Some(Strategy::Granular),
             "pbf" | "fluid" | "water" => Some(Strategy::Pbf),
             "dfsph" | "divergence-free" => Some(Strategy::Dfsph),
+            "iisph" | "implicit" => Some(Strategy::Iisph),
+            "wcsph" | "tait" => Some(Strategy::Wcsph),
+            "viscoelastic" | "goo" | "slime" => Some(Strategy::Viscoelastic),
             "mlsmpm" | "mpm" | "jelly" => Some(Strategy::Mlsmpm),
+            "flip" | "pic" | "mac" => Some(Strategy::Flip),
+            "lbm" | "lattice" | "d2q9" => Some(Strategy::Lbm),
             _ => None,
         }
     }
@@ -88,7 +145,12 @@ impl Strategy {
             Strategy::Granular,
             Strategy::Pbf,
             Strategy::Dfsph,
+            Strategy::Iisph,
+            Strategy::Wcsph,
+            Strategy::Viscoelastic,
             Strategy::Mlsmpm,
+            Strategy::Flip,
+            Strategy::Lbm,
         ]
     }
 
@@ -98,7 +160,12 @@ impl Strategy {
             Strategy::Granular => "granular",
             Strategy::Pbf => "pbf",
             Strategy::Dfsph => "dfsph",
+            Strategy::Iisph => "iisph",
+            Strategy::Wcsph => "wcsph",
+            Strategy::Viscoelastic => "viscoelastic",
             Strategy::Mlsmpm => "mlsmpm",
+            Strategy::Flip => "flip",
+            Strategy::Lbm => "lbm",
         }
     }
 
@@ -107,7 +174,12 @@ impl Strategy {
             Strategy::Granular => Box::new(GranularSolver::new(scale, Vec::new())),
             Strategy::Pbf => Box::new(Pbf::new()),
             Strategy::Dfsph => Box::new(Dfsph::new()),
+            Strategy::Iisph => Box::new(Iisph::new()),
+            Strategy::Wcsph => Box::new(Wcsph::new()),
+            Strategy::Viscoelastic => Box::new(Viscoelastic::new()),
             Strategy::Mlsmpm => Box::new(Mlsmpm::new()),
+            Strategy::Flip => Box::new(Flip::new()),
+            Strategy::Lbm => Box::new(Lbm::new()),
         }
     }
 }
@@ -123,10 +195,62 @@ pub enum EventToPthread {
 pub struct ShareData {
     pub c_pos: Vec<Vec2>,
     pub c_color: Vec<f32>,
+    /// Persistent particle IDs, parallel to `c_pos`. [`Physics`] hands them
+    /// out in order of arrival (the initial particles are 0..n) and carries
+    /// them through every reorder and removal, so `c_id[i]` names the same
+    /// particle from step to step even when its index `i` does not.
+    pub c_id: Vec<u32>,
+    /// Fluid phase of each particle, parallel to `c_pos`: an index into
+    /// [`Physics::set_phases`]. [`Physics`] fills missing entries with 0 and
+    /// carries the IDs through reorders and removal; only PBF, the SPH
+    /// solvers and MLS-MPM tell the phases apart.
+    pub c_phase: Vec<u8>,
+    /// Radius of each particle, parallel to `c_pos`. [`Physics`] fills
+    /// missing entries with `BALL_SIZE` and carries the radii through
+    /// reorders and removal; only the granular engine sizes its contacts by
+    /// them (the other strategies treat every particle as `BALL_SIZE`).
+    pub c_radius: Vec<f32>,
     pub phys_time: f32,
     pub perf_stats: PerformanceStats,
 }
 
+/// Display hues of phases 1, 2, …; phase 0 keeps the speed hue.
+const PHASE_HUES: [f32; 4] = [38.0, 330.0, 110.0, 0.0];
+
+impl ShareData {
+    /// Hue particle `i` is drawn with: its phase's color outside phase 0,
+    /// else the speed hue the solver left in `c_color`.
+    pub fn hue(&self, i: usize) -> f32 {
+        match self.c_phase.get(i) {
+            Some(&k) if k > 0 => PHASE_HUES[(k as usize - 1) % PHASE_HUES.len()],
+            _ => self.c_color[i],
+        }
+    }
+
+    /// Radius particle `i` is drawn (and, under the granular strategy,
+    /// collided) with.
+    pub fn radius(&self, i: usize) -> f32 {
+        self.c_radius.get(i).copied().unwrap_or(BALL_SIZE)
+    }
+
+    /// Current index of the particle with ID `id`, if it still exists. A
+    /// linear scan; use [`ShareData::id_to_index`] to look up many.
+    pub fn index_of(&self, id: u32) -> Option<usize> {
+        self.c_id.iter().position(|&k| k == id)
+    }
+
+    /// Dense ID → index table: entry `id` is that particle's current index,
+    /// or `u32::MAX` if it has been removed (or not yet tagged).
+    pub fn id_to_index(&self) -> Vec<u32> {
+        let len = self.c_id.iter().max().map_or(0, |&m| m as usize + 1);
+        let mut table = vec![u32::MAX; len];
+        for (i, &id) in self.c_id.iter().enumerate() {
+            table[id as usize] = i as u32;
+        }
+        table
+    }
+}
+
 // Performance statistics for benchmarking
 #[derive(Default, Clone)]
 pub struct PerformanceStats {
@@ -146,6 +270,18 @@ pub struct PerformanceStats {
     pub mean_speed: f32,
     pub max_speed: f32,
     pub pbf_density_ratio: f32,
+    /// Particles per simulated second that left through outflow edges over
+    /// the last step (see [`Physics::outflow`] for the running tally).
+    pub outflow_flux: f32,
+    /// Iterations the last implicit viscosity solve took (DFSPH with
+    /// [`DfsphParams::viscosity`] set; 0 otherwise).
+    pub viscosity_iters: usize,
+    /// Iterations the last pressure solve took (IISPH; FLIP's slowest body
+    /// of fluid; 0 otherwise).
+    pub pressure_iters: usize,
+    /// Steps the last substep was split into by the CFL condition (WCSPH;
+    /// LBM's lattice steps; 0 otherwise).
+    pub sph_substeps: usize,
 }
 
 /// A fluid-simulation strategy: one numerical method for advancing the
@@ -163,8 +299,24 @@ pub trait FluidSolver: Send {
     /// Advance the particle system by one substep of length `dt` under
     /// `gravity`. `share` carries positions/colors/stats; `c_opos` is the
     /// Størmer–Verlet previous-position array (velocity memory) that outlives
-    /// any strategy switch.
-    fn substep(&mut self, dt: f32, gravity: Vec2, share: &mut ShareData, c_opos: &mut Vec<Vec2>);
+    /// any strategy switch; `bounds` is the obstacle set every particle must
+    /// stay out of, on top of the box walls (mutable so contacts with rigid
+    /// bodies can book their impulses).
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    );
+
+    /// Particles are being deleted between steps: drop the per-particle state
+    /// of every `i` with `keep[i] == false`, survivors keeping their order, so
+    /// it lines up with `c_pos` again. State the solver has not sized yet
+    /// (particles added since its last substep sit at the tail) is shorter
+    /// than `keep`; only its prefix is filtered.
+    fn retain(&mut self, keep: &[bool]);
 
     /// Whether Verlet neighbor lists are active (HUD readout; granular only).
     fn verlet_lists_enabled(&self) -> bool {
@@ -181,12 +333,34 @@ pub trait FluidSolver: Send {
     fn set_solver_omega(&mut self, _omega: f32) {}
     /// Serial→parallel crossover particle count (granular only).
     fn set_par_min_particles(&mut self, _par_min: usize) {}
+    /// Static and kinetic Coulomb friction and rolling resistance of the
+    /// particle contacts (granular only; 0 = frictionless, rolls freely).
+    fn set_friction(&mut self, _static_mu: f32, _kinetic_mu: f32, _rolling: f32) {}
+    /// Wet-grain capillary cohesion: the pull at contact (gravity units) and
+    /// the gap beyond contact at which the bridge ruptures, clamped to
+    /// 0..=1.5 px (granular only; 0 = dry).
+    fn set_cohesion(&mut self, _strength: f32, _rupture: f32) {}
     /// Toggle Verlet neighbor lists (granular only).
     fn toggle_verlet_lists(&mut self) {}
     /// Override the density-solver coefficients (PBF only).
     fn set_pbf_params(&mut self, _params: PbfParams) {}
+    /// Override the DFSPH coefficients (DFSPH only).
+    fn set_dfsph_params(&mut self, _params: DfsphParams) {}
+    /// Override the IISPH coefficients (IISPH only).
+    fn set_iisph_params(&mut self, _params: IisphParams) {}
+    /// Override the WCSPH coefficients (WCSPH only).
+    fn set_wcsph_params(&mut self, _params: WcsphParams) {}
+    /// Override the viscoelastic coefficients (viscoelastic only).
+    fn set_viscoelastic_params(&mut self, _params: ViscoelasticParams) {}
     /// Override the MLS-MPM coefficients / material (MPM only).
     fn set_mpm_params(&mut self, _params: MpmParams) {}
+    /// Override the FLIP coefficients (FLIP only).
+    fn set_flip_params(&mut self, _params: FlipParams) {}
+    /// Override the lattice Boltzmann coefficients (LBM only).
+    fn set_lbm_params(&mut self, _params: LbmParams) {}
+    /// The fluid phases the particles' phase IDs index (PBF, the SPH solvers
+    /// and MPM only).
+    fn set_phases(&mut self, _phases: &[Phase]) {}
 
     /// Bench/test hook: forces via the grid stencil (granular only; empty
     /// otherwise). See [`GranularSolver`].
@@ -210,6 +384,22 @@ pub struct Physics {
 
     solver: Box<dyn FluidSolver>,
     strategy: Strategy,
+    /// Obstacles inside the box; outlive a strategy switch like `c_opos`.
+    boundaries: Boundaries,
+    /// Particle sources / sinks, and the cap on the total count (emitters and
+    /// the cannon stop adding once it is reached).
+    emitters: Vec<Emitter>,
+    emission: Emission,
+    /// The ID the next particle to arrive gets (see [`ShareData::c_id`]).
+    next_id: u32,
+    drains: Vec<Drain>,
+    /// Particles deleted by the outflow edges so far.
+    outflow: Outflow,
+    /// Fluid phases, handed to every new solver too.
+    phases: Vec<Phase>,
+    max_particles: Option<usize>,
+    /// Simulated seconds so far; poses the moving boundaries.
+    time: f32,
     /// Seed force scale, kept so a strategy switch can rebuild a solver with it.
     scale: f32,
 
@@ -237,6 +427,15 @@ impl Physics {
             rx,
             solver: Box::new(GranularSolver::new(scale, c_force)),
             strategy: Strategy::default(),
+            boundaries: Boundaries::default(),
+            emitters: Vec::new(),
+            emission: Emission::default(),
+            next_id: 0,
+            drains: Vec::new(),
+            outflow: Outflow::default(),
+            phases: Vec::new(),
+            max_particles: None,
+            time: 0.0,
             scale,
             gravity: GRAVITY,
             substeps: 1,
@@ -256,6 +455,22 @@ impl Physics {
     pub fn set_strategy(&mut self, strategy: Strategy) {
         self.strategy = strategy;
         self.solver = strategy.make_solver(self.scale);
+        self.solver.set_phases(&self.phases);
+    }
+
+    /// Define the fluid phases: entry `k` describes the particles whose
+    /// [`ShareData::c_phase`] is `k` (an empty table, the default, or a
+    /// missing entry is a [`Phase::default`] fluid). PBF and the SPH solvers
+    /// run them as a multi-phase fluid, MLS-MPM as a mixed-material scene
+    /// (each phase's [`Phase::mpm`] material and density); the other
+    /// strategies ignore phases.
+    pub fn set_phases(&mut self, phases: Vec<Phase>) {
+        self.phases = phases;
+        self.solver.set_phases(&self.phases);
+    }
+
+    pub fn phases(&self) -> &[Phase] {
+        &self.phases
     }
 
     /// Per-substep gravity vector. Scenarios use this to tilt the box.
@@ -263,6 +478,115 @@ impl Physics {
         self.gravity = gravity;
     }
 
+    /// Place an obstacle in the box (static, or kinematic via its
+    /// [`Motion`]); every strategy collides with it from the next substep on.
+    /// Returns its index in [`Physics::obstacles`].
+    pub fn add_obstacle(&mut self, obstacle: Obstacle) -> usize {
+        self.boundaries.add(obstacle)
+    }
+
+    /// Remove every obstacle (the box walls remain).
+    pub fn clear_obstacles(&mut self) {
+        self.boundaries.clear();
+    }
+
+    pub fn obstacles(&self) -> &[Obstacle] {
+        self.boundaries.obstacles()
+    }
+
+    /// Reshape the tank: particles are kept inside the container's SDF (and
+    /// the box) from the next substep on. `None` restores the plain box.
+    pub fn set_container(&mut self, container: Option<Container>) {
+        self.boundaries.set_container(container);
+    }
+
+    pub fn container(&self) -> Option<&Container> {
+        self.boundaries.container()
+    }
+
+    /// Resize the box. Every strategy re-tiles its grid to the new size on
+    /// the next substep; particles left outside are pushed back in by the
+    /// walls.
+    pub fn set_domain(&mut self, domain: Domain) {
+        self.boundaries.set_domain(domain);
+    }
+
+    pub fn domain(&self) -> Domain {
+        *self.boundaries.domain()
+    }
+
+    /// Drop a rigid body into the tank; it is pushed by the fluid and pushes
+    /// back under every strategy. Returns its index in [`Physics::bodies`].
+    pub fn add_body(&mut self, body: RigidBody) -> usize {
+        self.boundaries.add_body(body)
+    }
+
+    pub fn clear_bodies(&mut self) {
+        self.boundaries.clear_bodies();
+    }
+
+    /// The rigid bodies at their current pose and velocity.
+    pub fn bodies(&self) -> &[RigidBody] {
+        self.boundaries.bodies()
+    }
+
+    /// Add a nozzle; it releases particles from the next step on. Returns its
+    /// index in [`Physics::emitters`].
+    pub fn add_emitter(&mut self, emitter: Emitter) -> usize {
+        self.emitters.push(emitter);
+        self.emitters.len() - 1
+    }
+
+    pub fn emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+
+    /// For retargeting or pausing a nozzle mid-run.
+    pub fn emitters_mut(&mut self) -> &mut [Emitter] {
+        &mut self.emitters
+    }
+
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+        self.emission.clear();
+    }
+
+    /// Add a sink; particles inside it are deleted at the end of every step.
+    pub fn add_drain(&mut self, drain: Drain) -> usize {
+        self.drains.push(drain);
+        self.drains.len() - 1
+    }
+
+    pub fn drains(&self) -> &[Drain] {
+        &self.drains
+    }
+
+    pub fn clear_drains(&mut self) {
+        self.drains.clear();
+    }
+
+    /// Particles deleted so far by each outflow edge of the domain.
+    pub fn outflow(&self) -> Outflow {
+        self.outflow
+    }
+
+    /// Cap the particle count (`None` = unlimited). Emitters and the cannon
+    /// add nothing past it; particles already over it are left alone.
+    pub fn set_max_particles(&mut self, max: Option<usize>) {
+        self.max_particles = max;
+    }
+
+    /// Signed distance to the nearest obstacle or container wall at its
+    /// current pose (negative inside the solid). For drawing the boundaries.
+    pub fn boundary_distance(&self, p: Vec2) -> f32 {
+        self.boundaries.distance(p)
+    }
+
+    /// Simulated time in seconds (what kinematic boundaries are driven by).
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
     /// Adjust the repulsion force scale (granular; ignored by other models).
     pub fn add_scale(&mut self, delta: f32) {
         self.scale += delta;
@@ -274,11 +598,41 @@ impl Physics {
         self.solver.set_pbf_params(params);
     }
 
+    /// Override the DFSPH coefficients.
+    pub fn set_dfsph_params(&mut self, params: DfsphParams) {
+        self.solver.set_dfsph_params(params);
+    }
+
+    /// Override the IISPH coefficients.
+    pub fn set_iisph_params(&mut self, params: IisphParams) {
+        self.solver.set_iisph_params(params);
+    }
+
+    /// Override the WCSPH coefficients.
+    pub fn set_wcsph_params(&mut self, params: WcsphParams) {
+        self.solver.set_wcsph_params(params);
+    }
+
+    /// Override the viscoelastic (Clavet) coefficients.
+    pub fn set_viscoelastic_params(&mut self, params: ViscoelasticParams) {
+        self.solver.set_viscoelastic_params(params);
+    }
+
     /// Override the MLS-MPM coefficients / material.
     pub fn set_mpm_params(&mut self, params: MpmParams) {
         self.solver.set_mpm_params(params);
     }
 
+    /// Override the FLIP coefficients.
+    pub fn set_flip_params(&mut self, params: FlipParams) {
+        self.solver.set_flip_params(params);
+    }
+
+    /// Override the lattice Boltzmann coefficients / collision operator.
+    pub fn set_lbm_params(&mut self, params: LbmParams) {
+        self.solver.set_lbm_params(params);
+    }
+
     /// Far-field force refresh interval in substeps (1 = every substep).
     pub fn set_force_interval(&mut self, interval: usize) {
         self.solver.set_force_interval(interval);
@@ -301,6 +655,26 @@ impl Physics {
         self.solver.set_par_min_particles(par_min);
     }
 
+    /// Static and kinetic Coulomb friction of the granular contacts, so piles
+    /// hold an angle of repose instead of slumping flat (0 = frictionless),
+    /// and their rolling resistance: the fraction, 0..=1, of a touching
+    /// pair's relative rolling velocity lost each solver iteration (0 = rolls
+    /// freely). A dry-sand heap wants 1 and more solver iterations.
+    pub fn set_friction(&mut self, static_mu: f32, kinetic_mu: f32, rolling: f32) {
+        self.solver.set_friction(static_mu, kinetic_mu, rolling);
+    }
+
+    /// Capillary-bridge cohesion between granular particles, for wet sand
+    /// that holds steep walls and clumps: the pull at contact, in gravity
+    /// units, fading to nothing `rupture` px past contact. 0 = dry.
+    ///
+    /// `rupture` is clamped to 0..=1.5 px, the gap the one-cell force
+    /// stencil can hold (`FORCE_CUTOFF − 2·BALL_SIZE`); a longer bridge
+    /// would be cut off at the stencil's edge, so larger values act as 1.5.
+    pub fn set_cohesion(&mut self, strength: f32, rupture: f32) {
+        self.solver.set_cohesion(strength, rupture);
+    }
+
     /// Internal substeps per `step()` call (Small Steps): each runs the full
     /// solver pipeline at dt/substeps. Pair with proportionally fewer solver
     /// iterations and a scaled `force_interval` to hold the far-field refresh
@@ -338,14 +712,40 @@ impl Physics {
         // into substeps with proportionally fewer solver iterations is more
         // accurate than iterating; requires the a·dt² integrator (stage 21).
         let s = self.substeps.max(1);
+        let h = effective_dt / s as f32;
+        self.emit(effective_dt, h, share);
+        self.tag_new(share);
         for _ in 0..s {
+            // Kinematic boundaries take their end-of-substep pose, which is
+            // where the particles must end up clear of them; bodies move to
+            // their predicted pose, and take the fluid's push afterwards.
+            self.time += h;
+            self.boundaries
+                .advance(self.time, h, self.gravity / PHYS_TIME_STEP);
             self.solver.substep(
-                effective_dt / s as f32,
+                h,
                 self.gravity,
                 share,
                 &mut self.c_opos,
+                &mut self.boundaries,
             );
+            self.boundaries.finish_bodies();
         }
+        if !self.drains.is_empty() {
+            let drains = std::mem::take(&mut self.drains);
+            self.remove_particles(share, |_, p| drains.iter().any(|d| d.contains(p)));
+            self.drains = drains;
+        }
+        let domain = *self.boundaries.domain();
+        let edges = [domain.left, domain.right, domain.top, domain.bottom];
+        share.perf_stats.outflow_flux = if edges.contains(&Edge::Outflow) {
+            let mut outflow = self.outflow;
+            let gone = self.remove_particles(share, |_, p| outflow.record(&domain, p));
+            self.outflow = outflow;
+            gone as f32 / effective_dt
+        } else {
+            0.0
+        };
 
         // Adaptive dt reads the last substep's peak speed (both models fill
         // perf_stats.max_speed in the shared |Δx|·20 units).
@@ -368,10 +768,94 @@ impl Physics {
         self.adaptive_dt
     }
 
+    /// Release every emitter's particles for a step of `dt`. Their previous
+    /// positions encode the launch velocity over one substep `h`, the same way
+    /// the cannon's do.
+    fn emit(&mut self, dt: f32, h: f32, share: &mut ShareData) {
+        if self.emitters.is_empty() {
+            return;
+        }
+        let mut spawned = Vec::new();
+        let room = self.room(share.c_pos.len());
+        self.emission.emit(&self.emitters, dt, room, &mut spawned);
+        share.c_phase.resize(share.c_pos.len(), 0);
+        share.c_radius.resize(share.c_pos.len(), BALL_SIZE);
+        for (p, v, phase) in spawned {
+            share.c_pos.push(p);
+            self.c_opos.push(p - v * h);
+            share.c_color.push(0.0);
+            share.c_phase.push(phase);
+            share.c_radius.push(BALL_SIZE);
+        }
+    }
+
+    /// Delete every particle for which `remove(index, position)` holds, from
+    /// `c_pos`, `c_color`, `c_opos` and the active strategy's per-particle
+    /// state alike. Survivors keep their relative order: one at index `i`
+    /// moves down by the number of removed particles before it. Call between
+    /// steps (never from inside one). Returns how many were removed.
+    pub fn remove_particles(
+        &mut self,
+        share: &mut ShareData,
+        mut remove: impl FnMut(usize, Vec2) -> bool,
+    ) -> usize {
+        let keep: Vec<bool> = share
+            .c_pos
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| !remove(i, p))
+            .collect();
+        let removed = keep.iter().filter(|&&k| !k).count();
+        if removed > 0 {
+            retain_flagged(&mut share.c_pos, &keep);
+            retain_flagged(&mut share.c_color, &keep);
+            retain_flagged(&mut share.c_id, &keep);
+            retain_flagged(&mut share.c_phase, &keep);
+            retain_flagged(&mut share.c_radius, &keep);
+            retain_flagged(&mut self.c_opos, &keep);
+            self.solver.retain(&keep);
+        }
+        removed
+    }
+
+    /// Delete every particle inside `region` (where its SDF is negative).
+    /// Returns how many were removed.
+    pub fn remove_in_region(&mut self, share: &mut ShareData, region: &Sdf) -> usize {
+        self.remove_particles(share, |_, p| region.eval(p).0 < 0.0)
+    }
+
+    /// Give every particle past the end of `c_id` (the initial set, or ones
+    /// pushed since) the next fresh IDs, those past the end of `c_phase`
+    /// phase 0, and those past the end of `c_radius` the default radius.
+    fn tag_new(&mut self, share: &mut ShareData) {
+        let n = share.c_pos.len();
+        share.c_phase.resize(n, 0);
+        share.c_radius.resize(n, BALL_SIZE);
+        if share.c_id.len() < n {
+            let first = self.next_id;
+            self.next_id += (n - share.c_id.len()) as u32;
+            share.c_id.extend(first..self.next_id);
+        }
+    }
+
+    /// How many particles may still be added on top of `count`.
+    fn room(&self, count: usize) -> usize {
+        self.max_particles
+            .map_or(usize::MAX, |max| max.saturating_sub(count))
+    }
+
     pub fn do_cannon(&mut self, dt: f32, share: &mut ShareData, start: Vec2, cannon: Vec2) {
-        for k in 0..20 {
-            self.cannon(-k as f32 * (2.2 * BALL_SIZE), 0., dt, share, start, cannon);
+        for k in 0..20.min(self.room(share.c_pos.len())) {
+            self.cannon(
+                -(k as f32) * (2.2 * BALL_SIZE),
+                0.,
+                dt,
+                share,
+                start,
+                cannon,
+            );
         }
+        self.tag_new(share);
     }
 
     pub fn toggle_verlet_lists(&mut self) {
@@ -415,6 +899,44 @@ impl Physics {
     }
 }
 
+/// Compact per-particle `state` in place to the entries flagged in `keep`,
+/// preserving order. A `state` shorter than `keep` (not yet grown to
+/// particles added since it was last sized) has just its prefix filtered.
+pub(crate) fn retain_flagged<T>(state: &mut Vec<T>, keep: &[bool]) {
+    let mut flags = keep.iter();
+    state.retain(|_| flags.next().copied().unwrap_or(true));
+}
+
+/// Shared/rendered bookkeeping at the end of a substep for the strategies
+/// that carry their own particle velocities, mirroring the granular/PBF
+/// convention: `c_opos = pos − v·dt` (so external velocity readers agree),
+/// `c_color` encodes speed as a hue, and the perf stats get the mean and
+/// peak speed plus the strategy's `density_ratio` (1.0 = at rest density).
+pub(crate) fn report(
+    share: &mut ShareData,
+    c_opos: &mut [Vec2],
+    vel: &[Vec2],
+    dt: f32,
+    density_ratio: f32,
+) {
+    let n = vel.len();
+    let x = &share.c_pos;
+    let mut speed_sum = 0.0f32;
+    let mut max_speed = 0.0f32;
+    for i in 0..n {
+        c_opos[i] = x[i] - vel[i] * dt;
+        let speed = (vel[i] * dt * 20.0).length();
+        share.c_color[i] = (speed + 198.0) % 360.0;
+        speed_sum += speed;
+        max_speed = max_speed.max(speed);
+    }
+
+    let ps = &mut share.perf_stats;
+    ps.mean_speed = speed_sum / n.max(1) as f32;
+    ps.max_speed = max_speed;
+    ps.pbf_density_ratio = density_ratio;
+}
+
 /// Hardware reciprocal square root (SSE `rsqrtss`, ~12-bit) refined with one
 /// Newton-Raphson step to ~22 bits — plenty for force directions and contact
 /// normals, and much cheaper than `sqrt` + `div`. Callers must keep x > 0.
@@ -434,11 +956,24 @@ pub(crate) fn fast_rsqrt(x: f32) -> f32 {
 
 /// Keep a position inside the box by one ball radius, matching the granular
 /// wall inset. Pure position clamp (SPH-family models handle the bounce via
-/// the velocity update, which sees the clamped displacement).
+/// the velocity update, which sees the clamped displacement). Only
+/// [`Edge::Wall`] edges clamp; outflow and periodic edges are left open.
 #[inline(always)]
-pub(crate) fn clamp_wall(p: &mut Vec2) {
-    p.x = p.x.clamp(LEFT_WALL + BALL_SIZE, RIGHT_WALL - BALL_SIZE);
-    p.y = p.y.clamp(BOTTOM_WALL + BALL_SIZE, TOP_WALL - BALL_SIZE);
+pub(crate) fn clamp_wall(p: &mut Vec2, domain: &Domain) {
+    let (left, right) = domain.walls_x();
+    let (top, bottom) = domain.walls_y();
+    if left {
+        p.x = p.x.max(BALL_SIZE);
+    }
+    if right {
+        p.x = p.x.min(domain.width - BALL_SIZE);
+    }
+    if top {
+        p.y = p.y.max(BALL_SIZE);
+    }
+    if bottom {
+        p.y = p.y.min(domain.height - BALL_SIZE);
+    }
 }
 
 enum Collision {
@@ -448,18 +983,25 @@ enum Collision {
     Right,
 }
 
-fn collides_wall(pos_a: Vec2, scale_a: f32) -> (Option<Collision>, Option<Collision>) {
+fn collides_wall(
+    pos_a: Vec2,
+    scale_a: f32,
+    domain: &Domain,
+) -> (Option<Collision>, Option<Collision>) {
+    // only Wall edges collide; outflow and periodic ones are open
+    let (left, right) = domain.walls_x();
+    let (top, bottom) = domain.walls_y();
     let mut vert = None;
-    if pos_a.y - scale_a <= BOTTOM_WALL {
+    if top && pos_a.y - scale_a <= 0.0 {
         vert = Some(Collision::Bottom);
-    } else if pos_a.y + scale_a >= TOP_WALL {
+    } else if bottom && pos_a.y + scale_a >= domain.height {
         vert = Some(Collision::Top);
     }
 
     let mut hor = None;
-    if pos_a.x - scale_a <= LEFT_WALL {
+    if left && pos_a.x - scale_a <= 0.0 {
         hor = Some(Collision::Left);
-    } else if pos_a.x + scale_a >= RIGHT_WALL {
+    } else if right && pos_a.x + scale_a >= domain.width {
         hor = Some(Collision::Right);
     }
 
@@ -470,20 +1012,27 @@ fn collides_wall(pos_a: Vec2, scale_a: f32) -> (Option<Collision>, Option<Collis
 /// Without it, every particle pushed past the same wall corner in the same
 /// substep clamps onto the *identical* (x, y) — exactly-coincident pairs,
 /// which the packed solver's degenerate mask can never separate. Sub-µm
-/// magnitude: invisible, but breaks exact ties deterministically.
-pub(crate) fn resolve_wall_collision(c_pos: &mut Vec2, c_opos: &mut Vec2, salt: f32) {
-    let (hor, ver) = collides_wall(*c_pos, BALL_SIZE);
+/// magnitude: invisible, but breaks exact ties deterministically. `radius`
+/// is the particle's own (see [`ShareData::c_radius`]).
+pub(crate) fn resolve_wall_collision(
+    c_pos: &mut Vec2,
+    c_opos: &mut Vec2,
+    radius: f32,
+    salt: f32,
+    domain: &Domain,
+) {
+    let (hor, ver) = collides_wall(*c_pos, radius, domain);
 
     let curr_vel = (*c_pos - *c_opos) * 0.4;
     const EPS: f32 = 0.00001;
     use Collision::*;
     match hor {
         Some(Left) => {
-            c_pos.x = LEFT_WALL + BALL_SIZE + EPS + salt;
+            c_pos.x = radius + EPS + salt;
             c_opos.x = c_pos.x + curr_vel.x;
         }
         Some(Right) => {
-            c_pos.x = RIGHT_WALL - BALL_SIZE - EPS - salt;
+            c_pos.x = domain.width - radius - EPS - salt;
             c_opos.x = c_pos.x + curr_vel.x;
         }
         _ => {}
@@ -491,11 +1040,11 @@ pub(crate) fn resolve_wall_collision(c_pos: &mut Vec2, c_opos: &mut Vec2, salt:
 
     match ver {
         Some(Bottom) => {
-            c_pos.y = BOTTOM_WALL + BALL_SIZE + EPS + salt;
+            c_pos.y = radius + EPS + salt;
             c_opos.y = c_pos.y + curr_vel.y;
         }
         Some(Top) => {
-            c_pos.y = TOP_WALL - BALL_SIZE - EPS - salt;
+            c_pos.y = domain.height - radius - EPS - salt;
             c_opos.y = c_pos.y + curr_vel.y;
         }
         _ => {}
@@ -511,8 +1060,56 @@ pub(crate) fn wall_salt(i: usize) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::{HEIGHT, WIDTH};
     use std::sync::mpsc::channel;
 
+    /// A `cols × rows` block at the 6 px rest spacing with its top-left
+    /// particle at `origin`, all moving at `vel`, ready to step under
+    /// `strategy` in `domain` at the fixed [`PHYS_TIME_STEP`].
+    pub(crate) fn block_scene(
+        strategy: Strategy,
+        domain: Domain,
+        origin: Vec2,
+        (cols, rows): (usize, usize),
+        vel: Vec2,
+    ) -> (Physics, ShareData) {
+        let mut positions = Vec::with_capacity(cols * rows);
+        for gy in 0..rows {
+            for gx in 0..cols {
+                positions.push(origin + Vec2::new(gx as f32, gy as f32) * 2.0 * BALL_SIZE);
+            }
+        }
+        let n = positions.len();
+        let opos = positions
+            .iter()
+            .map(|&p| p - vel * PHYS_TIME_STEP)
+            .collect();
+        let (_tx, rx) = channel();
+        let mut physics = Physics::new(opos, vec![Vec2::ZERO; n], rx, 2000.0);
+        physics.toggle_adaptive_dt();
+        physics.set_strategy(strategy);
+        physics.set_domain(domain);
+        let share = ShareData {
+            c_pos: positions,
+            c_color: vec![0.0; n],
+            ..Default::default()
+        };
+        (physics, share)
+    }
+
+    /// The grid strategies' dam break: a 20×60 column (1200 particles)
+    /// against the left wall of a 240×600 tank, ready to release under
+    /// `strategy`.
+    pub(crate) fn dam_break_scene(strategy: Strategy) -> (Physics, ShareData) {
+        block_scene(
+            strategy,
+            Domain::new(240.0, 600.0),
+            Vec2::new(3.0, 243.0),
+            (20, 60),
+            Vec2::ZERO,
+        )
+    }
+
     /// Two exactly-coincident particles must separate to contact distance —
     /// the pre-stage-20 solver left them glued forever.
     #[test]
@@ -536,4 +1133,835 @@ mod tests {
             "coincident pair still glued: dist = {dist}"
         );
     }
+
+    /// Wet sand: with capillary cohesion on top of the contact friction, a
+    /// collapsing column keeps a much narrower, taller heap than dry grains.
+    #[test]
+    fn cohesion_holds_a_steeper_heap() {
+        let mut positions = Vec::new();
+        for gy in 0..40 {
+            for gx in 0..20 {
+                let x = 690.0 + gx as f32 * 6.0 + (gy % 2) as f32 * 3.0;
+                positions.push(Vec2::new(x, HEIGHT - 4.0 - gy as f32 * 5.3));
+            }
+        }
+        let n = positions.len();
+        let mut heaps = Vec::new();
+        for strength in [0.0, 50.0] {
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_friction(0.6, 0.5, 0.0);
+            physics.set_cohesion(strength, 1.5);
+            let mut share = ShareData {
+                c_pos: positions.clone(),
+                c_color: vec![0.0; n],
+                ..Default::default()
+            };
+            for _ in 0..1500 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            let (lo, hi) = share
+                .c_pos
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(a, b), p| (a.min(p.x), b.max(p.x)));
+            let top = share.c_pos.iter().map(|p| HEIGHT - p.y).fold(0.0, f32::max);
+            heaps.push((hi - lo, top));
+        }
+        let (dry, wet) = (heaps[0], heaps[1]);
+        assert!(
+            wet.0 < 0.6 * dry.0 && wet.1 > dry.1,
+            "heap (width, height) dry {dry:?}, wet {wet:?}"
+        );
+    }
+
+    /// The capillary bridge must stay inside the one-cell stencil: the grid
+    /// gather reproduces the exact pairwise forces with the strongest,
+    /// longest bridge the knob allows (a longer one is clamped).
+    #[test]
+    fn cohesion_stays_exact_in_the_stencil() {
+        // A jittered lattice, so many gaps fall inside the rupture distance.
+        let mut seed = 7u32;
+        let mut jitter = || {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+        };
+        let mut positions = Vec::new();
+        for gy in 0..30 {
+            for gx in 0..30 {
+                let p = Vec2::new(400.0 + gx as f32 * 6.8, 400.0 + gy as f32 * 6.8);
+                positions.push(p + Vec2::new(jitter(), jitter()) * 1.6);
+            }
+        }
+        let n = positions.len();
+        let (_tx, rx) = channel();
+        let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+        let dry = physics.forces_direct(&positions);
+        physics.set_cohesion(100.0, 10.0);
+        let exact = physics.forces_direct(&positions);
+        let grid = physics.forces_grid(&positions);
+        let pulled = exact
+            .iter()
+            .zip(&dry)
+            .filter(|(e, d)| (**e - **d).length() > 1.0);
+        assert!(pulled.count() > n / 2, "the bridge barely acted");
+        for (i, (e, g)) in exact.iter().zip(&grid).enumerate() {
+            assert!(
+                (*e - *g).length() <= 1e-3 * (1.0 + e.length()),
+                "particle {i}: exact {e} vs grid {g}"
+            );
+        }
+    }
+
+    /// Brazil-nut effect: shaking a granular bed lifts a 2:1 intruder from
+    /// the floor while a small tracer in its place stays put. At rest, the
+    /// mixed bed keeps every pair near its own contact distance r_i + r_j.
+    #[test]
+    fn shaking_lifts_a_large_intruder() {
+        let (w, h) = (180.0, 600.0);
+        let mut heights = Vec::new();
+        for big in [BALL_SIZE, 2.0 * BALL_SIZE] {
+            let mut positions = vec![Vec2::new(0.5 * w, h - big - 0.5)];
+            for gy in 0..40 {
+                for gx in 0..29 {
+                    let x = 4.0 + gx as f32 * 6.0 + (gy % 2) as f32 * 3.0;
+                    let p = Vec2::new(x, h - 3.5 - gy as f32 * 5.3);
+                    if (p - positions[0]).length() > big + 3.5 {
+                        positions.push(p);
+                    }
+                }
+            }
+            let n = positions.len();
+            let mut c_radius = vec![BALL_SIZE; n];
+            c_radius[0] = big;
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_domain(Domain::new(w, h));
+            physics.set_friction(0.5, 0.4, 0.0);
+            let mut share = ShareData {
+                c_pos: positions,
+                c_color: vec![0.0; n],
+                c_radius,
+                ..Default::default()
+            };
+            for _ in 0..1000 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            for a in 0..n {
+                for b in a + 1..n {
+                    let d = (share.c_pos[a] - share.c_pos[b]).length();
+                    let contact = share.c_radius[a] + share.c_radius[b];
+                    assert!(d > 0.6 * contact, "pair {a}-{b} at {d} of {contact}");
+                }
+            }
+            // 6 Hz vertical shaking at 3.5 g, in the box frame. The intruder
+            // climbs from ~2 s in; over the next ~4 s it averages ~35 px up
+            // while the tracer rattles a few px off the floor.
+            let mut sum = 0.0;
+            for step in 0..4000 {
+                let t = step as f32 * PHYS_TIME_STEP;
+                let lift = 3.5 * (std::f32::consts::TAU * 6.0 * t).sin();
+                physics.set_gravity(GRAVITY * (1.0 - lift));
+                physics.step(PHYS_TIME_STEP, &mut share);
+                if step >= 2000 {
+                    sum += h - share.c_pos[share.index_of(0).unwrap()].y;
+                }
+            }
+            heights.push(sum / 2000.0);
+        }
+        assert!(
+            heights[0] < 15.0 && heights[1] > 25.0,
+            "mean heights of the tracer / intruder: {heights:?}"
+        );
+    }
+
+    /// A collapsing granular column runs out across the whole floor when the
+    /// contacts are frictionless, but holds a heap with Coulomb friction — in
+    /// both the serial Gauss-Seidel and the packed Jacobi engines.
+    #[test]
+    fn contact_friction_holds_a_granular_heap() {
+        let mut positions = Vec::new();
+        for gy in 0..40 {
+            for gx in 0..20 {
+                let x = 690.0 + gx as f32 * 6.0 + (gy % 2) as f32 * 3.0;
+                positions.push(Vec2::new(x, HEIGHT - 4.0 - gy as f32 * 5.3));
+            }
+        }
+        let n = positions.len();
+        for par_min in [usize::MAX, 0] {
+            let mut widths = Vec::new();
+            for mu in [0.0, 1.0] {
+                let (_tx, rx) = channel();
+                let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+                physics.toggle_adaptive_dt();
+                physics.set_par_min_particles(par_min);
+                physics.set_friction(mu, 0.8 * mu, 0.0);
+                let mut share = ShareData {
+                    c_pos: positions.clone(),
+                    c_color: vec![0.0; n],
+                    ..Default::default()
+                };
+                for _ in 0..1500 {
+                    physics.step(PHYS_TIME_STEP, &mut share);
+                }
+                let (lo, hi) = share
+                    .c_pos
+                    .iter()
+                    .fold((f32::MAX, f32::MIN), |(a, b), p| (a.min(p.x), b.max(p.x)));
+                widths.push(hi - lo);
+            }
+            assert!(
+                widths[0] > 1200.0 && widths[1] < 900.0,
+                "par_min {par_min}: heap widths without / with friction {widths:?}"
+            );
+        }
+    }
+
+    /// Rolling resistance must let a dry-sand heap stand at a sand-like
+    /// angle of repose in either engine. A 60° triangle of grains slumps to
+    /// its repose angle; the flank is read off the column-height profile
+    /// between a quarter and three quarters of the peak. Without rolling
+    /// resistance the Jacobi engine lets it run out to ~25°.
+    #[test]
+    fn rolling_resistance_holds_a_sand_slope() {
+        let base = 40;
+        let mut positions = Vec::new();
+        for gy in 0..base {
+            for gx in 0..base - gy {
+                let x = 0.5 * WIDTH + (gx as f32 - 0.5 * base as f32) * 6.0 + gy as f32 * 3.0;
+                positions.push(Vec2::new(x, HEIGHT - 3.0 - gy as f32 * 5.196));
+            }
+        }
+        let n = positions.len();
+        let repose = |par_min: usize, rolling: f32| {
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_par_min_particles(par_min);
+            physics.set_solver_iterations(8);
+            physics.set_friction(0.6, 0.5, rolling);
+            let mut share = ShareData {
+                c_pos: positions.clone(),
+                c_color: vec![0.0; n],
+                ..Default::default()
+            };
+            for _ in 0..720 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            let s = 2.0 * BALL_SIZE;
+            let mut heights = vec![0.0f32; (WIDTH / s) as usize + 1];
+            for p in &share.c_pos {
+                let c = (p.x / s) as usize;
+                heights[c] = heights[c].max(HEIGHT - p.y);
+            }
+            let (peak, &top) = heights
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .unwrap();
+            let run = |frac: f32, dir: isize| {
+                let mut c = peak as isize;
+                while heights[c as usize] >= frac * top {
+                    c += dir;
+                }
+                c
+            };
+            let run = |dir: isize| (run(0.25, dir) - run(0.75, dir)).abs() as f32 * s;
+            let flank = |run: f32| (0.5 * top / run).atan().to_degrees();
+            0.5 * (flank(run(-1)) + flank(run(1)))
+        };
+        let serial = repose(usize::MAX, 1.0);
+        let (free, packed) = (repose(0, 0.0), repose(0, 1.0));
+        assert!(
+            serial > 30.0 && packed > 30.0 && packed > free + 10.0,
+            "sand slope serial {serial:.1}°, packed {packed:.1}° ({free:.1}° without rolling)"
+        );
+    }
+
+    /// A blob dropped onto a shelf must never end up inside it under any
+    /// strategy, and a good part of it must come to rest on top (DFSPH
+    /// splashes some over the ends, so not all of it).
+    #[test]
+    fn shelf_holds_under_every_strategy() {
+        let shelf = Shape::Capsule {
+            a: Vec2::new(400.0, 700.0),
+            b: Vec2::new(1100.0, 700.0),
+            radius: 12.0,
+        };
+        for &strategy in Strategy::all() {
+            let (mut physics, mut share) = block_scene(
+                strategy,
+                Domain::default(),
+                Vec2::new(715.0, 550.0),
+                (12, 12),
+                Vec2::ZERO,
+            );
+            let n = share.c_pos.len();
+            physics.add_obstacle(Obstacle::new(shelf.clone()));
+            for step in 0..600 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+                let inside = share
+                    .c_pos
+                    .iter()
+                    .filter(|&&p| shelf.distance(p).0 < 0.5 * BALL_SIZE)
+                    .count();
+                assert_eq!(inside, 0, "{strategy:?}: inside the shelf at step {step}");
+            }
+            let held = share.c_pos.iter().filter(|p| p.y < 700.0).count();
+            assert!(held > n / 4, "{strategy:?}: only {held}/{n} held up");
+        }
+    }
+
+    /// A blob dropped into a round tank (a container SDF, not the box) stays
+    /// inside it under every strategy and piles up at the bottom of the bowl.
+    #[test]
+    fn round_tank_contains_every_strategy() {
+        let tank = Sdf::Circle {
+            center: Vec2::new(750.0, 600.0),
+            radius: 250.0,
+        };
+        for &strategy in Strategy::all() {
+            let (mut physics, mut share) = block_scene(
+                strategy,
+                Domain::default(),
+                Vec2::new(715.0, 450.0),
+                (12, 12),
+                Vec2::ZERO,
+            );
+            let n = share.c_pos.len();
+            physics.set_container(Some(Container::new(tank.clone())));
+            for step in 0..600 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+                let outside = share
+                    .c_pos
+                    .iter()
+                    .filter(|&&p| tank.eval(p).0 > 0.0)
+                    .count();
+                assert_eq!(outside, 0, "{strategy:?}: outside the tank at step {step}");
+            }
+            let low = share.c_pos.iter().filter(|p| p.y > 700.0).count();
+            assert!(low > n / 2, "{strategy:?}: only {low}/{n} reached the bowl");
+        }
+    }
+
+    /// A tall, narrow tank whose width is not a multiple of the grid cell:
+    /// every strategy keeps the fluid inside it and lands it on *its* floor,
+    /// below where the default box would have stopped it.
+    #[test]
+    fn narrow_domain_contains_every_strategy() {
+        let domain = Domain::new(310.0, 1400.0);
+        for &strategy in Strategy::all() {
+            let (mut physics, mut share) = block_scene(
+                strategy,
+                domain,
+                Vec2::new(100.0, 200.0),
+                (12, 20),
+                Vec2::ZERO,
+            );
+            let n = share.c_pos.len();
+            let outside = |p: &&Vec2| {
+                !(p.x >= 0.0 && p.x <= domain.width && p.y >= 0.0 && p.y <= domain.height)
+            };
+            for step in 0..600 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+                assert_eq!(
+                    share.c_pos.iter().filter(outside).count(),
+                    0,
+                    "{strategy:?}: outside the domain at step {step}: {:?}",
+                    share.c_pos.iter().find(outside)
+                );
+            }
+            let low = share.c_pos.iter().filter(|p| p.y > 1300.0).count();
+            assert!(
+                low > n / 2,
+                "{strategy:?}: only {low}/{n} reached the floor"
+            );
+        }
+    }
+
+    /// A layer driven sideways along a periodic channel must stream through
+    /// the seam under every strategy: particles re-enter from the left, stay in
+    /// the box, and nothing piles up against a wall that isn't there.
+    #[test]
+    fn periodic_channel_streams_through_the_seam() {
+        let domain = Domain {
+            left: Edge::Periodic,
+            right: Edge::Periodic,
+            ..Domain::new(300.0, 300.0)
+        };
+        for &strategy in Strategy::all() {
+            let (mut physics, mut share) =
+                block_scene(strategy, domain, Vec2::new(3.0, 267.0), (50, 6), Vec2::ZERO);
+            let n = share.c_pos.len();
+            physics.set_gravity(Vec2::new(3.0, 9.8));
+            let mut crossings = 0;
+            for step in 0..600 {
+                let before = share.c_pos.clone();
+                physics.step(PHYS_TIME_STEP, &mut share);
+                assert_eq!(share.c_pos.len(), n, "{strategy:?}: lost particles");
+                let outside = share
+                    .c_pos
+                    .iter()
+                    .filter(|p| !(p.x >= 0.0 && p.x < 300.0 && p.y >= 0.0 && p.y <= 300.0))
+                    .count();
+                assert_eq!(
+                    outside,
+                    0,
+                    "{strategy:?}: outside the domain at step {step}: {:?}",
+                    share
+                        .c_pos
+                        .iter()
+                        .find(|p| !(p.x >= 0.0 && p.x < 300.0 && p.y >= 0.0 && p.y <= 300.0))
+                );
+                crossings += before
+                    .iter()
+                    .zip(&share.c_pos)
+                    .filter(|(a, b)| (b.x - a.x).abs() > 150.0)
+                    .count();
+            }
+            assert!(
+                crossings > n / 4,
+                "{strategy:?}: only {crossings} seam crossings"
+            );
+            let right = share.c_pos.iter().filter(|p| p.x >= 150.0).count();
+            assert!(
+                right > n * 3 / 10 && right < n * 7 / 10,
+                "{strategy:?}: {right}/{n} in the right half"
+            );
+            let high = share.c_pos.iter().filter(|p| p.y < 200.0).count();
+            assert!(high < n / 20, "{strategy:?}: {high}/{n} piled up");
+        }
+    }
+
+    /// A pool tilted toward an outflow edge must pour out through it under
+    /// every strategy: the escaped particles are deleted and counted against
+    /// that edge, and the remaining walls still hold.
+    #[test]
+    fn outflow_edge_drains_a_tilted_pool() {
+        let domain = Domain {
+            right: Edge::Outflow,
+            ..Domain::new(400.0, 300.0)
+        };
+        for &strategy in Strategy::all() {
+            let (mut physics, mut share) = block_scene(
+                strategy,
+                domain,
+                Vec2::new(100.0, 255.0),
+                (20, 8),
+                Vec2::ZERO,
+            );
+            let n = share.c_pos.len();
+            physics.set_gravity(Vec2::new(4.0, 9.8));
+            let mut flux = 0.0;
+            for step in 0..600 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+                flux += share.perf_stats.outflow_flux * PHYS_TIME_STEP;
+                let outside = share
+                    .c_pos
+                    .iter()
+                    .filter(|p| !(p.x >= 0.0 && p.x <= 400.0 && p.y >= 0.0 && p.y <= 300.0))
+                    .count();
+                assert_eq!(
+                    outside, 0,
+                    "{strategy:?}: outside the domain at step {step}"
+                );
+            }
+            let out = physics.outflow();
+            let gone = (n - share.c_pos.len()) as u64;
+            assert_eq!(
+                out.right, gone,
+                "{strategy:?}: tally {out:?} vs {gone} removed"
+            );
+            assert_eq!(out.total(), out.right, "{strategy:?}: {out:?}");
+            assert_eq!(flux.round() as u64, gone, "{strategy:?}: flux {flux}");
+            assert!(
+                gone > n as u64 * 3 / 4,
+                "{strategy:?}: only {gone}/{n} poured out"
+            );
+        }
+    }
+
+    /// A piston moving into a resting pool must push it along under every
+    /// strategy (momentum goes into the fluid, nothing tunnels through).
+    #[test]
+    fn piston_pushes_fluid_under_every_strategy() {
+        for &strategy in Strategy::all() {
+            let (mut physics, mut share) = block_scene(
+                strategy,
+                Domain::default(),
+                Vec2::new(60.0, HEIGHT - 59.0),
+                (20, 10),
+                Vec2::ZERO,
+            );
+            let n = share.c_pos.len();
+            // Quarter period = 1 s: the paddle sweeps 400 px to the right.
+            physics.add_obstacle(Obstacle {
+                motion: Motion::Oscillate {
+                    amplitude: Vec2::new(400.0, 0.0),
+                    period: 4.0,
+                },
+                ..Obstacle::new(Shape::Capsule {
+                    a: Vec2::new(40.0, HEIGHT - 150.0),
+                    b: Vec2::new(40.0, HEIGHT + 20.0),
+                    radius: 8.0,
+                })
+            });
+            for _ in 0..480 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            let paddle = 40.0 + 400.0;
+            let behind = share.c_pos.iter().filter(|p| p.x < paddle).count();
+            assert!(behind < n / 20, "{strategy:?}: {behind}/{n} left behind");
+        }
+    }
+
+    /// A light disc floats on a pool and a heavy one sinks into it, under
+    /// every strategy: the fluid holds the body up (buoyancy via the
+    /// recorded contact impulses) and gets displaced by it.
+    #[test]
+    fn bodies_float_and_sink_under_every_strategy() {
+        let (x0, x1) = (600.0, 840.0);
+        for &strategy in Strategy::all() {
+            for (density, floats) in [(0.5, true), (3.0, false)] {
+                let (mut physics, mut share) = block_scene(
+                    strategy,
+                    Domain::default(),
+                    Vec2::new(x0 + 5.0, HEIGHT - 179.0),
+                    (39, 30),
+                    Vec2::ZERO,
+                );
+                physics.set_container(Some(Container::new(Sdf::Rect {
+                    min: Vec2::new(x0, -100.0),
+                    max: Vec2::new(x1, HEIGHT + 100.0),
+                })));
+                physics.add_body(RigidBody::disc(
+                    Vec2::new(720.0, HEIGHT - 300.0),
+                    30.0,
+                    density,
+                ));
+                for _ in 0..960 {
+                    physics.step(PHYS_TIME_STEP, &mut share);
+                }
+                let y = physics.bodies()[0].position.y;
+                let surface = share.c_pos.iter().map(|p| p.y).fold(f32::MAX, f32::min);
+                if floats {
+                    assert!(
+                        (y - surface).abs() < 30.0,
+                        "{strategy:?}: light body at {y}, surface at {surface}"
+                    );
+                } else {
+                    // The stiffer solvers let it settle into the pool rather
+                    // than reach the floor within two seconds; either way it
+                    // ends up well below the surface. Goo's springs are
+                    // elastic below their yield strain, so it holds the disc
+                    // in a dent (~30 px) instead of letting it sink.
+                    let depth = match strategy {
+                        Strategy::Viscoelastic => 20.0,
+                        _ => 40.0,
+                    };
+                    assert!(
+                        y > surface + depth,
+                        "{strategy:?}: heavy body at {y}, surface at {surface}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// A sideways jet fills up to the particle cap, then a drain over the
+    /// floor takes most of it away again — with every strategy's
+    /// per-particle state following the count down and the survivors still
+    /// simulated sanely.
+    #[test]
+    fn emitter_fills_to_cap_and_drain_empties_under_every_strategy() {
+        const CAP: usize = 400;
+        for &strategy in Strategy::all() {
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(Vec::new(), Vec::new(), rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_strategy(strategy);
+            physics.set_max_particles(Some(CAP));
+            let nozzle = Vec2::new(300.0, 600.0);
+            physics.add_emitter(Emitter {
+                spread: 0.1,
+                ..Emitter::new(nozzle, Vec2::new(300.0, 0.0), 2400.0)
+            });
+            let mut share = ShareData::default();
+            for _ in 0..120 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            assert_eq!(share.c_pos.len(), CAP, "{strategy:?}: capped count");
+            let mean_x = share.c_pos.iter().map(|p| p.x).sum::<f32>() / CAP as f32;
+            assert!(mean_x > nozzle.x + 20.0, "{strategy:?}: jet went {mean_x}");
+
+            physics.emitters_mut()[0].active = false;
+            physics.add_drain(Drain::new(Sdf::Rect {
+                min: Vec2::new(-100.0, HEIGHT - 150.0),
+                max: Vec2::new(WIDTH + 100.0, HEIGHT + 100.0),
+            }));
+            for _ in 0..480 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+                assert_eq!(share.c_color.len(), share.c_pos.len());
+            }
+            let left = share.c_pos.len();
+            assert!(left < CAP / 4, "{strategy:?}: {left} left after draining");
+            for p in &share.c_pos {
+                assert!(p.is_finite() && p.y < HEIGHT - 100.0, "{strategy:?}: {p}");
+            }
+        }
+    }
+
+    /// Two blobs far apart, the right one in flight: deleting the left blob
+    /// mid-run must leave the right one's trajectory exactly as if the left
+    /// had never been touched, i.e. every strategy's velocity / deformation
+    /// state was compacted along with the positions rather than shifted onto
+    /// the wrong particles.
+    #[test]
+    fn removal_keeps_survivor_state_under_every_strategy() {
+        let mut positions = Vec::new();
+        for gy in 0..12 {
+            for gx in 0..12 {
+                let offset = Vec2::new(gx as f32, gy as f32) * 6.0;
+                positions.push(Vec2::new(200.0, HEIGHT - 72.0) + offset);
+                positions.push(Vec2::new(1000.0, 600.0) + offset);
+            }
+        }
+        let n = positions.len();
+        // Landing on the floor on the left (so it gets compressed and
+        // sheared), thrown up and sideways on the right.
+        let opos: Vec<Vec2> = positions
+            .iter()
+            .map(|&p| {
+                if p.x > WIDTH / 2.0 {
+                    p - Vec2::new(-150.0, -300.0) * PHYS_TIME_STEP
+                } else {
+                    p
+                }
+            })
+            .collect();
+        let left = |_: usize, p: Vec2| p.x < WIDTH / 2.0;
+        for &strategy in Strategy::all() {
+            let run = |cut: bool| {
+                let (_tx, rx) = channel();
+                let mut physics = Physics::new(opos.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+                physics.toggle_adaptive_dt();
+                physics.set_strategy(strategy);
+                let mut share = ShareData {
+                    c_pos: positions.clone(),
+                    c_color: vec![0.0; n],
+                    ..Default::default()
+                };
+                for step in 0..80 {
+                    if step == 40 && cut {
+                        assert_eq!(physics.remove_particles(&mut share, left), n / 2);
+                    }
+                    physics.step(PHYS_TIME_STEP, &mut share);
+                }
+                let mut right: Vec<Vec2> = share
+                    .c_pos
+                    .into_iter()
+                    .filter(|p| p.x > WIDTH / 2.0)
+                    .collect();
+                right.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+                right
+            };
+            let (kept, cut) = (run(false), run(true));
+            assert_eq!(kept.len(), cut.len(), "{strategy:?}");
+            for (a, b) in kept.iter().zip(&cut) {
+                assert!((*a - *b).length() < 1e-2, "{strategy:?}: {a} vs {b}");
+            }
+        }
+    }
+
+    /// IDs follow their particles through the granular SFC reorder (which
+    /// permutes every array) and through a removal: looked up by ID, a
+    /// particle of a walled-in pool or of a droplet falling into it moves
+    /// less than half the lattice spacing per step, i.e. never jumps onto
+    /// another particle's spot.
+    #[test]
+    fn ids_follow_particles_through_reorder_and_removal() {
+        let mut positions = Vec::new();
+        for gy in 0..16 {
+            for gx in 0..40 {
+                let p = Vec2::new(300.0 + gx as f32 * 6.0, HEIGHT - 5.0 - gy as f32 * 6.0);
+                positions.push(p);
+            }
+        }
+        let pool = positions.len() as u32;
+        for gy in 0..5 {
+            for gx in 0..5 {
+                positions.push(Vec2::new(400.0 + gx as f32 * 6.0, 300.0 + gy as f32 * 6.0));
+            }
+        }
+        let n = positions.len();
+        for &strategy in Strategy::all() {
+            let (_tx, rx) = channel();
+            let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+            physics.toggle_adaptive_dt();
+            physics.set_strategy(strategy);
+            physics.set_container(Some(Container::new(Sdf::Rect {
+                min: Vec2::new(297.0, -100.0),
+                max: Vec2::new(537.0, HEIGHT + 100.0),
+            })));
+            let mut share = ShareData {
+                c_pos: positions.clone(),
+                c_color: vec![0.0; n],
+                ..Default::default()
+            };
+            physics.step(PHYS_TIME_STEP, &mut share);
+            let mut ids = share.c_id.clone();
+            ids.sort_unstable();
+            assert!(ids.into_iter().eq(0..n as u32), "{strategy:?}");
+            for _ in 0..40 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            for step in 40..160 {
+                if step == 100 {
+                    let ids = share.c_id.clone();
+                    // Take the droplet out while it is still in the air.
+                    let removed = physics.remove_particles(&mut share, |i, _| ids[i] >= pool);
+                    assert_eq!(removed, 25);
+                    assert!(share.c_id.iter().all(|&id| id < pool));
+                }
+                let before: Vec<(u32, Vec2)> = share
+                    .c_id
+                    .iter()
+                    .copied()
+                    .zip(share.c_pos.iter().copied())
+                    .collect();
+                physics.step(PHYS_TIME_STEP, &mut share);
+                let index = share.id_to_index();
+                for (id, p) in before {
+                    let i = index[id as usize] as usize;
+                    assert_eq!(share.index_of(id), Some(i));
+                    let moved = (share.c_pos[i] - p).length();
+                    assert!(moved < 3.0, "{strategy:?} step {step}: #{id} moved {moved}");
+                }
+            }
+        }
+    }
+
+    /// A heavy layer resting on a light one is unstable (Rayleigh–Taylor):
+    /// under every strategy that weighs particles by their phase density the
+    /// heavy phase must end up underneath, while the same stack with one
+    /// shared density stays put. Viscoelastic, FLIP and LBM ignore phase
+    /// density and sit out.
+    #[test]
+    fn heavy_phase_sinks_through_light_one() {
+        for strategy in [
+            Strategy::Pbf,
+            Strategy::Dfsph,
+            Strategy::Iisph,
+            Strategy::Wcsph,
+            Strategy::Mlsmpm,
+        ] {
+            for heavy in [1.0, 3.0] {
+                let mut positions = Vec::new();
+                let mut phase = Vec::new();
+                for gy in 0..30 {
+                    for gx in 0..24 {
+                        // A small kink in the interface seeds the overturn.
+                        let wobble = if gy == 14 && gx < 12 { 2.0 } else { 0.0 };
+                        positions.push(Vec2::new(
+                            6.0 + gx as f32 * 6.0,
+                            HEIGHT - 5.0 - gy as f32 * 6.0 - wobble,
+                        ));
+                        phase.push(u8::from(gy >= 15));
+                    }
+                }
+                let n = positions.len();
+                let (_tx, rx) = channel();
+                let mut physics = Physics::new(positions.clone(), vec![Vec2::ZERO; n], rx, 2000.0);
+                physics.toggle_adaptive_dt();
+                physics.set_strategy(strategy);
+                physics.set_domain(Domain::new(156.0, HEIGHT));
+                physics.set_phases(vec![
+                    Phase::default(),
+                    Phase {
+                        density: heavy,
+                        ..Phase::default()
+                    },
+                ]);
+                let mut share = ShareData {
+                    c_pos: positions,
+                    c_color: vec![0.0; n],
+                    c_phase: phase,
+                    ..Default::default()
+                };
+                for _ in 0..2000 {
+                    physics.step(PHYS_TIME_STEP, &mut share);
+                }
+                let mean_y = |k: u8| {
+                    let ys: Vec<f32> = (0..n)
+                        .filter(|&i| share.c_phase[i] == k)
+                        .map(|i| share.c_pos[i].y)
+                        .collect();
+                    ys.iter().sum::<f32>() / ys.len() as f32
+                };
+                let (light, dense) = (mean_y(0), mean_y(1));
+                assert!(
+                    share.c_pos.iter().all(|p| p.is_finite()),
+                    "{strategy:?}: non-finite positions"
+                );
+                if heavy > 1.0 {
+                    assert!(
+                        dense > light + 10.0,
+                        "{strategy:?}: heavy phase at y={dense}, light at y={light}"
+                    );
+                } else {
+                    assert!(
+                        dense < light - 40.0,
+                        "{strategy:?}: equal phases mixed: {dense} vs {light}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// A thin weightless filament: the pressure solve alone leaves it
+    /// straight (or lets it smear apart); Akinci tension draws it up into
+    /// one drop.
+    #[test]
+    fn surface_tension_pulls_a_filament_into_a_drop() {
+        let width = |strategy: Strategy, gamma: f32| {
+            let (mut physics, mut share) = block_scene(
+                strategy,
+                Domain::default(),
+                Vec2::new(150.0, 200.0),
+                (50, 3),
+                Vec2::ZERO,
+            );
+            physics.set_gravity(Vec2::ZERO);
+            physics.set_pbf_params(PbfParams {
+                surface_tension: gamma,
+                ..Default::default()
+            });
+            physics.set_dfsph_params(DfsphParams {
+                surface_tension: gamma,
+                ..Default::default()
+            });
+            for _ in 0..1500 {
+                physics.step(PHYS_TIME_STEP, &mut share);
+            }
+            assert!(
+                share.c_pos.iter().all(|p| p.is_finite()),
+                "{strategy:?} γ={gamma}: non-finite positions"
+            );
+            let xs = share.c_pos.iter().map(|p| p.x);
+            xs.clone().fold(f32::MIN, f32::max) - xs.fold(f32::MAX, f32::min)
+        };
+        for strategy in [Strategy::Pbf, Strategy::Dfsph] {
+            let loose = width(strategy, 0.0);
+            let drawn = width(strategy, 2000.0);
+            assert!(
+                loose > 250.0,
+                "{strategy:?}: filament contracted untensioned ({loose})"
+            );
+            assert!(
+                drawn < 120.0,
+                "{strategy:?}: tension left the filament {drawn} wide"
+            );
+        }
+    }
 }
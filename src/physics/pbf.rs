@@ -18,16 +18,32 @@
 //!        constraint C_i = ρ_i/ρ0 − 1
 //!        λ_i = −C_i / (Σ_k ‖∇_k C_i‖² + ε)
 //!        Δx_i = (1/ρ0) Σ_j (λ_i + λ_j + s_corr) ∇W_spiky(x_i−x_j, h)
-//!      s_corr is the artificial-pressure cohesion term (paper §4); walls are
-//!      enforced by clamping after each iteration.
-//!   4. velocity v_i = (x_i − x_prev)/Δt.
-//!   5. XSPH viscosity + vorticity confinement (paper §§5–6).
+//!      s_corr is the artificial-pressure cohesion term (paper §4); walls and
+//!      obstacles are enforced by projection after each iteration.
+//!   4. velocity v_i = (x_i − x_prev)/Δt, then the obstacle contact response
+//!      (restitution/friction) for particles left touching one.
+//!   5. XSPH viscosity + vorticity confinement (paper §§5–6), and the
+//!      optional Akinci surface tension ([`PbfParams::surface_tension`]) and
+//!      wall adhesion ([`Obstacle::adhesion`](super::Obstacle::adhesion)).
+//!
+//! Multi-phase: with several [`Phase`]s the constraint stays on the number
+//! density (every phase rests at ρ0), and each particle's correction is
+//! weighted by its inverse mass w_i = 1/m_i as in mass-weighted PBD (Macklin
+//! et al. 2014, "Unified Particle Physics"):
+//!   λ_i = −C_i / (Σ_k w_k ‖∇_k C_i‖² + ε)
+//!   Δx_i = (w_i/ρ0) Σ_j (λ_i + λ_j + s_corr) ∇W_ij
+//! A light particle yields more of a shared correction than a heavy one, so
+//! the heavy phase sinks.
 
 use glam::Vec2;
 use rayon::prelude::*;
 
-use super::{clamp_wall, FluidSolver, ShareData, PHYS_TIME_STEP};
-use crate::constants::{BALL_SIZE, HEIGHT, WIDTH};
+use super::phase::inv_masses;
+use super::sph::{grid_axes, CellBlock, Neighbors, SurfaceTension, WallAdhesion};
+use super::{
+    clamp_wall, retain_flagged, Boundaries, Domain, FluidSolver, Phase, ShareData, PHYS_TIME_STEP,
+};
+use crate::constants::BALL_SIZE;
 
 // Smoothing radius: ~2.5 rest spacings, so each particle sees ~20 neighbors.
 const PBF_H: f32 = 15.0;
@@ -70,6 +86,12 @@ pub struct PbfParams {
     /// is generous enough to keep normal cohesion (equilibrium λ is O(10s)) but
     /// bounds the pathological blow-up with only a handful of particles.
     pub lambda_max: f32,
+    /// Akinci surface-tension strength γ, px/s² (0 = off; see
+    /// `sph::SurfaceTension`). s_corr alone only keeps the surface from
+    /// tearing; this pulls droplets round and beads up thin jets. A few
+    /// hundred is a visible effect against this engine's gravity (≈4700);
+    /// ~2000 draws a weightless filament into one drop.
+    pub surface_tension: f32,
 }
 
 impl Default for PbfParams {
@@ -106,6 +128,7 @@ impl Default for PbfParams {
             vorticity: 0.0,
             max_corr: 0.12 * PBF_H,
             lambda_max: 30.0,
+            surface_tension: 0.0,
         }
     }
 }
@@ -142,11 +165,13 @@ fn grad_spiky(d: Vec2) -> Vec2 {
 }
 
 /// Uniform grid at cell size h for PBF neighbor search, sized at runtime from
-/// the box. Same counting-sort CSR layout as the granular `CsrGrid`, but its
-/// own type so the two models don't fight over the compile-time granular cell
-/// size.
+/// the domain (and rebuilt when it changes). Same counting-sort CSR layout as
+/// the granular `CsrGrid`, but its own type so the two models don't fight
+/// over the granular cell size.
 #[derive(Default)]
 struct PbfGrid {
+    domain: Domain,
+    cell: Vec2,
     w: usize,
     h: usize,
     cell_start: Vec<u32>, // w*h + 1 offsets
@@ -156,10 +181,11 @@ struct PbfGrid {
 }
 
 impl PbfGrid {
-    fn new() -> Self {
-        let w = (WIDTH / PBF_H).ceil() as usize + 1;
-        let h = (HEIGHT / PBF_H).ceil() as usize + 1;
+    fn new(domain: &Domain) -> Self {
+        let ((w, cw), (h, ch)) = grid_axes(domain, PBF_H);
         Self {
+            domain: *domain,
+            cell: Vec2::new(cw, ch),
             w,
             h,
             cell_start: vec![0; w * h + 1],
@@ -171,8 +197,8 @@ impl PbfGrid {
 
     #[inline(always)]
     fn coord(&self, p: Vec2) -> (usize, usize) {
-        let x = ((p.x / PBF_H) as isize).clamp(0, self.w as isize - 1) as usize;
-        let y = ((p.y / PBF_H) as isize).clamp(0, self.h as isize - 1) as usize;
+        let x = ((p.x / self.cell.x) as isize).clamp(0, self.w as isize - 1) as usize;
+        let y = ((p.y / self.cell.y) as isize).clamp(0, self.h as isize - 1) as usize;
         (x, y)
     }
 
@@ -206,23 +232,27 @@ impl PbfGrid {
         }
     }
 
-    /// Invoke `f(j)` for every particle in the 3×3 cell block around `pos`
-    /// (its cell contains every partner within h).
+    /// Invoke `f(j, o)` for every particle in the 3×3 cell block around `pos`
+    /// (its cell contains every partner within h); `x_j + o` is the image of
+    /// j nearest `pos` (`o` is zero except across a periodic seam).
     #[inline(always)]
-    fn for_neighbors(&self, pos: Vec2, mut f: impl FnMut(usize)) {
+    fn for_neighbors(&self, pos: Vec2, f: impl FnMut(usize, Vec2)) {
         let (cx, cy) = self.coord(pos);
-        let y0 = cy.saturating_sub(1);
-        let y1 = (cy + 1).min(self.h - 1);
-        let x0 = cx.saturating_sub(1);
-        let x1 = (cx + 1).min(self.w - 1);
-        for yy in y0..=y1 {
-            let base = yy * self.w;
-            let s = self.cell_start[base + x0] as usize;
-            let e = self.cell_start[base + x1 + 1] as usize;
-            for &j in &self.indices[s..e] {
-                f(j as usize);
-            }
+        CellBlock {
+            domain: &self.domain,
+            w: self.w,
+            h: self.h,
+            cell_start: &self.cell_start,
+            indices: &self.indices,
         }
+        .for_neighbors(cx, cy, f);
+    }
+}
+
+impl Neighbors for PbfGrid {
+    #[inline(always)]
+    fn for_neighbors(&self, pos: Vec2, f: impl FnMut(usize, Vec2)) {
+        PbfGrid::for_neighbors(self, pos, f)
     }
 }
 
@@ -239,13 +269,17 @@ pub struct Pbf {
     dp: Vec<Vec2>,       // Δx_i for the current iteration
     vscratch: Vec<Vec2>, // velocity double-buffer for XSPH / vorticity
     curl: Vec<f32>,      // per-particle scalar vorticity ω_i
+    phases: Vec<Phase>,
+    inv_mass: Vec<f32>, // w_i = 1/m_i from the particle's phase
+    tension: SurfaceTension,
+    adhesion: WallAdhesion,
 }
 
 impl Pbf {
     pub fn new() -> Self {
         let params = PbfParams::default();
         Self {
-            grid: PbfGrid::new(),
+            grid: PbfGrid::new(&Domain::default()),
             rest_density: rest_density(),
             scorr_denom: w_poly6(params.scorr_dq * params.scorr_dq),
             params,
@@ -255,6 +289,10 @@ impl Pbf {
             dp: Vec::new(),
             vscratch: Vec::new(),
             curl: Vec::new(),
+            phases: Vec::new(),
+            inv_mass: Vec::new(),
+            tension: SurfaceTension::new(PBF_H, PBF_REST_SPACING, true),
+            adhesion: WallAdhesion::new(PBF_H, PBF_REST_SPACING),
         }
     }
 
@@ -263,10 +301,14 @@ impl Pbf {
         self.scorr_denom = w_poly6(params.scorr_dq * params.scorr_dq);
     }
 
-    fn ensure_sized(&mut self, n: usize) {
-        if self.vel.len() != n {
-            self.vel.resize(n, Vec2::ZERO);
-        }
+    /// Size the per-particle arrays for `x`. Newcomers (emitted, fired, or
+    /// everyone after a strategy switch) take their velocity from the shared
+    /// Verlet state `c_opos`; the rest is per-substep scratch.
+    fn ensure_sized(&mut self, x: &[Vec2], c_opos: &[Vec2], dt: f32) {
+        let n = x.len();
+        let seen = self.vel.len().min(n);
+        self.vel.truncate(n);
+        self.vel.extend((seen..n).map(|i| (x[i] - c_opos[i]) / dt));
         self.prev.resize(n, Vec2::ZERO);
         self.lambda.resize(n, 0.0);
         self.dp.resize(n, Vec2::ZERO);
@@ -275,8 +317,9 @@ impl Pbf {
     }
 
     /// One Jacobi iteration: density → λ (parallel gather), then Δx from the
-    /// λ's (parallel gather), then apply with a clamp and wall projection.
-    fn solve_iteration(&mut self, x: &mut [Vec2]) {
+    /// λ's (parallel gather), then apply with a clamp and wall/obstacle
+    /// projection.
+    fn solve_iteration(&mut self, x: &mut [Vec2], bounds: &mut Boundaries) {
         let n = x.len();
         let rho0 = self.rest_density;
         let inv_rho0 = 1.0 / rho0;
@@ -287,27 +330,30 @@ impl Pbf {
         let lambda_max = self.params.lambda_max;
 
         // Pass A: density and λ. ∇_i C_i = (1/ρ0) Σ_j ∇W_ij; the constraint
-        // gradient sum is |Σ_j ∇W|² (the k=i term) plus Σ_j|∇W|² (k=j terms),
-        // all scaled by 1/ρ0². Read-only over the grid ⇒ trivially parallel.
+        // gradient sum is w_i|Σ_j ∇W|² (the k=i term) plus Σ_j w_j|∇W|² (k=j
+        // terms), all scaled by 1/ρ0². Read-only over the grid ⇒ trivially
+        // parallel.
         {
             let grid = &self.grid;
+            let inv_mass = &self.inv_mass;
             let x_ro: &[Vec2] = x;
             self.lambda.par_iter_mut().enumerate().for_each(|(i, lam)| {
                 let xi = x_ro[i];
                 let mut rho = 0.0f32;
                 let mut grad_i = Vec2::ZERO; // Σ_j ∇W_ij
                 let mut sum_grad2 = 0.0f32; // Σ_j |∇W_ij|²
-                grid.for_neighbors(xi, |j| {
-                    let d = xi - x_ro[j];
+                grid.for_neighbors(xi, |j, o| {
+                    let d = xi - x_ro[j] - o;
                     rho += w_poly6(d.length_squared());
                     if j != i {
                         let g = grad_spiky(d);
                         grad_i += g;
-                        sum_grad2 += g.length_squared();
+                        sum_grad2 += inv_mass[j] * g.length_squared();
                     }
                 });
                 let c = rho * inv_rho0 - 1.0;
-                let sum_grad_c2 = (grad_i.length_squared() + sum_grad2) * (inv_rho0 * inv_rho0);
+                let sum_grad_c2 =
+                    (inv_mass[i] * grad_i.length_squared() + sum_grad2) * (inv_rho0 * inv_rho0);
                 // Cap λ (see PbfParams::lambda_max): keeps incompressibility
                 // and normal cohesion but bounds the huge positive λ a sparse,
                 // far-under-dense fluid would otherwise produce, which is what
@@ -320,21 +366,22 @@ impl Pbf {
         {
             let grid = &self.grid;
             let lambda = &self.lambda;
+            let inv_mass = &self.inv_mass;
             let x_ro: &[Vec2] = x;
             let scorr_denom = self.scorr_denom;
             self.dp.par_iter_mut().enumerate().for_each(|(i, dpi)| {
                 let xi = x_ro[i];
                 let lami = lambda[i];
                 let mut corr = Vec2::ZERO;
-                grid.for_neighbors(xi, |j| {
+                grid.for_neighbors(xi, |j, o| {
                     if j == i {
                         return;
                     }
-                    let d = xi - x_ro[j];
+                    let d = xi - x_ro[j] - o;
                     let scorr = scorr(d.length_squared(), scorr_denom, scorr_k, scorr_n);
                     corr += grad_spiky(d) * (lami + lambda[j] + scorr);
                 });
-                let mut c = corr * inv_rho0;
+                let mut c = corr * (inv_mass[i] * inv_rho0);
                 let m2 = c.length_squared();
                 if m2 > max_corr * max_corr {
                     c *= max_corr / m2.sqrt();
@@ -343,10 +390,12 @@ impl Pbf {
             });
         }
 
-        // Apply and re-project onto the box.
+        // Apply and re-project onto the box and out of the obstacles.
+        let domain = *bounds.domain();
         for i in 0..n {
             x[i] += self.dp[i];
-            clamp_wall(&mut x[i]);
+            clamp_wall(&mut x[i], &domain);
+            bounds.project(&mut x[i], BALL_SIZE);
         }
     }
 
@@ -369,11 +418,11 @@ impl Pbf {
             let xi = x[i];
             let vi = vel[i];
             let mut acc = Vec2::ZERO;
-            grid.for_neighbors(xi, |j| {
+            grid.for_neighbors(xi, |j, o| {
                 if j == i {
                     return;
                 }
-                let w = w_poly6((xi - x[j]).length_squared());
+                let w = w_poly6((xi - x[j] - o).length_squared());
                 acc += (vel[j] - vi) * w;
             });
             *o = vi + acc * (xsph_c * inv_rho0);
@@ -398,12 +447,12 @@ impl Pbf {
                 let xi = x[i];
                 let vi = vel[i];
                 let mut omega = 0.0f32;
-                grid.for_neighbors(xi, |j| {
+                grid.for_neighbors(xi, |j, o| {
                     if j == i {
                         return;
                     }
                     let dv = vel[j] - vi;
-                    let g = grad_spiky(xi - x[j]);
+                    let g = grad_spiky(xi - x[j] - o);
                     omega += dv.x * g.y - dv.y * g.x; // (dv × ∇W)_z
                 });
                 *w = omega;
@@ -418,11 +467,11 @@ impl Pbf {
         out.par_iter_mut().enumerate().for_each(|(i, o)| {
             let xi = x[i];
             let mut grad_w = Vec2::ZERO; // ∇|ω|
-            grid.for_neighbors(xi, |j| {
+            grid.for_neighbors(xi, |j, o| {
                 if j == i {
                     return;
                 }
-                grad_w += grad_spiky(xi - x[j]) * curl[j].abs();
+                grad_w += grad_spiky(xi - x[j] - o) * curl[j].abs();
             });
             let mut add = Vec2::ZERO;
             let len = grad_w.length();
@@ -449,40 +498,71 @@ impl FluidSolver for Pbf {
         self.set_params(params);
     }
 
-    fn substep(&mut self, dt: f32, gravity: Vec2, share: &mut ShareData, c_opos: &mut Vec<Vec2>) {
+    fn set_phases(&mut self, phases: &[Phase]) {
+        self.phases = phases.to_vec();
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.vel, keep);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
         let n = share.c_pos.len();
         if n == 0 {
             return;
         }
-        self.ensure_sized(n);
         c_opos.resize(n, Vec2::ZERO);
+        self.ensure_sized(&share.c_pos, c_opos, dt);
+        inv_masses(&self.phases, &share.c_phase, n, &mut self.inv_mass);
 
         // Effective acceleration matched to the granular Verlet integrator
         // (x += a·dt²·INV_PHYS_DT there), so both models fall identically.
         const INV_PHYS_DT: f32 = 1.0 / PHYS_TIME_STEP;
         let accel = gravity * INV_PHYS_DT;
 
-        // 1. Predict: symplectic Euler under gravity.
+        // 1. Predict: symplectic Euler under gravity. The predicted velocity
+        // is parked in `vscratch` (free until the step-5 post-process) as the
+        // approach velocity for the obstacle response in step 4.
         let x = &mut share.c_pos;
+        let domain = *bounds.domain();
         for i in 0..n {
             self.vel[i] += accel * dt;
+            self.vscratch[i] = self.vel[i];
             self.prev[i] = x[i];
             x[i] += self.vel[i] * dt;
-            clamp_wall(&mut x[i]);
+            // Across a periodic seam: re-enter on the far side, carrying the
+            // predicted-from position along so the velocity is unchanged.
+            let wrapped = domain.wrap(x[i]);
+            self.prev[i] += wrapped - x[i];
+            x[i] = wrapped;
+            clamp_wall(&mut x[i], &domain);
+            bounds.project(&mut x[i], BALL_SIZE);
         }
 
         // 2. Neighbor grid at cell size h.
+        if self.grid.domain != domain {
+            self.grid = PbfGrid::new(&domain);
+        }
         self.grid.build(x);
 
         // 3. Density-constraint solver iterations (Jacobi).
         for _ in 0..self.params.iters {
-            self.solve_iteration(x);
+            self.solve_iteration(x, bounds);
         }
 
         // 4. Derive velocity from the total position change, with a safety
         // clamp: no particle may move more than half a smoothing radius per
         // substep. This bounds a single bad step so a transient can't cascade
         // into a full blow-up (belt-and-suspenders alongside the λ clamp).
+        // Projection already stopped the into-obstacle motion; the response
+        // adds the bounce and friction it can't express as a position.
         let inv_dt = 1.0 / dt;
         let vmax = 0.5 * PBF_H * inv_dt;
         let vmax2 = vmax * vmax;
@@ -492,11 +572,28 @@ impl FluidSolver for Pbf {
             if s2 > vmax2 {
                 v *= vmax / s2.sqrt();
             }
+            bounds.respond(x[i], &mut v, self.vscratch[i], BALL_SIZE);
             self.vel[i] = v;
         }
 
-        // 5. Velocity post-process: vorticity confinement then XSPH viscosity.
+        // 5. Velocity post-process: vorticity confinement, surface tension
+        // and wall adhesion, then XSPH viscosity.
         self.apply_vorticity(x, dt);
+        if self.params.surface_tension > 0.0 {
+            let gamma = self.params.surface_tension;
+            let accel = self
+                .tension
+                .accelerations(&self.grid, x, &self.inv_mass, gamma);
+            for (v, a) in self.vel.iter_mut().zip(accel) {
+                *v += *a * dt;
+            }
+        }
+        if bounds.adhesive() {
+            let reach = self.adhesion.reach();
+            for (v, &p) in self.vel.iter_mut().zip(x.iter()) {
+                *v += bounds.adhesion(p, reach, |d| self.adhesion.profile(d)) * dt;
+            }
+        }
         self.apply_xsph(x);
 
         // Bookkeeping for the shared/rendered state: c_opos mirrors the
@@ -506,6 +603,8 @@ impl FluidSolver for Pbf {
         let mut speed_sum = 0.0f32;
         let mut max_speed = 0.0f32;
         for i in 0..n {
+            // The solve can push a particle back across a periodic seam.
+            x[i] = domain.wrap(x[i]);
             c_opos[i] = x[i] - self.vel[i] * dt;
             // (c_pos − c_opos)*20 == vel*dt*20 is the granular speed metric.
             let speed = (self.vel[i] * dt * 20.0).length();
@@ -524,7 +623,7 @@ impl FluidSolver for Pbf {
             let xi = x[i];
             let mut rho = 0.0f32;
             self.grid
-                .for_neighbors(xi, |j| rho += w_poly6((xi - x[j]).length_squared()));
+                .for_neighbors(xi, |j, o| rho += w_poly6((xi - x[j] - o).length_squared()));
             dsum += (rho / self.rest_density) as f64;
             cnt += 1;
             i += stride;
@@ -0,0 +1,147 @@
+//! Rigid bodies floating in the fluid, two-way coupled through the boundary
+//! path.
+//!
+//! A body is a [`Shape`] in its own frame (centred on its center of mass)
+//! plus mass, moment of inertia, pose and velocity. To the fluid it is just
+//! one more moving collider: every strategy projects particles out of it and
+//! responds to its surface velocity exactly as for a kinematic obstacle. The
+//! difference is that each of those contacts is *recorded* — the momentum the
+//! contact gave a particle (or an MPM grid node) is collected with the
+//! opposite sign as a linear and angular impulse on the body. After the
+//! substep the coordinator applies the impulses and gravity, moves the body,
+//! and resolves its contacts with the box walls, the container and the
+//! obstacles.
+//!
+//! Masses are in the fluid's units: one fluid particle weighs its rest area
+//! (2·BALL_SIZE)², i.e. the fluid has unit area density, so a body's
+//! `density` is relative to water — below 1 floats, above 1 sinks. Bodies do
+//! not collide with each other.
+
+use glam::Vec2;
+
+use super::Shape;
+use crate::constants::BALL_SIZE;
+
+/// Mass of one fluid particle (its rest area at unit density).
+pub(crate) const FLUID_PARTICLE_MASS: f32 = (2.0 * BALL_SIZE) * (2.0 * BALL_SIZE);
+
+/// A dynamic rigid body.
+#[derive(Clone, Debug)]
+pub struct RigidBody {
+    /// Geometry in the body frame, centred on the center of mass.
+    pub shape: Shape,
+    pub mass: f32,
+    /// Moment of inertia about the center of mass.
+    pub inertia: f32,
+    pub position: Vec2,
+    pub angle: f32,
+    pub velocity: Vec2,
+    /// Angular velocity, rad/s.
+    pub omega: f32,
+    /// Bounce and Coulomb friction, used both for the fluid contacts and for
+    /// the body's own contacts with the walls.
+    pub restitution: f32,
+    pub friction: f32,
+    /// Fluid adhesion, as [`Obstacle::adhesion`](super::Obstacle::adhesion).
+    pub adhesion: f32,
+}
+
+impl RigidBody {
+    /// A body at rest at `position` with the given mass properties.
+    pub fn new(shape: Shape, mass: f32, inertia: f32, position: Vec2) -> Self {
+        Self {
+            shape,
+            mass,
+            inertia,
+            position,
+            angle: 0.0,
+            velocity: Vec2::ZERO,
+            omega: 0.0,
+            restitution: 0.0,
+            friction: 0.3,
+            adhesion: 0.0,
+        }
+    }
+
+    /// A solid disc; `density` is relative to the fluid.
+    pub fn disc(center: Vec2, radius: f32, density: f32) -> Self {
+        let mass = density * std::f32::consts::PI * radius * radius;
+        let shape = Shape::Circle {
+            center: Vec2::ZERO,
+            radius,
+        };
+        Self::new(shape, mass, 0.5 * mass * radius * radius, center)
+    }
+
+    /// A solid box with half extents `half`; `density` is relative to the
+    /// fluid.
+    pub fn rect(center: Vec2, half: Vec2, density: f32) -> Self {
+        let mass = density * 4.0 * half.x * half.y;
+        let shape = Shape::Polygon(vec![
+            Vec2::new(-half.x, -half.y),
+            Vec2::new(half.x, -half.y),
+            Vec2::new(half.x, half.y),
+            Vec2::new(-half.x, half.y),
+        ]);
+        let inertia = mass * (half.x * half.x + half.y * half.y) / 3.0;
+        Self::new(shape, mass, inertia, center)
+    }
+
+    /// World position of body-frame point `local`.
+    pub fn world_point(&self, local: Vec2) -> Vec2 {
+        self.position + Vec2::from_angle(self.angle).rotate(local)
+    }
+
+    /// Velocity of the body material at world point `p`.
+    pub fn velocity_at(&self, p: Vec2) -> Vec2 {
+        self.velocity + (p - self.position).perp() * self.omega
+    }
+
+    /// Apply an impulse `j` at world point `p`.
+    pub fn apply_impulse(&mut self, j: Vec2, p: Vec2) {
+        self.velocity += j / self.mass;
+        self.omega += (p - self.position).perp_dot(j) / self.inertia;
+    }
+
+    /// The points (body frame) and radii the body touches the walls with:
+    /// the center of a disc, the end caps of a capsule, the corners of a
+    /// polygon.
+    pub(crate) fn contact_points(&self) -> Vec<(Vec2, f32)> {
+        match &self.shape {
+            Shape::Circle { center, radius } => vec![(*center, *radius)],
+            Shape::Capsule { a, b, radius } => vec![(*a, *radius), (*b, *radius)],
+            Shape::Segment { a, b } => vec![(*a, 0.0), (*b, 0.0)],
+            Shape::Polygon(verts) => verts.iter().map(|&v| (v, 0.0)).collect(),
+        }
+    }
+
+    /// Resolve one contact of a body point of radius `rad` at world `q`
+    /// against a surface at distance `d` with normal `n` moving at `vb`:
+    /// push the body out, then a normal impulse with restitution and a
+    /// Coulomb-capped tangential one.
+    pub(crate) fn resolve_contact(&mut self, q: Vec2, rad: f32, d: f32, n: Vec2, vb: Vec2) {
+        if d >= rad {
+            return;
+        }
+        self.position += n * (rad - d);
+        let p = q + n * (rad - d) - n * rad; // contact point on the body surface
+        let r = p - self.position;
+        let rel = self.velocity_at(p) - vb;
+        let vn = rel.dot(n);
+        if vn >= 0.0 {
+            return;
+        }
+        let rn = r.perp_dot(n);
+        let jn = -(1.0 + self.restitution) * vn / (1.0 / self.mass + rn * rn / self.inertia);
+        let mut j = n * jn;
+        let vt = rel - n * vn;
+        let vt_len = vt.length();
+        if vt_len > 1e-6 {
+            let t = vt / vt_len;
+            let rt = r.perp_dot(t);
+            let jt = (vt_len / (1.0 / self.mass + rt * rt / self.inertia)).min(self.friction * jn);
+            j -= t * jt;
+        }
+        self.apply_impulse(j, p);
+    }
+}
@@ -1,11 +1,16 @@
 //! Shared SPH plumbing for the pressure-projection solvers: a runtime-sized
-//! counting-sort neighbor grid and the 2D smoothing kernels. Kept separate
+//! counting-sort neighbor grid, the 2D smoothing kernels, and the forces both
+//! PBF and DFSPH add on top of their pressure solve. Kept separate
 //! from PBF's hand-rolled `PbfGrid` (which predates this module and stays as
-//! it is, validated) so new SPH strategies don't each re-copy the same grid.
+//! it is, validated) so new SPH strategies don't each re-copy the same grid;
+//! the two share only the 3×3 block walk and its periodic seams
+//! ([`CellBlock`]).
 
 use glam::Vec2;
+use rayon::prelude::*;
 
-use crate::constants::{HEIGHT, WIDTH};
+use super::{Boundaries, Domain, PHYS_TIME_STEP};
+use crate::constants::BALL_SIZE;
 
 /// 2D SPH smoothing kernels with support radius `h`: poly6 for density,
 /// spiky-gradient for forces (its gradient stays large as r→0, so the pressure
@@ -30,6 +35,12 @@ impl SphKernel {
         }
     }
 
+    /// Support radius.
+    #[inline(always)]
+    pub fn h(&self) -> f32 {
+        self.h
+    }
+
     /// W(r,h) = (4/π h^8)(h²−r²)³, evaluated from r². Zero beyond h.
     #[inline(always)]
     pub fn w(&self, r2: f32) -> f32 {
@@ -54,6 +65,36 @@ impl SphKernel {
         d * f
     }
 
+    /// Akinci et al.'s cohesion spline, scaled to peak at 1 (r = h/2):
+    /// attractive (> 0) between h/2 and h, mildly repulsive closer in so
+    /// pairs don't clump. Zero at and beyond h.
+    #[inline(always)]
+    pub fn cohesion(&self, r: f32) -> f32 {
+        if r >= self.h || r <= 0.0 {
+            return 0.0;
+        }
+        let h6 = self.h2 * self.h2 * self.h2;
+        let c = (self.h - r).powi(3) * r.powi(3);
+        let c = if 2.0 * r > self.h {
+            c
+        } else {
+            2.0 * c - h6 / 64.0
+        };
+        c * (64.0 / h6)
+    }
+
+    /// Akinci et al.'s adhesion spline, scaled to peak at 1 (r = 3h/4):
+    /// purely attractive, and zero inside h/2 so it never drags a particle
+    /// into the wall. Zero at and beyond h.
+    #[inline(always)]
+    pub fn adhesion(&self, r: f32) -> f32 {
+        if r >= self.h || 2.0 * r <= self.h {
+            return 0.0;
+        }
+        let q = -4.0 * r * r / self.h + 6.0 * r - 2.0 * self.h;
+        (4.0 * q / self.h).max(0.0).powf(0.25)
+    }
+
     /// Rest density of a square lattice at spacing `s` (poly6 sum incl. self).
     pub fn rest_density(&self, s: f32) -> f32 {
         let reach = (self.h / s).ceil() as i32 + 1;
@@ -68,12 +109,346 @@ impl SphKernel {
     }
 }
 
+/// A neighbor grid over the domain: `f(j, o)` for every particle that may
+/// lie within the support of `pos`, `x_j + o` being its image nearest `pos`.
+pub(crate) trait Neighbors: Sync {
+    fn for_neighbors(&self, pos: Vec2, f: impl FnMut(usize, Vec2));
+}
+
+impl Neighbors for SphGrid {
+    #[inline(always)]
+    fn for_neighbors(&self, pos: Vec2, f: impl FnMut(usize, Vec2)) {
+        SphGrid::for_neighbors(self, pos, f)
+    }
+}
+
+/// Surface tension after Akinci, Akinci & Teschner (SIGGRAPH Asia 2013): a
+/// pairwise cohesion force along the [`SphKernel::cohesion`] spline plus a
+/// curvature force that pulls on differences of the color-field normals
+/// n_i = h Σ_j (1/ρ_j) ∇W_ij, both scaled by K_ij = 2ρ0/(ρ_i + ρ_j) so
+/// particles short of neighbors (the surface) feel it more:
+///   a_i = −γ Σ_j m_j K_ij (C(r_ij) x̂_ij + n_i − n_j)
+/// The normals vanish in the bulk, so the curvature term only acts at the
+/// surface and minimizes its length; cohesion holds thin sheets and jets
+/// together until they bead up. Densities are number densities (see
+/// [`Phase`](super::Phase)) and γ is an acceleration, px/s², per unit of neighbor weight.
+///
+/// The curvature term pushes a flat surface layer outward against the
+/// cohesion and relies on the pressure solve to hold it; a solver whose
+/// pressure can only push (DFSPH) peels its surface off under it and uses
+/// the cohesion term alone.
+pub(crate) struct SurfaceTension {
+    kernel: SphKernel,
+    curvature: bool,
+    rest_density: f32,
+    density: Vec<f32>,
+    normal: Vec<Vec2>,
+    accel: Vec<Vec2>,
+}
+
+impl SurfaceTension {
+    /// For a fluid of support `h` resting at lattice spacing `spacing`;
+    /// `curvature` enables the normal-difference term.
+    pub fn new(h: f32, spacing: f32, curvature: bool) -> Self {
+        let kernel = SphKernel::new(h);
+        Self {
+            rest_density: kernel.rest_density(spacing),
+            kernel,
+            curvature,
+            density: Vec::new(),
+            normal: Vec::new(),
+            accel: Vec::new(),
+        }
+    }
+
+    /// The surface-tension acceleration of every particle at `x` for
+    /// strength `gamma`; particle j weighs `1 / inv_mass[j]`.
+    pub fn accelerations<G: Neighbors>(
+        &mut self,
+        grid: &G,
+        x: &[Vec2],
+        inv_mass: &[f32],
+        gamma: f32,
+    ) -> &[Vec2] {
+        let n = x.len();
+        let kernel = &self.kernel;
+        self.density.resize(n, 0.0);
+        self.normal.resize(n, Vec2::ZERO);
+        self.accel.resize(n, Vec2::ZERO);
+
+        self.density
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(i, rho)| {
+                let xi = x[i];
+                let mut sum = 0.0;
+                grid.for_neighbors(xi, |j, o| sum += kernel.w((xi - x[j] - o).length_squared()));
+                *rho = sum;
+            });
+
+        let density = &self.density;
+        let curvature = self.curvature;
+        self.normal.par_iter_mut().enumerate().for_each(|(i, nrm)| {
+            if !curvature {
+                return;
+            }
+            let xi = x[i];
+            let mut sum = Vec2::ZERO;
+            grid.for_neighbors(xi, |j, o| {
+                if j != i {
+                    sum += kernel.grad(xi - x[j] - o) / density[j];
+                }
+            });
+            *nrm = sum * kernel.h;
+        });
+
+        let normal = &self.normal;
+        let rho0 = self.rest_density;
+        self.accel.par_iter_mut().enumerate().for_each(|(i, a)| {
+            let xi = x[i];
+            let mut sum = Vec2::ZERO;
+            grid.for_neighbors(xi, |j, o| {
+                if j == i {
+                    return;
+                }
+                let d = xi - x[j] - o;
+                let r = d.length();
+                if r >= kernel.h || r < 1e-6 {
+                    return;
+                }
+                let k = 2.0 * rho0 / (density[i] + density[j]);
+                let pull = d * (kernel.cohesion(r) / r) + (normal[i] - normal[j]);
+                sum += pull * (k / inv_mass[j]);
+            });
+            *a = sum * -gamma;
+        });
+        &self.accel
+    }
+}
+
+/// The pull of an adhesive surface on a particle at distance d, for
+/// [`Boundaries::adhesion`](super::Boundaries::adhesion). Akinci et al.
+/// sample the boundary with particles; here the surface stands in for a
+/// straight row of them at the rest spacing, half a spacing inside the solid
+/// so a particle resting on the surface is one spacing from the row, as from
+/// a fluid neighbor. The profile is the normal component of
+/// [`SphKernel::adhesion`] summed along that row, scaled to peak at 1 so β
+/// reads as an acceleration like γ.
+pub(crate) struct WallAdhesion {
+    kernel: SphKernel,
+    spacing: f32,
+    scale: f32,
+}
+
+impl WallAdhesion {
+    pub fn new(h: f32, spacing: f32) -> Self {
+        let mut w = Self {
+            kernel: SphKernel::new(h),
+            spacing,
+            scale: 1.0,
+        };
+        let peak = (1..64)
+            .map(|i| w.profile(h * i as f32 / 64.0))
+            .fold(0.0, f32::max);
+        w.scale = 1.0 / peak;
+        w
+    }
+
+    /// Support of the profile: zero at and beyond h.
+    pub fn reach(&self) -> f32 {
+        self.kernel.h
+    }
+
+    pub fn profile(&self, d: f32) -> f32 {
+        if d <= 0.0 || d >= self.kernel.h {
+            return 0.0;
+        }
+        let reach = (self.kernel.h / self.spacing) as i32;
+        let depth = d + 0.5 * self.spacing;
+        let mut sum = 0.0;
+        for m in -reach..=reach {
+            let r = depth.hypot(m as f32 * self.spacing);
+            sum += self.kernel.adhesion(r) * depth / r;
+        }
+        sum * self.scale
+    }
+}
+
+/// Cells per axis and their size for a neighbor grid of support `h` over
+/// `domain`. A walled axis gets ⌈len/h⌉ + 1 cells of exactly h (positions are
+/// clamped into the last); a periodic one must tile exactly for its stencil
+/// to wrap, so it gets ⌊len/h⌋ cells stretched to fit.
+pub(crate) fn grid_axes(domain: &Domain, h: f32) -> ((usize, f32), (usize, f32)) {
+    let axis = |len: f32, periodic: bool| {
+        if periodic {
+            let n = ((len / h) as usize).max(1);
+            (n, len / n as f32)
+        } else {
+            ((len / h).ceil() as usize + 1, h)
+        }
+    };
+    (
+        axis(domain.width, domain.periodic_x()),
+        axis(domain.height, domain.periodic_y()),
+    )
+}
+
+/// The box the SPH solvers keep particle centers in: `BALL_SIZE` inside
+/// each wall edge, unbounded across open and periodic ones (periodic axes
+/// wrap instead).
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WallBox {
+    pub lo: Vec2,
+    pub hi: Vec2,
+}
+
+impl Default for WallBox {
+    fn default() -> Self {
+        Self {
+            lo: Vec2::NEG_INFINITY,
+            hi: Vec2::INFINITY,
+        }
+    }
+}
+
+impl WallBox {
+    pub fn new(domain: &Domain) -> Self {
+        let walls = |len: f32, (lo, hi): (bool, bool)| {
+            (
+                if lo { BALL_SIZE } else { f32::NEG_INFINITY },
+                if hi { len - BALL_SIZE } else { f32::INFINITY },
+            )
+        };
+        let (lo_x, hi_x) = walls(domain.width, domain.walls_x());
+        let (lo_y, hi_y) = walls(domain.height, domain.walls_y());
+        Self {
+            lo: Vec2::new(lo_x, lo_y),
+            hi: Vec2::new(hi_x, hi_y),
+        }
+    }
+
+    /// Project `p` onto the box, killing the into-wall part of `v`.
+    #[inline(always)]
+    pub fn clamp(&self, p: &mut Vec2, v: &mut Vec2) {
+        if p.x < self.lo.x {
+            p.x = self.lo.x;
+            v.x = v.x.max(0.0);
+        } else if p.x > self.hi.x {
+            p.x = self.hi.x;
+            v.x = v.x.min(0.0);
+        }
+        if p.y < self.lo.y {
+            p.y = self.lo.y;
+            v.y = v.y.max(0.0);
+        } else if p.y > self.hi.y {
+            p.y = self.hi.y;
+            v.y = v.y.min(0.0);
+        }
+    }
+
+    /// `p` projected onto the box.
+    #[inline(always)]
+    pub fn clamp_point(&self, p: Vec2) -> Vec2 {
+        p.clamp(self.lo, self.hi)
+    }
+}
+
+/// Advect the particles by their velocities over `dt` (capped at `vmax`
+/// first), project them onto the box and out of the obstacles, and wrap the
+/// periodic axes.
+pub(crate) fn advect(
+    x: &mut [Vec2],
+    vel: &mut [Vec2],
+    dt: f32,
+    vmax: f32,
+    walls: &WallBox,
+    bounds: &mut Boundaries,
+) {
+    let vmax2 = vmax * vmax;
+    let domain = *bounds.domain();
+    for (xi, vi) in x.iter_mut().zip(vel) {
+        let mut v = *vi;
+        let s2 = v.length_squared();
+        if s2 > vmax2 {
+            v *= vmax / s2.sqrt();
+        }
+        let mut p = *xi + v * dt;
+        walls.clamp(&mut p, &mut v);
+        bounds.collide(&mut p, &mut v, BALL_SIZE);
+        *xi = domain.wrap(p);
+        *vi = v;
+    }
+}
+
+/// Fit `vel` to the particles in `x`: survivors keep their velocity,
+/// newcomers take theirs from `c_opos`.
+pub(crate) fn seed_velocities(vel: &mut Vec<Vec2>, x: &[Vec2], c_opos: &[Vec2], dt: f32) {
+    let n = x.len();
+    let seen = vel.len().min(n);
+    vel.truncate(n);
+    vel.extend((seen..n).map(|i| (x[i] - c_opos[i]) / dt));
+}
+
+/// Per-particle state an SPH strategy keeps alongside `c_pos`, fitted to the
+/// particle count by [`fit_particles`].
+pub(crate) trait PerParticle {
+    fn fit(&mut self, n: usize);
+}
+
+impl<T: Clone + Default> PerParticle for Vec<T> {
+    fn fit(&mut self, n: usize) {
+        self.resize(n, T::default());
+    }
+}
+
+/// Fit a strategy's per-particle arrays to the particles in `x`: `vel` as in
+/// [`seed_velocities`], and every array in `state` drops the removed tail or
+/// grows with default (zero) entries.
+pub(crate) fn fit_particles(
+    vel: &mut Vec<Vec2>,
+    x: &[Vec2],
+    c_opos: &[Vec2],
+    dt: f32,
+    state: &mut [&mut dyn PerParticle],
+) {
+    seed_velocities(vel, x, c_opos, dt);
+    for a in state {
+        a.fit(x.len());
+    }
+}
+
+/// Gravity as the acceleration the granular Verlet integrator effectively
+/// applies (x += g·dt²/PHYS_TIME_STEP there), so the SPH strategies fall at
+/// the same rate as the grains.
+#[inline]
+pub(crate) fn effective_gravity(gravity: Vec2) -> Vec2 {
+    gravity / PHYS_TIME_STEP
+}
+
+/// Mean of `density_ratio(i)` (ρ_i/ρ0; 1.0 = incompressible) over a
+/// subsample of the `n` particles, for [`report`](super::report).
+pub(crate) fn sampled_density_ratio(n: usize, density_ratio: impl Fn(usize) -> f32) -> f32 {
+    let stride = (n / 256).max(1);
+    let mut dsum = 0.0f64;
+    let mut cnt = 0usize;
+    let mut i = 0;
+    while i < n {
+        dsum += density_ratio(i) as f64;
+        cnt += 1;
+        i += stride;
+    }
+    (dsum / cnt.max(1) as f64) as f32
+}
+
 /// Uniform grid at a runtime cell size, counting-sort CSR layout (Green 2010,
 /// Hoetzlein 2014). Cell size is set to the SPH support radius h so the 3×3
-/// block around a particle's cell contains every neighbour within h.
+/// block around a particle's cell contains every neighbour within h; the cell
+/// count follows the domain (see [`SphGrid::fit`]).
 #[derive(Default)]
 pub struct SphGrid {
-    cell: f32,
+    h_support: f32,
+    cell: Vec2,
+    domain: Domain,
     w: usize,
     h: usize,
     cell_start: Vec<u32>, // w*h + 1 offsets
@@ -83,11 +458,12 @@ pub struct SphGrid {
 }
 
 impl SphGrid {
-    pub fn new(cell_size: f32) -> Self {
-        let w = (WIDTH / cell_size).ceil() as usize + 1;
-        let h = (HEIGHT / cell_size).ceil() as usize + 1;
+    pub fn new(cell_size: f32, domain: &Domain) -> Self {
+        let ((w, cw), (h, ch)) = grid_axes(domain, cell_size);
         Self {
-            cell: cell_size,
+            h_support: cell_size,
+            cell: Vec2::new(cw, ch),
+            domain: *domain,
             w,
             h,
             cell_start: vec![0; w * h + 1],
@@ -97,10 +473,17 @@ impl SphGrid {
         }
     }
 
+    /// Re-size the grid for `domain` if it changed since the last build.
+    pub fn fit(&mut self, domain: &Domain) {
+        if self.domain != *domain {
+            *self = Self::new(self.h_support, domain);
+        }
+    }
+
     #[inline(always)]
     fn coord(&self, p: Vec2) -> (usize, usize) {
-        let x = ((p.x / self.cell) as isize).clamp(0, self.w as isize - 1) as usize;
-        let y = ((p.y / self.cell) as isize).clamp(0, self.h as isize - 1) as usize;
+        let x = ((p.x / self.cell.x) as isize).clamp(0, self.w as isize - 1) as usize;
+        let y = ((p.y / self.cell.y) as isize).clamp(0, self.h as isize - 1) as usize;
         (x, y)
     }
 
@@ -134,10 +517,44 @@ impl SphGrid {
         }
     }
 
-    /// Invoke `f(j)` for every particle in the 3×3 cell block around `pos`.
+    /// Invoke `f(j, o)` for every particle in the 3×3 cell block around
+    /// `pos`; `x_j + o` is the image of j nearest `pos` (`o` is zero except
+    /// across a periodic seam).
     #[inline(always)]
-    pub fn for_neighbors(&self, pos: Vec2, mut f: impl FnMut(usize)) {
+    pub fn for_neighbors(&self, pos: Vec2, f: impl FnMut(usize, Vec2)) {
         let (cx, cy) = self.coord(pos);
+        CellBlock {
+            domain: &self.domain,
+            w: self.w,
+            h: self.h,
+            cell_start: &self.cell_start,
+            indices: &self.indices,
+        }
+        .for_neighbors(cx, cy, f);
+    }
+}
+
+/// A borrowed view of a counting-sort CSR grid over `domain` (`cell_start`
+/// holds w·h + 1 offsets into `indices`), walking the 3×3 block around a cell
+/// for both [`SphGrid`] and PBF's own grid.
+pub(crate) struct CellBlock<'a> {
+    pub(crate) domain: &'a Domain,
+    pub(crate) w: usize,
+    pub(crate) h: usize,
+    pub(crate) cell_start: &'a [u32],
+    pub(crate) indices: &'a [u32],
+}
+
+impl CellBlock<'_> {
+    /// Invoke `f(j, o)` for every particle in the 3×3 block around cell
+    /// (cx, cy); `o` is zero except across a periodic seam, where it carries
+    /// j to its image on this side.
+    #[inline(always)]
+    pub(crate) fn for_neighbors(&self, cx: usize, cy: usize, mut f: impl FnMut(usize, Vec2)) {
+        if self.on_seam(cx, cy) {
+            self.for_neighbors_wrapped(cx, cy, f);
+            return;
+        }
         let y0 = cy.saturating_sub(1);
         let y1 = (cy + 1).min(self.h - 1);
         let x0 = cx.saturating_sub(1);
@@ -147,7 +564,34 @@ impl SphGrid {
             let s = self.cell_start[base + x0] as usize;
             let e = self.cell_start[base + x1 + 1] as usize;
             for &j in &self.indices[s..e] {
-                f(j as usize);
+                f(j as usize, Vec2::ZERO);
+            }
+        }
+    }
+
+    /// Whether the 3×3 block of cell (cx, cy) crosses a periodic seam.
+    #[inline(always)]
+    fn on_seam(&self, cx: usize, cy: usize) -> bool {
+        (self.domain.periodic_x() && (cx == 0 || cx == self.w - 1))
+            || (self.domain.periodic_y() && (cy == 0 || cy == self.h - 1))
+    }
+
+    /// `for_neighbors` for a cell on a periodic seam: cell by cell, with the
+    /// wrapped-around ones carrying the offset to their images.
+    fn for_neighbors_wrapped(&self, cx: usize, cy: usize, mut f: impl FnMut(usize, Vec2)) {
+        for dy in -1..=1 {
+            let Some((yy, oy)) = self.domain.step_y(cy, dy, self.h) else {
+                continue;
+            };
+            for dx in -1..=1 {
+                let Some((xx, ox)) = self.domain.step_x(cx, dx, self.w) else {
+                    continue;
+                };
+                let c = yy * self.w + xx;
+                let (s, e) = (self.cell_start[c] as usize, self.cell_start[c + 1] as usize);
+                for &j in &self.indices[s..e] {
+                    f(j as usize, Vec2::new(ox, oy));
+                }
             }
         }
     }
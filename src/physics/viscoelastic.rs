@@ -0,0 +1,553 @@
+//! Particle-based viscoelastic fluid (Clavet, Beaudoin & Poulin, SCA 2005) as
+//! a `FluidSolver` strategy: slime, goo and stringy dough.
+//!
+//! A prediction–relaxation scheme, like PBF, but with Clavet's own pieces:
+//!   1. gravity, then pairwise radial viscosity impulses on approaching pairs,
+//!      I = Δt (1 − q)(σu + βu²) r̂ with u the approach speed and q = r/h
+//!   2. predict x += Δt v
+//!   3. adjust the spring network (below) and apply its displacements
+//!   4. **double-density relaxation**: the density ρ_i = Σ_j (1 − q)² and a
+//!      near-density ρⁿ_i = Σ_j (1 − q)³ give a pressure P = k(ρ − ρ0) and a
+//!      near-pressure Pⁿ = kⁿ ρⁿ, and each pair is pushed apart by
+//!      D = Δt² (P (1 − q) + Pⁿ (1 − q)²) r̂, half to each side. The
+//!      near-pressure only ever repels, so it keeps particles from clumping
+//!      and gives the free surface its tension without a separate term.
+//!   5. project onto the box and out of the obstacles; v = (x − x_prev)/Δt
+//!
+//! **Springs** carry the elasticity. Every neighbor pair gets one, created
+//! at rest length L = h, where its pull (1 − L/h)(L − r) is zero. Stretched
+//! beyond the yield ratio γ of L, the rest length creeps toward the current
+//! length at the plasticity rate α (and likewise when compressed), so a spring
+//! that is held stretched or squeezed gradually *remembers* its new length
+//! and pulls with it: the fluid flows, but resists and recoils. A spring whose
+//! rest length grows past h is removed, which is how a strand finally snaps.
+//! α = 0 keeps every rest length (and every spring) for good — but since new
+//! springs start at L = h, that is a plain liquid; the goo needs α > 0.
+//!
+//! The pair passes are Jacobi (each particle sums its half of every pair's
+//! displacement against the pre-pass positions) rather than the paper's
+//! in-place Gauss–Seidel, so they run in parallel. The spring network is a
+//! sorted pair list, merged with the grid's neighbor pairs each step (serial,
+//! and deterministic), and remapped on removal. Phases are ignored.
+
+use glam::Vec2;
+use rayon::prelude::*;
+
+use super::sph::{effective_gravity, fit_particles, sampled_density_ratio, SphGrid, WallBox};
+use super::{report, retain_flagged, Boundaries, Domain, FluidSolver, ShareData};
+use crate::constants::BALL_SIZE;
+
+// Interaction radius and rest spacing shared with the SPH solvers, so a goo
+// packs the same particle count to the same volume.
+const VISCO_H: f32 = 15.0;
+const VISCO_REST_SPACING: f32 = 2.0 * BALL_SIZE;
+
+/// Tunable viscoelastic coefficients (the paper's symbols in parentheses).
+#[derive(Clone, Copy, Debug)]
+pub struct ViscoelasticParams {
+    /// Pressure stiffness (k), px/s² per unit density error.
+    pub stiffness: f32,
+    /// Near-pressure stiffness (kⁿ), px/s² per unit near-density.
+    pub near_stiffness: f32,
+    /// Linear viscosity (σ), 1/s.
+    pub linear_viscosity: f32,
+    /// Quadratic viscosity (β), s/px. Damps fast approaches, so impacts
+    /// don't scatter particles.
+    pub quadratic_viscosity: f32,
+    /// Spring stiffness (k^spring), 1/s² (0 = no springs: Clavet's liquid).
+    pub spring_stiffness: f32,
+    /// Yield ratio (γ): the fraction of its rest length a spring stretches or
+    /// compresses elastically before it starts to deform plastically.
+    pub yield_ratio: f32,
+    /// Plasticity rate (α), 1/s: how fast a yielded spring's rest length
+    /// follows its length. Small is springy slime, large is runny dough.
+    pub plasticity: f32,
+}
+
+impl Default for ViscoelasticParams {
+    fn default() -> Self {
+        Self {
+            stiffness: 2.0e5,
+            // kⁿ/k = 0.1: the near-pressure settles the packing ~5% below
+            // ρ0. At kⁿ ≫ k a free blob swells to ~0.55 ρ0 before the
+            // negative pressure holds it.
+            near_stiffness: 2.0e4,
+            linear_viscosity: 20.0,
+            quadratic_viscosity: 0.01,
+            spring_stiffness: 1.0e5,
+            yield_ratio: 0.1,
+            plasticity: 10.0,
+        }
+    }
+}
+
+/// One spring of the network: particle indices `i < j` and rest length.
+#[derive(Clone, Copy)]
+struct Spring {
+    i: u32,
+    j: u32,
+    rest: f32,
+}
+
+/// Viscoelastic solver state: explicit velocities, the spring network, and
+/// the pass scratch.
+pub struct Viscoelastic {
+    grid: SphGrid,
+    rest_density: f32,
+    params: ViscoelasticParams,
+    vel: Vec<Vec2>,
+    prev: Vec<Vec2>,
+    density: Vec<(f32, f32)>, // (ρ_i, ρⁿ_i)
+    dx: Vec<Vec2>,            // per-particle displacement / impulse sums
+    springs: Vec<Spring>,
+    merged: Vec<Spring>,         // merge double-buffer
+    candidates: Vec<(u32, u32)>, // this step's neighbor pairs, sorted
+}
+
+impl Default for Viscoelastic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// (1 − r/h), the shared kernel of every Clavet pass.
+#[inline(always)]
+fn falloff(r: f32) -> f32 {
+    1.0 - r / VISCO_H
+}
+
+impl Viscoelastic {
+    pub fn new() -> Self {
+        // ρ0: the density of a square lattice at the rest spacing, so the
+        // relaxation packs the fluid there (the paper picks ρ0 freely).
+        let s = VISCO_REST_SPACING;
+        let reach = (VISCO_H / s).ceil() as i32;
+        let mut rest_density = 0.0;
+        for iy in -reach..=reach {
+            for ix in -reach..=reach {
+                let r = ((ix * ix + iy * iy) as f32).sqrt() * s;
+                if r > 0.0 && r < VISCO_H {
+                    rest_density += falloff(r).powi(2);
+                }
+            }
+        }
+        Self {
+            grid: SphGrid::new(VISCO_H, &Domain::default()),
+            rest_density,
+            params: ViscoelasticParams::default(),
+            vel: Vec::new(),
+            prev: Vec::new(),
+            density: Vec::new(),
+            dx: Vec::new(),
+            springs: Vec::new(),
+            merged: Vec::new(),
+            candidates: Vec::new(),
+        }
+    }
+
+    pub fn set_params(&mut self, params: ViscoelasticParams) {
+        self.params = params;
+        if params.spring_stiffness == 0.0 {
+            self.springs.clear();
+        }
+    }
+
+    /// Step 1b: radial viscosity impulses between approaching neighbors,
+    /// each particle taking its half of every pair's impulse.
+    fn apply_viscosity(&mut self, x: &[Vec2], dt: f32) {
+        let (sigma, beta) = (
+            self.params.linear_viscosity,
+            self.params.quadratic_viscosity,
+        );
+        if sigma == 0.0 && beta == 0.0 {
+            return;
+        }
+        {
+            let grid = &self.grid;
+            let vel = &self.vel;
+            self.dx.par_iter_mut().enumerate().for_each(|(i, dv)| {
+                let xi = x[i];
+                let vi = vel[i];
+                let mut acc = Vec2::ZERO;
+                grid.for_neighbors(xi, |j, o| {
+                    let d = x[j] + o - xi; // i → j
+                    let r2 = d.length_squared();
+                    if j == i || r2 >= VISCO_H * VISCO_H || r2 == 0.0 {
+                        return;
+                    }
+                    let r = r2.sqrt();
+                    let n = d / r;
+                    let u = (vi - vel[j]).dot(n); // > 0: approaching
+                    if u > 0.0 {
+                        acc -= n * (0.5 * dt * falloff(r) * (sigma * u + beta * u * u));
+                    }
+                });
+                *dv = acc;
+            });
+        }
+        for (v, dv) in self.vel.iter_mut().zip(&self.dx) {
+            *v += *dv;
+        }
+    }
+
+    /// Step 3: merge this step's neighbor pairs into the spring network
+    /// (new pairs get L = h), creep yielded rest lengths, drop springs past
+    /// h, and apply the spring displacements.
+    fn update_springs(&mut self, x: &mut [Vec2], domain: &Domain, dt: f32) {
+        let ViscoelasticParams {
+            spring_stiffness: k,
+            yield_ratio: gamma,
+            plasticity: alpha,
+            ..
+        } = self.params;
+        if k == 0.0 {
+            return;
+        }
+
+        // Neighbor pairs i < j within h, sorted for the merge.
+        self.candidates.clear();
+        for (i, &xi) in x.iter().enumerate() {
+            let start = self.candidates.len();
+            self.grid.for_neighbors(xi, |j, o| {
+                if j > i && (xi - x[j] - o).length_squared() < VISCO_H * VISCO_H {
+                    self.candidates.push((i as u32, j as u32));
+                }
+            });
+            self.candidates[start..].sort_unstable();
+        }
+        self.candidates.dedup();
+
+        // Merge (both lists are sorted by (i, j)), then creep and cull.
+        let mut merged = std::mem::take(&mut self.merged);
+        merged.clear();
+        let (mut a, mut b) = (0, 0);
+        while a < self.springs.len() || b < self.candidates.len() {
+            let old = self.springs.get(a).map(|s| (s.i, s.j));
+            let new = self.candidates.get(b).copied();
+            let spring = match (old, new) {
+                (Some(o), Some(c)) if o == c => {
+                    a += 1;
+                    b += 1;
+                    self.springs[a - 1]
+                }
+                (Some(o), Some(c)) if c < o => {
+                    b += 1;
+                    Spring {
+                        i: c.0,
+                        j: c.1,
+                        rest: VISCO_H,
+                    }
+                }
+                (Some(_), _) => {
+                    a += 1;
+                    self.springs[a - 1]
+                }
+                (None, Some(c)) => {
+                    b += 1;
+                    Spring {
+                        i: c.0,
+                        j: c.1,
+                        rest: VISCO_H,
+                    }
+                }
+                (None, None) => unreachable!(),
+            };
+            let (i, j) = (spring.i as usize, spring.j as usize);
+            let r = domain.min_image(x[j] - x[i]).length();
+            let mut rest = spring.rest;
+            let tolerance = gamma * rest;
+            if r > rest + tolerance {
+                rest += dt * alpha * (r - rest - tolerance);
+            } else if r < rest - tolerance {
+                rest -= dt * alpha * (rest - tolerance - r);
+            }
+            if rest <= VISCO_H {
+                merged.push(Spring { rest, ..spring });
+            }
+        }
+        std::mem::swap(&mut self.springs, &mut merged);
+        self.merged = merged;
+
+        // Displacements, Gauss–Seidel over the springs as in the paper: each
+        // spring moves its two ends symmetrically, so the order only
+        // matters at second order and the network stays deterministic.
+        let scale = dt * dt * k;
+        for s in &self.springs {
+            let (i, j) = (s.i as usize, s.j as usize);
+            let d = domain.min_image(x[j] - x[i]);
+            let r = d.length();
+            if r == 0.0 {
+                continue;
+            }
+            let disp = d * (scale * (1.0 - s.rest / VISCO_H) * (s.rest - r) / r);
+            x[i] -= disp * 0.5;
+            x[j] += disp * 0.5;
+        }
+    }
+
+    /// Step 4: double-density relaxation, as a Jacobi pass.
+    fn relax_density(&mut self, x: &mut [Vec2], dt: f32) {
+        let (k, k_near) = (self.params.stiffness, self.params.near_stiffness);
+        let rho0 = self.rest_density;
+        let dt2 = dt * dt;
+        {
+            let grid = &self.grid;
+            let xs = &*x;
+            self.density
+                .par_iter_mut()
+                .enumerate()
+                .for_each(|(i, out)| {
+                    let xi = xs[i];
+                    let (mut rho, mut near) = (0.0f32, 0.0f32);
+                    grid.for_neighbors(xi, |j, o| {
+                        let r2 = (xi - xs[j] - o).length_squared();
+                        if j == i || r2 >= VISCO_H * VISCO_H {
+                            return;
+                        }
+                        let q1 = falloff(r2.sqrt());
+                        rho += q1 * q1;
+                        near += q1 * q1 * q1;
+                    });
+                    *out = (rho, near);
+                });
+            let density = &self.density;
+            // Pressures: P = k(ρ − ρ0), Pⁿ = kⁿρⁿ, per particle.
+            let pressure = |i: usize| {
+                let (rho, near) = density[i];
+                (k * (rho - rho0), k_near * near)
+            };
+            self.dx.par_iter_mut().enumerate().for_each(|(i, out)| {
+                let xi = xs[i];
+                let (p_i, pn_i) = pressure(i);
+                let mut acc = Vec2::ZERO;
+                grid.for_neighbors(xi, |j, o| {
+                    let d = xs[j] + o - xi; // i → j
+                    let r2 = d.length_squared();
+                    if j == i || r2 >= VISCO_H * VISCO_H || r2 == 0.0 {
+                        return;
+                    }
+                    let r = r2.sqrt();
+                    let q1 = falloff(r);
+                    let (p_j, pn_j) = pressure(j);
+                    // Half of i's push on j plus half of j's push on i,
+                    // both moving i away from j.
+                    let mag = 0.5 * dt2 * ((p_i + p_j) * q1 + (pn_i + pn_j) * q1 * q1);
+                    acc -= d * (mag / r);
+                });
+                *out = acc;
+            });
+        }
+        for (p, d) in x.iter_mut().zip(&self.dx) {
+            *p += *d;
+        }
+    }
+}
+
+impl FluidSolver for Viscoelastic {
+    fn name(&self) -> &'static str {
+        "Viscoelastic"
+    }
+
+    fn set_viscoelastic_params(&mut self, params: ViscoelasticParams) {
+        self.set_params(params);
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.vel, keep);
+        // Remap the spring ends to the compacted indices (monotone, so the
+        // list stays sorted) and drop springs that lost an end.
+        let mut next = 0u32;
+        let remap: Vec<u32> = keep
+            .iter()
+            .map(|&k| {
+                if k {
+                    next += 1;
+                    next - 1
+                } else {
+                    u32::MAX
+                }
+            })
+            .collect();
+        self.springs.retain_mut(|s| {
+            let (i, j) = (remap[s.i as usize], remap[s.j as usize]);
+            s.i = i;
+            s.j = j;
+            i != u32::MAX && j != u32::MAX
+        });
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
+        let n = share.c_pos.len();
+        if n == 0 {
+            return;
+        }
+        c_opos.resize(n, Vec2::ZERO);
+        fit_particles(
+            &mut self.vel,
+            &share.c_pos,
+            c_opos,
+            dt,
+            &mut [&mut self.prev, &mut self.density, &mut self.dx],
+        );
+
+        let accel = effective_gravity(gravity);
+        let domain = *bounds.domain();
+
+        // 1. Gravity and viscosity impulses.
+        for v in &mut self.vel {
+            *v += accel * dt;
+        }
+        self.grid.fit(&domain);
+        self.grid.build(&share.c_pos);
+        self.apply_viscosity(&share.c_pos, dt);
+
+        // 2. Predict.
+        self.prev.copy_from_slice(&share.c_pos);
+        for (x, v) in share.c_pos.iter_mut().zip(&self.vel) {
+            *x += *v * dt;
+        }
+
+        // 3–4. Springs and double-density relaxation at the predicted
+        // positions.
+        self.grid.build(&share.c_pos);
+        self.update_springs(&mut share.c_pos, &domain, dt);
+        self.relax_density(&mut share.c_pos, dt);
+
+        // 5. Project onto the box and out of the obstacles, then take the
+        // velocity from the step's total displacement; open and periodic
+        // edges have no wall (periodic axes wrap).
+        let walls = WallBox::new(&domain);
+        let inv_dt = 1.0 / dt;
+        let x = &mut share.c_pos;
+        for ((xi, vi), prev) in x.iter_mut().zip(&mut self.vel).zip(&self.prev) {
+            let mut p = walls.clamp_point(*xi);
+            let mut v = (p - *prev) * inv_dt;
+            bounds.collide(&mut p, &mut v, BALL_SIZE);
+            *xi = domain.wrap(p);
+            *vi = v;
+        }
+
+        // 6. Shared/rendered bookkeeping and diagnostics, as DFSPH (with
+        // Clavet's density, as relaxed this step).
+        let (density, rho0) = (&self.density, self.rest_density);
+        let ratio = sampled_density_ratio(n, |i| density[i].0 / rho0);
+        report(share, c_opos, &self.vel, dt, ratio);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{HEIGHT, WIDTH};
+    use crate::physics::tests::block_scene;
+    use crate::physics::{Strategy, PHYS_TIME_STEP};
+
+    /// A 20×20 blob at `(x, y)` moving at `vel` px/s, under `gravity`, stepped
+    /// `steps` times. Returns the final positions and the centroid velocity
+    /// over the last 100 steps.
+    fn run_blob(
+        origin: Vec2,
+        vel: Vec2,
+        gravity: Option<Vec2>,
+        params: ViscoelasticParams,
+        steps: usize,
+    ) -> (Vec<Vec2>, Vec2) {
+        let (mut physics, mut share) = block_scene(
+            Strategy::Viscoelastic,
+            Domain::default(),
+            origin,
+            (20, 20),
+            vel,
+        );
+        physics.set_viscoelastic_params(params);
+        if let Some(g) = gravity {
+            physics.set_gravity(g);
+        }
+        let n = share.c_pos.len();
+        let centroid = |x: &[Vec2]| x.iter().copied().sum::<Vec2>() / n as f32;
+        let mut before = Vec2::ZERO;
+        for k in 0..steps {
+            if k + 100 == steps {
+                before = centroid(&share.c_pos);
+            }
+            physics.step(PHYS_TIME_STEP, &mut share);
+        }
+        let drift = (centroid(&share.c_pos) - before) / (100.0 * PHYS_TIME_STEP);
+        (share.c_pos, drift)
+    }
+
+    fn extent(x: &[Vec2]) -> Vec2 {
+        let lo = x
+            .iter()
+            .copied()
+            .fold(Vec2::splat(f32::INFINITY), Vec2::min);
+        let hi = x
+            .iter()
+            .copied()
+            .fold(Vec2::splat(f32::NEG_INFINITY), Vec2::max);
+        hi - lo
+    }
+
+    /// Dropped goo must stay finite and inside the box and come to rest as
+    /// a mound rather than spreading across the floor.
+    #[test]
+    fn viscoelastic_blob_is_stable() {
+        let start = Vec2::new(600.0, 300.0);
+        let params = ViscoelasticParams::default();
+        let (x, drift) = run_blob(start, Vec2::ZERO, None, params, 1500);
+        assert!(x.iter().all(|p| p.is_finite()), "non-finite positions");
+        assert!(
+            x.iter().all(
+                |p| (-1.0..=WIDTH + 1.0).contains(&p.x) && (-1.0..=HEIGHT + 1.0).contains(&p.y)
+            ),
+            "particles escaped the box"
+        );
+        assert!(drift.length() < 20.0, "still moving: {drift}");
+        // ~500 px wide (the 120 px blob sagged into a dome); a spring-less
+        // Clavet liquid runs wall to wall.
+        let width = extent(&x).x;
+        assert!(width < 700.0, "goo spread to {width} px");
+    }
+
+    /// The springs' memory of the blob's shape: thrown at a wall in zero
+    /// gravity, goo bounces off whole, where the spring-less liquid splats
+    /// into a sheet along the wall and stays there.
+    #[test]
+    fn goo_rebounds_where_liquid_splats() {
+        let throw = |spring_stiffness| {
+            let params = ViscoelasticParams {
+                spring_stiffness,
+                ..Default::default()
+            };
+            let start = Vec2::new(400.0, 540.0);
+            run_blob(
+                start,
+                Vec2::new(-400.0, 0.0),
+                Some(Vec2::ZERO),
+                params,
+                1000,
+            )
+        };
+        let (goo, goo_drift) = throw(ViscoelasticParams::default().spring_stiffness);
+        let (liquid, liquid_drift) = throw(0.0);
+        // Goo leaves at ~220 px/s (restitution ~0.55) and ~140 px tall.
+        assert!(
+            goo_drift.x > 100.0 && extent(&goo).y < 250.0,
+            "goo: drift {goo_drift}, extent {}",
+            extent(&goo)
+        );
+        // The liquid sheet runs ~850 px up the wall and drifts off slowly.
+        assert!(
+            liquid_drift.x < 50.0 && extent(&liquid).y > 500.0,
+            "liquid: drift {liquid_drift}, extent {}",
+            extent(&liquid)
+        );
+    }
+}
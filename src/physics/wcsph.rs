@@ -0,0 +1,371 @@
+//! Weakly-compressible SPH (Monaghan 1994; Becker & Teschner, SCA 2007) as a
+//! `FluidSolver` strategy — the textbook explicit baseline the implicit
+//! solvers are measured against.
+//!
+//! Nothing is solved: the pressure is a stiff function of the density, the
+//! Tait equation of state
+//!   p_i = B ((ρ_i/ρ0)^γ − 1),   B = ρ0 c²/γ,
+//! with γ = 7 and a numerical speed of sound c ([`WcsphParams::sound_speed`]),
+//! and the accelerations are the symmetric SPH pressure gradient plus
+//! Monaghan's artificial viscosity:
+//!   a_i = g − w_i Σ_j (p_i/ρ_i² + p_j/ρ_j² + Π_ij) ∇W_ij,
+//!   Π_ij = −2αhc/(ρ_i + ρ_j) · v_ij·x_ij/(|x_ij|² + 0.01h²)  (approaching only)
+//! integrated by symplectic Euler. The fluid compresses by ~|v|²/c² in flow
+//! and ~g·d/c² at depth d, so c trades incompressibility for cost.
+//!
+//! That cost is the CFL condition Δt ≤ λ h/(c + |v|max): at the default c
+//! it sits several times below the engine's 480 Hz substep, so each engine
+//! substep is split into as many equal WCSPH steps as the condition asks for
+//! (capped at [`WcsphParams::max_substeps`]), recomputed from the current
+//! fastest particle. A splash into the floor under the engine's large
+//! effective gravity raises |v|max and with it the count, instead of blowing
+//! up.
+//!
+//! Negative pressures are clamped to zero: with γ = 7 the under-dense free
+//! surface would otherwise pull particles into clumps (the tensile
+//! instability). Masses, phases and units follow [`Dfsph`](super::Dfsph):
+//! mⱼ = 1, ρ_i = Σ_j W_ij, and a heavier phase enters through w_i = 1/m_i.
+
+use glam::Vec2;
+use rayon::prelude::*;
+
+use super::phase::inv_masses;
+use super::sph::{
+    advect, effective_gravity, fit_particles, sampled_density_ratio, SphGrid, SphKernel, WallBox,
+};
+use super::{report, retain_flagged, Boundaries, Domain, FluidSolver, Phase, ShareData};
+use crate::constants::BALL_SIZE;
+
+// The smoothing radius and rest spacing of the other SPH solvers, so a WCSPH
+// fluid packs the same particle count to the same volume.
+const WCSPH_H: f32 = 15.0;
+const WCSPH_REST_SPACING: f32 = 2.0 * BALL_SIZE;
+
+/// Tait exponent, the usual choice for water.
+const TAIT_GAMMA: i32 = 7;
+
+/// Tunable WCSPH coefficients.
+#[derive(Clone, Copy, Debug)]
+pub struct WcsphParams {
+    /// Numerical speed of sound c, px/s. Stiffer (less compressible) fluid
+    /// for proportionally more substeps.
+    pub sound_speed: f32,
+    /// Monaghan artificial-viscosity coefficient α.
+    pub viscosity: f32,
+    /// CFL number λ of the substep condition.
+    pub cfl: f32,
+    /// Cap on WCSPH steps per engine substep.
+    pub max_substeps: usize,
+}
+
+impl Default for WcsphParams {
+    fn default() -> Self {
+        Self {
+            // A fall across the box reaches √(2gH) ≈ 3400 px/s, so a splash
+            // stays below Mach ~0.3, and a 500 px deep pool compresses by
+            // g·d/c² ≈ 2.4% at the floor. ~4 steps per engine substep at
+            // rest.
+            sound_speed: 1.0e4,
+            // At 0.05 a dropped blob is still boiling after 3 s; 0.1 brings it
+            // to rest, calmer than DFSPH, as artificial viscosity damps.
+            viscosity: 0.1,
+            cfl: 0.4,
+            max_substeps: 32,
+        }
+    }
+}
+
+/// Weakly-compressible SPH solver state: velocities, plus the density and
+/// pressure of the current WCSPH step.
+pub struct Wcsph {
+    grid: SphGrid,
+    kernel: SphKernel,
+    rest_density: f32,
+    params: WcsphParams,
+    vel: Vec<Vec2>,
+    density: Vec<f32>,
+    pressure: Vec<f32>, // p_i/ρ_i², the form the force sums
+    accel: Vec<Vec2>,
+    phases: Vec<Phase>,
+    inv_mass: Vec<f32>, // w_i = 1/m_i from the particle's phase
+}
+
+impl Default for Wcsph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wcsph {
+    pub fn new() -> Self {
+        let kernel = SphKernel::new(WCSPH_H);
+        Self {
+            grid: SphGrid::new(WCSPH_H, &Domain::default()),
+            rest_density: kernel.rest_density(WCSPH_REST_SPACING),
+            kernel,
+            params: WcsphParams::default(),
+            vel: Vec::new(),
+            density: Vec::new(),
+            pressure: Vec::new(),
+            accel: Vec::new(),
+            phases: Vec::new(),
+            inv_mass: Vec::new(),
+        }
+    }
+
+    pub fn set_params(&mut self, params: WcsphParams) {
+        self.params = params;
+    }
+
+    /// ρ_i (incl. self term) and the Tait pressure, stored as p_i/ρ_i².
+    fn compute_density_pressure(&mut self, x: &[Vec2]) {
+        let grid = &self.grid;
+        let kernel = &self.kernel;
+        let rho0 = self.rest_density;
+        let c = self.params.sound_speed;
+        let stiffness = rho0 * c * c / TAIT_GAMMA as f32; // B
+        self.density
+            .par_iter_mut()
+            .zip(self.pressure.par_iter_mut())
+            .enumerate()
+            .for_each(|(i, (rho_i, p_i))| {
+                let xi = x[i];
+                let mut rho = 0.0f32;
+                grid.for_neighbors(xi, |j, o| {
+                    rho += kernel.w((xi - x[j] - o).length_squared());
+                });
+                *rho_i = rho;
+                let p = stiffness * ((rho / rho0).powi(TAIT_GAMMA) - 1.0);
+                *p_i = p.max(0.0) / (rho * rho);
+            });
+    }
+
+    /// Pressure and artificial-viscosity accelerations plus gravity.
+    fn compute_accel(&mut self, x: &[Vec2], gravity: Vec2) {
+        let grid = &self.grid;
+        let kernel = &self.kernel;
+        let vel = &self.vel;
+        let density = &self.density;
+        let pressure = &self.pressure;
+        let inv_mass = &self.inv_mass;
+        let h = kernel.h();
+        let eps = 0.01 * h * h;
+        // 2αhc, the numerator of Π_ij's ν.
+        let nu = 2.0 * self.params.viscosity * h * self.params.sound_speed;
+        self.accel.par_iter_mut().enumerate().for_each(|(i, a)| {
+            let xi = x[i];
+            let vi = vel[i];
+            let mut acc = Vec2::ZERO;
+            grid.for_neighbors(xi, |j, o| {
+                if j == i {
+                    return;
+                }
+                let d = xi - x[j] - o;
+                let mut k = pressure[i] + pressure[j];
+                let vd = (vi - vel[j]).dot(d);
+                if vd < 0.0 {
+                    k -= nu / (density[i] + density[j]) * vd / (d.length_squared() + eps);
+                }
+                acc += kernel.grad(d) * k;
+            });
+            *a = gravity - acc * inv_mass[i];
+        });
+    }
+}
+
+impl FluidSolver for Wcsph {
+    fn name(&self) -> &'static str {
+        "WCSPH"
+    }
+
+    fn set_wcsph_params(&mut self, params: WcsphParams) {
+        self.set_params(params);
+    }
+
+    fn set_phases(&mut self, phases: &[Phase]) {
+        self.phases = phases.to_vec();
+    }
+
+    fn retain(&mut self, keep: &[bool]) {
+        retain_flagged(&mut self.vel, keep);
+    }
+
+    fn substep(
+        &mut self,
+        dt: f32,
+        gravity: Vec2,
+        share: &mut ShareData,
+        c_opos: &mut Vec<Vec2>,
+        bounds: &mut Boundaries,
+    ) {
+        let n = share.c_pos.len();
+        if n == 0 {
+            return;
+        }
+        c_opos.resize(n, Vec2::ZERO);
+        fit_particles(
+            &mut self.vel,
+            &share.c_pos,
+            c_opos,
+            dt,
+            &mut [&mut self.density, &mut self.pressure, &mut self.accel],
+        );
+        inv_masses(&self.phases, &share.c_phase, n, &mut self.inv_mass);
+
+        let accel = effective_gravity(gravity);
+
+        // The CFL split, from the fastest particle entering the substep.
+        let vmax = self
+            .vel
+            .iter()
+            .map(|v| v.length_squared())
+            .fold(0.0f32, f32::max)
+            .sqrt();
+        let step = self.params.cfl * WCSPH_H / (self.params.sound_speed + vmax);
+        let substeps = ((dt / step).ceil() as usize).clamp(1, self.params.max_substeps);
+        let tau = dt / substeps as f32;
+
+        // The box, as DFSPH's.
+        let walls = WallBox::new(bounds.domain());
+        self.grid.fit(bounds.domain());
+
+        for _ in 0..substeps {
+            self.grid.build(&share.c_pos);
+            self.compute_density_pressure(&share.c_pos);
+            self.compute_accel(&share.c_pos, accel);
+
+            // Symplectic Euler, then project onto the box (killing the
+            // into-wall velocity) and out of the obstacles; the CFL split
+            // keeps the velocity in check, so it goes uncapped.
+            for (v, a) in self.vel.iter_mut().zip(&self.accel) {
+                *v += *a * tau;
+            }
+            advect(
+                &mut share.c_pos,
+                &mut self.vel,
+                tau,
+                f32::INFINITY,
+                &walls,
+                bounds,
+            );
+        }
+
+        // Shared/rendered bookkeeping and diagnostics, as DFSPH.
+        let (density, rho0) = (&self.density, self.rest_density);
+        let ratio = sampled_density_ratio(n, |i| density[i] / rho0);
+        report(share, c_opos, &self.vel, dt, ratio);
+        share.perf_stats.sph_substeps = substeps;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{HEIGHT, WIDTH};
+    use crate::physics::tests::block_scene;
+    use crate::physics::{Strategy, GRAVITY, PHYS_TIME_STEP};
+
+    /// A WCSPH blob dropped in the box must stay finite, stay inside the
+    /// walls and settle toward rest, with the splash raising the CFL step
+    /// count but never to the cap.
+    #[test]
+    fn wcsph_blob_is_stable() {
+        let (mut physics, mut share) = block_scene(
+            Strategy::Wcsph,
+            Domain::default(),
+            Vec2::new(500.0, 300.0),
+            (30, 30),
+            Vec2::ZERO,
+        );
+        let (mut fewest, mut most) = (usize::MAX, 0);
+        for _ in 0..1500 {
+            physics.step(PHYS_TIME_STEP, &mut share);
+            fewest = fewest.min(share.perf_stats.sph_substeps);
+            most = most.max(share.perf_stats.sph_substeps);
+        }
+        let nan = share
+            .c_pos
+            .iter()
+            .filter(|p| !p.x.is_finite() || !p.y.is_finite())
+            .count();
+        let escaped = share
+            .c_pos
+            .iter()
+            .filter(|p| p.x < -1.0 || p.x > WIDTH + 1.0 || p.y < -1.0 || p.y > HEIGHT + 1.0)
+            .count();
+        assert_eq!(nan, 0, "WCSPH produced NaNs");
+        assert_eq!(escaped, 0, "WCSPH particles escaped the box");
+        // The same bar as DFSPH's blob: a blown-up run pins the speed metric
+        // near 117, a settled one is well under 15.
+        assert!(
+            share.perf_stats.mean_speed < 15.0,
+            "WCSPH did not settle: mean_speed = {}",
+            share.perf_stats.mean_speed
+        );
+        assert!(
+            fewest > 1 && most > fewest && most < WcsphParams::default().max_substeps,
+            "CFL steps ranged {fewest}..={most}"
+        );
+    }
+
+    /// A settled column is hydrostatic: the Tait equation of state puts the
+    /// density at depth d at ρ0 (1 + γ g d/c²)^(1/γ). Checked band by band
+    /// away from the surface and the walls, where the kernel is unclipped,
+    /// at a soft c = 5000 so the compression is several percent.
+    #[test]
+    fn column_matches_tait_hydrostatics() {
+        let s = WCSPH_REST_SPACING;
+        let (w, h) = (240.0, 480.0);
+        let c = 5000.0;
+        let (mut physics, mut share) = block_scene(
+            Strategy::Wcsph,
+            Domain::new(w, h),
+            Vec2::new(6.0, h - 3.0 - 69.0 * s),
+            (39, 70),
+            Vec2::ZERO,
+        );
+        physics.set_wcsph_params(WcsphParams {
+            sound_speed: c,
+            ..Default::default()
+        });
+        // The column rings acoustically and repacks off its square lattice
+        // for a while; by ~8 s it is within a few percent of Tait.
+        for _ in 0..4000 {
+            physics.step(PHYS_TIME_STEP, &mut share);
+        }
+
+        let x = &share.c_pos;
+        let kernel = SphKernel::new(WCSPH_H);
+        let rho0 = kernel.rest_density(s);
+        let mut grid = SphGrid::new(WCSPH_H, &Domain::new(w, h));
+        grid.build(x);
+        let top = x.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let g = GRAVITY.y / PHYS_TIME_STEP;
+        let gamma = TAIT_GAMMA as f32;
+        const BAND: f32 = 50.0;
+        // Depth bands 2..7: below the surface layer, above the floor's.
+        let mut bands = [(0.0f32, 0usize); 7];
+        for &p in x {
+            if p.x < 2.0 * WCSPH_H || p.x > w - 2.0 * WCSPH_H {
+                continue;
+            }
+            let b = ((p.y - top) / BAND) as usize;
+            if !(2..bands.len()).contains(&b) {
+                continue;
+            }
+            let mut rho = 0.0;
+            grid.for_neighbors(p, |j, o| rho += kernel.w((p - x[j] - o).length_squared()));
+            bands[b].0 += rho / rho0;
+            bands[b].1 += 1;
+        }
+        for (b, &(sum, count)) in bands.iter().enumerate().skip(2) {
+            let depth = (b as f32 + 0.5) * BAND;
+            let want = (1.0 + gamma * g * depth / (c * c)).powf(1.0 / gamma);
+            let got = sum / count as f32;
+            assert!(
+                count > 50 && (got - want).abs() < 0.15 * (want - 1.0),
+                "depth {depth}: rho/rho0 = {got}, Tait predicts {want} ({count} particles)"
+            );
+        }
+    }
+}